@@ -1,18 +1,195 @@
+use crate::*;
 use envstruct::prelude::*;
 use eyre::Context;
+use node::TokenUsage;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+pub type ProvidersConfig = BTreeMap<String, ProviderConfig>;
+pub type PriceTable = BTreeMap<String, ModelPrice>;
 
 #[derive(EnvStruct)]
 pub struct Config {
-    pub ai_api_token: String,
-    pub ai_api_url: String,
+    /// Url of the OpenAI compatible API registered as the `default` provider.
+    pub ai_api_url: Option<String>,
+    pub ai_api_token: Option<String>,
+    #[env(default = "gpt-4o-mini")]
+    pub ai_model: String,
+    /// Embedding model of the `default` provider.
+    #[env(default = "text-embedding-3-small")]
+    pub ai_embedding_model: String,
+    /// Additional named providers as a JSON object, e.g.
+    /// `{"local": {"kind": "ollama", "model": "llama3.2"}}`.
+    #[env(with = WithJson::<ProvidersConfig>, default = "{}")]
+    pub providers: ProvidersConfig,
+    /// Provider used by LLM nodes which do not specify one.
+    #[env(default = "default")]
+    pub default_provider: String,
+    /// Prices per model as a JSON object, e.g. `{"gpt-4o-mini": {"prompt": 0.15, "completion": 0.6}}`.
+    #[env(with = WithJson::<PriceTable>, default = "{}")]
+    pub prices: PriceTable,
+    /// Rate limit of the `default` provider as a JSON object, e.g.
+    /// `{"requests_per_minute": 500, "tokens_per_minute": 200000, "max_in_flight": 8}`.
+    #[env(with = WithJson::<RateLimitConfig>, default = "{}")]
+    pub ai_rate_limit: RateLimitConfig,
+    /// `live`, `record` or `replay`, see [`CacheMode`].
+    #[env(default = "live")]
+    pub llm_cache_mode: CacheMode,
+    /// Directory with recorded LLM responses.
+    #[env(default = "fixtures/llm")]
+    pub llm_cache_dir: String,
+    /// Root directory of file system nodes, their paths are relative to it.
+    #[env(default = ".")]
+    pub sandbox_root: String,
+    /// Comma separated programs the command node may run, nothing is allowed by default.
+    #[env(default = "")]
+    pub command_allowlist: Vec<String>,
+    /// Timeout of commands in seconds.
+    #[env(default = "30")]
+    pub command_timeout: Duration,
+    /// Max size of stdout and stderr of commands, the rest is dropped.
+    #[env(default = "1048576")]
+    pub command_max_output_bytes: usize,
+    /// Comma separated environment variables passed to commands, all others are removed.
+    #[env(default = "PATH,LANG")]
+    pub command_env: Vec<String>,
+    /// Comma separated hosts the HTTP node may call, `*.example.com` allows subdomains.
+    #[env(default = "")]
+    pub http_allowed_hosts: Vec<String>,
+    /// Timeout of HTTP requests in seconds.
+    #[env(default = "30")]
+    pub http_timeout: Duration,
+    #[env(default = "10485760")]
+    pub http_max_response_bytes: usize,
+
+    /// Address of the `serve` mode API.
+    #[env(default = "127.0.0.1:8080")]
+    pub server_addr: String,
+    /// Bearer token required by the `serve` mode API, the server does not start without it.
+    pub server_token: Option<String>,
+    /// Serve metrics in the Prometheus text format at `/metrics` of the `serve` mode API.
+    #[env(default = "false")]
+    pub server_metrics: bool,
+}
+
+/// Price of the model in USD per million tokens.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize)]
+pub struct ModelPrice {
+    pub prompt: f64,
+    pub completion: f64,
+}
+
+impl ModelPrice {
+    pub fn cost(&self, usage: &TokenUsage) -> f64 {
+        const TOKENS: f64 = 1_000_000.0;
+
+        (usage.prompt_tokens as f64 * self.prompt
+            + usage.completion_tokens as f64 * self.completion)
+            / TOKENS
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, derive_more::Display)]
+#[serde(rename_all = "lowercase")]
+pub enum ProviderKind {
+    /// OpenAI compatible chat completions API
+    #[display("openai")]
+    OpenAi,
+    /// Anthropic messages API
+    #[display("anthropic")]
+    Anthropic,
+    /// Ollama local HTTP API
+    #[display("ollama")]
+    Ollama,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ProviderConfig {
+    pub kind: ProviderKind,
+    /// Base url of the API, defaults to the public endpoint of the provider.
+    #[serde(default)]
+    pub api_url: Option<String>,
+    #[serde(default)]
+    pub api_token: Option<String>,
+    pub model: String,
+    #[serde(default)]
+    pub rate_limit: RateLimitConfig,
+}
+
+/// Client side limits of calls to a provider, only retries of rejected calls are enabled by
+/// default.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct RateLimitConfig {
+    pub requests_per_minute: Option<u32>,
+    /// Prompt tokens are estimated before the call, the difference with the reported usage is
+    /// accounted after it.
+    pub tokens_per_minute: Option<u32>,
+    /// Max amount of concurrent calls.
+    pub max_in_flight: Option<usize>,
+    /// Retries of calls rejected by the provider with `429 Too Many Requests`.
+    pub max_retries: u32,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            requests_per_minute: None,
+            tokens_per_minute: None,
+            max_in_flight: None,
+            max_retries: 3,
+        }
+    }
+}
+
+impl RateLimitConfig {
+    pub fn is_unlimited(&self) -> bool {
+        self.requests_per_minute.is_none()
+            && self.tokens_per_minute.is_none()
+            && self.max_in_flight.is_none()
+            && self.max_retries == 0
+    }
 }
 
 impl Config {
     pub fn init() -> eyre::Result<Self> {
         if dotenvy::dotenv().is_ok() {
-            println!("Loaded .env file");
+            eprintln!("Loaded .env file");
         }
 
         Self::with_prefix("AGENT").wrap_err("failed to load config")
     }
+
+    /// All configured providers including the `default` one defined by `AI_*` variables.
+    pub fn all_providers(&self) -> ProvidersConfig {
+        let mut providers = self.providers.clone();
+
+        if let Some(api_url) = &self.ai_api_url {
+            providers
+                .entry("default".to_string())
+                .or_insert_with(|| ProviderConfig {
+                    kind: ProviderKind::OpenAi,
+                    api_url: Some(api_url.clone()),
+                    api_token: self.ai_api_token.clone(),
+                    model: self.ai_model.clone(),
+                    rate_limit: self.ai_rate_limit.clone(),
+                });
+        }
+
+        providers
+    }
+
+    /// OpenAI compatible embeddings API defined by `AI_*` variables.
+    pub fn embeddings_provider(&self) -> Option<ProviderConfig> {
+        let api_url = self.ai_api_url.as_ref()?;
+
+        Some(ProviderConfig {
+            kind: ProviderKind::OpenAi,
+            api_url: Some(api_url.clone()),
+            api_token: self.ai_api_token.clone(),
+            model: self.ai_embedding_model.clone(),
+            rate_limit: self.ai_rate_limit.clone(),
+        })
+    }
 }
@@ -0,0 +1,135 @@
+use node::*;
+use std::collections::BTreeMap;
+use std::io::Write;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt};
+
+const HELP: &str = "\
+Commands:
+  inputs, i                    print the input values
+  memory, m                    print the memory values
+  set input NAME VALUE         change an input value for this execution
+  set memory NAME VALUE        change a memory value for this execution
+  step, s                      run the instance and pause before the next one
+  continue, c                  run until the next breakpoint
+  abort, q                     stop the run
+  help, h                      print this help
+Values are parsed as JSON, other text is used as a string.";
+
+/// Interactive front-end of a debug run, reads commands at every pause.
+///
+/// The end of the input continues the run without pausing.
+pub struct DebugConsole<R, W> {
+    input: R,
+    output: W,
+}
+
+impl<R: AsyncBufRead + Unpin, W: Write> DebugConsole<R, W> {
+    pub fn new(input: R, output: W) -> Self {
+        Self { input, output }
+    }
+
+    /// Handle the pauses until the run finished.
+    pub async fn run(mut self, mut session: DebugSession) -> eyre::Result<()> {
+        while let Some(mut pause) = session.next_pause().await {
+            writeln!(
+                self.output,
+                "Paused before {} ({})",
+                pause.instance_id, pause.node_id
+            )?;
+            print_values(&mut self.output, "Inputs", &pause.inputs)?;
+
+            let action = self.read_action(&mut pause).await?;
+            pause.resume(action);
+        }
+
+        Ok(())
+    }
+
+    async fn read_action(&mut self, pause: &mut DebugPause) -> eyre::Result<DebugAction> {
+        let mut line = String::new();
+
+        loop {
+            write!(self.output, "(debug {})> ", pause.instance_id)?;
+            self.output.flush()?;
+
+            line.clear();
+            if self.input.read_line(&mut line).await? == 0 {
+                writeln!(self.output)?;
+                return Ok(DebugAction::Continue);
+            }
+
+            let mut words = line.split_whitespace();
+            match words.next().unwrap_or_default() {
+                "" => {}
+                "inputs" | "i" => print_values(&mut self.output, "Inputs", &pause.inputs)?,
+                "memory" | "m" => print_values(&mut self.output, "Memory", &pause.memory)?,
+                "set" => {
+                    let values = match words.next() {
+                        Some("input") => &mut pause.inputs,
+                        Some("memory") => &mut pause.memory,
+                        _ => {
+                            writeln!(self.output, "Usage: set input|memory NAME VALUE")?;
+                            continue;
+                        }
+                    };
+                    // the value is the rest of the line, it may contain spaces
+                    let (Some(name), Some(value)) = (words.next(), skip_words(&line, 3)) else {
+                        writeln!(self.output, "Usage: set input|memory NAME VALUE")?;
+                        continue;
+                    };
+
+                    values.insert(name.to_string(), parse_value(value));
+                }
+                "step" | "s" => return Ok(DebugAction::Step),
+                "continue" | "c" => return Ok(DebugAction::Continue),
+                "abort" | "q" => return Ok(DebugAction::Abort),
+                "help" | "h" => writeln!(self.output, "{HELP}")?,
+                command => writeln!(self.output, "Unknown command {command:?}, try help")?,
+            }
+        }
+    }
+}
+
+fn print_values(
+    output: &mut impl Write,
+    title: &str,
+    values: &BTreeMap<String, Value>,
+) -> std::io::Result<()> {
+    if values.is_empty() {
+        return writeln!(output, "{title}: none");
+    }
+
+    writeln!(output, "{title}:")?;
+    for (name, value) in values {
+        writeln!(output, "  {name}{}", format_value(value))?;
+    }
+
+    Ok(())
+}
+
+/// Rest of the line after `count` whitespace separated words, `None` if nothing is left.
+pub(crate) fn skip_words(line: &str, count: usize) -> Option<&str> {
+    let mut rest = line.trim();
+    for _ in 0..count {
+        let end = rest.find(char::is_whitespace)?;
+        rest = rest[end..].trim_start();
+    }
+
+    Some(rest).filter(|rest| !rest.is_empty())
+}
+
+/// Parse a value typed in a console as JSON, other text is used as a string.
+pub(crate) fn parse_value(text: &str) -> Value {
+    let json =
+        serde_json::from_str(text).unwrap_or_else(|_| serde_json::Value::String(text.to_string()));
+
+    Value::from_json(json)
+}
+
+/// JSON of the value after ` = `, or the type name after `: ` if it is not serializable.
+pub(crate) fn format_value(value: &Value) -> String {
+    match value.to_json() {
+        Ok(json) => format!(" = {json}"),
+        Err(_) => format!(": {}", value.type_name()),
+    }
+}
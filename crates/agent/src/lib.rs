@@ -1,5 +1,19 @@
 mod config;
+mod debugger;
+mod llm;
 mod nodes;
+mod registry;
+mod repl;
+mod sandbox;
+mod server;
+mod telemetry;
 
 pub use config::*;
+pub use debugger::*;
+pub use llm::*;
 pub use nodes::*;
+pub use registry::*;
+pub use repl::*;
+pub use sandbox::*;
+pub use server::*;
+pub use telemetry::*;
@@ -0,0 +1,188 @@
+use crate::*;
+use eyre::ContextCompat;
+use node::*;
+use serde_json::json;
+
+/// Provider for Anthropic messages API.
+///
+/// JSON schema response format is implemented by forcing the model to call a tool with the
+/// schema as input, the tool input is returned as the reply text.
+pub struct AnthropicProvider {
+    http: reqwest::Client,
+    api_url: String,
+    api_token: Option<String>,
+    model: String,
+}
+
+impl AnthropicProvider {
+    pub const DEFAULT_API_URL: &str = "https://api.anthropic.com";
+    pub const API_VERSION: &str = "2023-06-01";
+
+    /// `max_tokens` is required by the API
+    const DEFAULT_MAX_TOKENS: u32 = 4096;
+
+    pub fn new(config: &ProviderConfig) -> Self {
+        let api_url = config.api_url.as_deref().unwrap_or(Self::DEFAULT_API_URL);
+
+        Self {
+            http: reqwest::Client::new(),
+            api_url: api_url.trim_end_matches('/').to_string(),
+            api_token: config.api_token.clone(),
+            model: config.model.clone(),
+        }
+    }
+
+    #[tracing::instrument(skip_all, fields(model))]
+    async fn complete_impl(&self, request: &LlmRequest) -> eyre::Result<LlmResponse> {
+        let model = request.model.as_deref().unwrap_or(&self.model);
+        tracing::Span::current().record("model", model);
+
+        let (system, messages): (Vec<_>, Vec<_>) = request
+            .conversation
+            .messages
+            .iter()
+            .partition(|message| message.role == ChatRole::System);
+
+        let mut body = json!({
+            "model": model,
+            "max_tokens": request.max_tokens.unwrap_or(Self::DEFAULT_MAX_TOKENS),
+            "messages": messages.into_iter().map(Self::encode_message).collect::<Vec<_>>(),
+        });
+
+        if !system.is_empty() {
+            body["system"] = json!(system
+                .iter()
+                .map(|message| message.text())
+                .collect::<Vec<_>>()
+                .join("\n\n"));
+        }
+        if let Some(temperature) = request.temperature {
+            body["temperature"] = json!(temperature);
+        }
+
+        let forced_tool = match &request.response_format {
+            Some(ResponseFormat::JsonSchema { name, schema }) => {
+                body["tools"] = json!([{
+                    "name": name,
+                    "description": "Respond with the data matching the input schema",
+                    "input_schema": schema,
+                }]);
+                body["tool_choice"] = json!({ "type": "tool", "name": name });
+                Some(name.as_str())
+            }
+            Some(ResponseFormat::Text) | None => None,
+        };
+
+        let mut http_request = self
+            .http
+            .post(format!("{}/v1/messages", self.api_url))
+            .header("anthropic-version", Self::API_VERSION)
+            .json(&body);
+        if let Some(api_token) = &self.api_token {
+            http_request = http_request.header("x-api-key", api_token);
+        }
+
+        let response = send_json(http_request).await?;
+
+        let usage = response.get("usage").map(|usage| {
+            TokenUsage::new(
+                usage["input_tokens"].as_u64().unwrap_or_default(),
+                usage["output_tokens"].as_u64().unwrap_or_default(),
+            )
+        });
+
+        Ok(LlmResponse {
+            message: Self::decode_message(&response, forced_tool)?,
+            model: response["model"].as_str().unwrap_or(model).to_string(),
+            usage,
+            retries: 0,
+        })
+    }
+
+    fn encode_message(message: &ChatMessage) -> serde_json::Value {
+        let mut content = Vec::new();
+
+        if let Some(tool_call_id) = &message.tool_call_id {
+            content.push(json!({
+                "type": "tool_result",
+                "tool_use_id": tool_call_id,
+                "content": message.text(),
+            }));
+        } else {
+            for part in &message.content {
+                content.push(match part {
+                    ContentPart::Text { text } => json!({ "type": "text", "text": text }),
+                    ContentPart::ImageUrl { url } => json!({
+                        "type": "image",
+                        "source": { "type": "url", "url": url },
+                    }),
+                });
+            }
+        }
+
+        for call in &message.tool_calls {
+            content.push(json!({
+                "type": "tool_use",
+                "id": call.id,
+                "name": call.name,
+                "input": call.arguments,
+            }));
+        }
+
+        // tool results are sent by the user
+        let role = match message.role {
+            ChatRole::Assistant => "assistant",
+            ChatRole::System | ChatRole::User | ChatRole::Tool => "user",
+        };
+
+        json!({ "role": role, "content": content })
+    }
+
+    fn decode_message(
+        response: &serde_json::Value,
+        forced_tool: Option<&str>,
+    ) -> eyre::Result<ChatMessage> {
+        let blocks = response["content"]
+            .as_array()
+            .context("LLM response has no content")?;
+
+        let mut text = Vec::new();
+        let mut tool_calls = Vec::new();
+
+        for block in blocks {
+            match block["type"].as_str() {
+                Some("text") => text.push(block["text"].as_str().unwrap_or_default().to_string()),
+                Some("tool_use") => {
+                    let name = block["name"].as_str().context("Tool use without name")?;
+
+                    if forced_tool == Some(name) {
+                        text.push(block["input"].to_string());
+                        continue;
+                    }
+
+                    tool_calls.push(ToolCall {
+                        id: block["id"].as_str().unwrap_or_default().to_string(),
+                        name: name.to_string(),
+                        arguments: block["input"].clone(),
+                    });
+                }
+                _ => {}
+            }
+        }
+
+        let mut message = ChatMessage::assistant(text.join("\n"));
+        message.tool_calls = tool_calls;
+
+        Ok(message)
+    }
+}
+
+impl LlmProvider for AnthropicProvider {
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn complete<'a>(&'a self, request: &'a LlmRequest) -> LlmResult<'a> {
+        Box::pin(self.complete_impl(request))
+    }
+}
@@ -0,0 +1,182 @@
+use crate::*;
+use envstruct::prelude::*;
+use eyre::WrapErr;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, derive_more::Display,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum CacheMode {
+    /// Always call the provider, fixtures are not used.
+    #[default]
+    #[display("live")]
+    Live,
+    /// Call the provider and write responses to the fixture directory.
+    #[display("record")]
+    Record,
+    /// Serve responses from the fixture directory only, failing on a miss.
+    #[display("replay")]
+    Replay,
+}
+
+impl std::str::FromStr for CacheMode {
+    type Err = eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "live" => Ok(Self::Live),
+            "record" => Ok(Self::Record),
+            "replay" => Ok(Self::Replay),
+            _ => Err(eyre::eyre!("Unknown LLM cache mode: {s:?}")),
+        }
+    }
+}
+
+impl EnvParsePrimitive for CacheMode {
+    fn parse(val: &str) -> Result<Self, BoxError> {
+        Ok(val.parse::<Self>().map_err(|err| err.to_string())?)
+    }
+}
+
+/// Fixture file with the request it was recorded for, kept for readability of diffs.
+#[derive(Serialize, Deserialize)]
+struct Fixture {
+    provider: String,
+    model: String,
+    request: LlmRequest,
+    response: LlmResponse,
+}
+
+/// Provider wrapper recording responses to fixtures or replaying them, see [`CacheMode`].
+///
+/// Fixtures are keyed on the provider name, the resolved model (the request override or the
+/// provider default) and the full request (messages and params), so any change of either is a
+/// cache miss.
+pub struct CachedProvider {
+    inner: Arc<dyn LlmProvider>,
+    name: String,
+    mode: CacheMode,
+    dir: PathBuf,
+}
+
+impl CachedProvider {
+    pub fn new(
+        name: impl Into<String>,
+        inner: Arc<dyn LlmProvider>,
+        mode: CacheMode,
+        dir: impl Into<PathBuf>,
+    ) -> Self {
+        Self {
+            inner,
+            name: name.into(),
+            mode,
+            dir: dir.into(),
+        }
+    }
+
+    /// Model the request is completed with.
+    fn resolved_model<'a>(&'a self, request: &'a LlmRequest) -> &'a str {
+        request
+            .model
+            .as_deref()
+            .unwrap_or_else(|| self.inner.model())
+    }
+
+    /// Path of the fixture for the request.
+    pub fn fixture_path(&self, request: &LlmRequest) -> eyre::Result<PathBuf> {
+        let model = self.resolved_model(request);
+        let request = serde_json::to_vec(request).wrap_err("Failed to serialize LLM request")?;
+
+        let mut hasher = Sha256::new();
+        hasher.update(self.name.as_bytes());
+        hasher.update([0]);
+        hasher.update(model.as_bytes());
+        hasher.update([0]);
+        hasher.update(&request);
+        let key = hasher
+            .finalize()
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect::<String>();
+
+        Ok(self.dir.join(format!("{}-{}.json", self.name, &key[..16])))
+    }
+
+    async fn replay(&self, path: &Path) -> eyre::Result<LlmResponse> {
+        let fixture = tokio::fs::read(path).await.wrap_err_with(|| {
+            format!(
+                "LLM fixture {} not found for provider {:?}, record it with the record cache mode",
+                path.display(),
+                self.name
+            )
+        })?;
+        let fixture: Fixture = serde_json::from_slice(&fixture)
+            .wrap_err_with(|| format!("Failed to parse LLM fixture {}", path.display()))?;
+
+        Ok(fixture.response)
+    }
+
+    async fn record(
+        &self,
+        path: &Path,
+        request: &LlmRequest,
+        response: &LlmResponse,
+    ) -> eyre::Result<()> {
+        let fixture = Fixture {
+            provider: self.name.clone(),
+            model: self.resolved_model(request).to_string(),
+            request: request.clone(),
+            response: response.clone(),
+        };
+        let fixture = serde_json::to_vec_pretty(&fixture)?;
+
+        tokio::fs::create_dir_all(&self.dir)
+            .await
+            .wrap_err_with(|| format!("Failed to create fixture dir {}", self.dir.display()))?;
+        tokio::fs::write(path, fixture)
+            .await
+            .wrap_err_with(|| format!("Failed to write LLM fixture {}", path.display()))
+    }
+}
+
+impl LlmProvider for CachedProvider {
+    fn model(&self) -> &str {
+        self.inner.model()
+    }
+
+    fn complete<'a>(&'a self, request: &'a LlmRequest) -> LlmResult<'a> {
+        Box::pin(async move {
+            match self.mode {
+                CacheMode::Live => self.inner.complete(request).await,
+                CacheMode::Replay => {
+                    let path = self.fixture_path(request)?;
+                    tracing::debug!(path = %path.display(), "Replaying LLM response");
+
+                    let result = self.replay(&path).await;
+                    let metric = match result {
+                        Ok(_) => METRIC_LLM_CACHE_HITS,
+                        Err(_) => METRIC_LLM_CACHE_MISSES,
+                    };
+                    metrics::counter!(metric, "provider" => self.name.clone()).increment(1);
+
+                    result
+                }
+                CacheMode::Record => {
+                    let path = self.fixture_path(request)?;
+                    metrics::counter!(METRIC_LLM_CACHE_MISSES, "provider" => self.name.clone())
+                        .increment(1);
+                    let response = self.inner.complete(request).await?;
+
+                    tracing::debug!(path = %path.display(), "Recording LLM response");
+                    self.record(&path, request, &response).await?;
+
+                    Ok(response)
+                }
+            }
+        })
+    }
+}
@@ -0,0 +1,122 @@
+use crate::*;
+use eyre::ContextCompat;
+use node::*;
+use serde_json::json;
+use std::future::Future;
+use std::pin::Pin;
+
+pub type EmbeddingResult<'a> = Pin<Box<dyn Future<Output = eyre::Result<Embeddings>> + Send + 'a>>;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Embeddings {
+    /// One vector per input text, in the order of the inputs.
+    pub vectors: Vec<Vec<f32>>,
+    pub model: String,
+    /// Usage reported by the provider.
+    pub usage: Option<TokenUsage>,
+}
+
+impl Embeddings {
+    /// Usage reported by the provider or estimated locally from the texts.
+    pub fn usage_or_estimate(&self, texts: &[String]) -> TokenUsage {
+        self.usage.unwrap_or_else(|| TokenUsage {
+            estimated: true,
+            ..TokenUsage::new(
+                texts.iter().map(|text| estimate_tokens(text) as u64).sum(),
+                0,
+            )
+        })
+    }
+}
+
+/// Backend able to embed texts.
+pub trait EmbeddingProvider: Send + Sync + 'static {
+    fn embed<'a>(&'a self, texts: &'a [String]) -> EmbeddingResult<'a>;
+}
+
+/// Client of OpenAI compatible `/embeddings` API.
+pub struct OpenAiEmbeddings {
+    http: reqwest::Client,
+    api_url: String,
+    api_token: Option<String>,
+    model: String,
+}
+
+impl OpenAiEmbeddings {
+    pub fn new(config: &ProviderConfig) -> Self {
+        let api_url = config
+            .api_url
+            .as_deref()
+            .unwrap_or(OpenAiProvider::DEFAULT_API_URL);
+
+        Self {
+            http: reqwest::Client::new(),
+            api_url: api_url.trim_end_matches('/').to_string(),
+            api_token: config.api_token.clone(),
+            model: config.model.clone(),
+        }
+    }
+
+    #[tracing::instrument(skip_all, fields(model = self.model, texts = texts.len()))]
+    async fn embed_impl(&self, texts: &[String]) -> eyre::Result<Embeddings> {
+        let mut http_request = self
+            .http
+            .post(format!("{}/embeddings", self.api_url))
+            .json(&json!({ "model": self.model, "input": texts }));
+        if let Some(api_token) = &self.api_token {
+            http_request = http_request.bearer_auth(api_token);
+        }
+
+        let response = send_json(http_request).await?;
+
+        let mut data = response["data"]
+            .as_array()
+            .context("Embeddings response has no data")?
+            .iter()
+            .enumerate()
+            .map(|(position, item)| {
+                let index = item["index"]
+                    .as_u64()
+                    .map_or(position, |index| index as usize);
+                let vector = item["embedding"]
+                    .as_array()
+                    .context("Embeddings response item has no embedding")?
+                    .iter()
+                    .map(|value| value.as_f64().map(|value| value as f32))
+                    .collect::<Option<Vec<_>>>()
+                    .context("Embedding is not a list of numbers")?;
+
+                Ok((index, vector))
+            })
+            .collect::<eyre::Result<Vec<_>>>()?;
+        data.sort_by_key(|(index, _)| *index);
+
+        if data.len() != texts.len() {
+            return Err(eyre::eyre!(
+                "Expected {} embeddings, got {}",
+                texts.len(),
+                data.len()
+            ));
+        }
+
+        let usage = response
+            .get("usage")
+            .and_then(|usage| usage["prompt_tokens"].as_u64())
+            .map(|prompt_tokens| TokenUsage::new(prompt_tokens, 0));
+
+        Ok(Embeddings {
+            vectors: data.into_iter().map(|(_, vector)| vector).collect(),
+            model: response["model"]
+                .as_str()
+                .unwrap_or(&self.model)
+                .to_string(),
+            usage,
+        })
+    }
+}
+
+impl EmbeddingProvider for OpenAiEmbeddings {
+    fn embed<'a>(&'a self, texts: &'a [String]) -> EmbeddingResult<'a> {
+        Box::pin(self.embed_impl(texts))
+    }
+}
@@ -0,0 +1,17 @@
+mod anthropic;
+mod cache;
+mod embeddings;
+mod ollama;
+mod openai;
+mod provider;
+mod rate_limit;
+mod request;
+
+pub use anthropic::*;
+pub use cache::*;
+pub use embeddings::*;
+pub use ollama::*;
+pub use openai::*;
+pub use provider::*;
+pub use rate_limit::*;
+pub use request::*;
@@ -0,0 +1,153 @@
+use crate::*;
+use eyre::ContextCompat;
+use node::*;
+use serde_json::json;
+
+/// Provider for Ollama local HTTP API.
+pub struct OllamaProvider {
+    http: reqwest::Client,
+    api_url: String,
+    model: String,
+}
+
+impl OllamaProvider {
+    pub const DEFAULT_API_URL: &str = "http://localhost:11434";
+
+    pub fn new(config: &ProviderConfig) -> Self {
+        let api_url = config.api_url.as_deref().unwrap_or(Self::DEFAULT_API_URL);
+
+        Self {
+            http: reqwest::Client::new(),
+            api_url: api_url.trim_end_matches('/').to_string(),
+            model: config.model.clone(),
+        }
+    }
+
+    #[tracing::instrument(skip_all, fields(model))]
+    async fn complete_impl(&self, request: &LlmRequest) -> eyre::Result<LlmResponse> {
+        let model = request.model.as_deref().unwrap_or(&self.model);
+        tracing::Span::current().record("model", model);
+
+        let mut options = serde_json::Map::new();
+        if let Some(temperature) = request.temperature {
+            options.insert("temperature".to_string(), json!(temperature));
+        }
+        if let Some(max_tokens) = request.max_tokens {
+            options.insert("num_predict".to_string(), json!(max_tokens));
+        }
+
+        let mut body = json!({
+            "model": model,
+            "stream": false,
+            "messages": request
+                .conversation
+                .messages
+                .iter()
+                .map(Self::encode_message)
+                .collect::<eyre::Result<Vec<_>>>()?,
+            "options": options,
+        });
+
+        if let Some(ResponseFormat::JsonSchema { schema, .. }) = &request.response_format {
+            body["format"] = schema.clone();
+        }
+
+        let http_request = self
+            .http
+            .post(format!("{}/api/chat", self.api_url))
+            .json(&body);
+
+        let response = send_json(http_request).await?;
+
+        let message = response
+            .get("message")
+            .context("LLM response has no message")?;
+
+        // counts are missing when the prompt was cached by ollama
+        let usage = match (
+            response["prompt_eval_count"].as_u64(),
+            response["eval_count"].as_u64(),
+        ) {
+            (Some(prompt_tokens), Some(completion_tokens)) => {
+                Some(TokenUsage::new(prompt_tokens, completion_tokens))
+            }
+            _ => None,
+        };
+
+        Ok(LlmResponse {
+            message: Self::decode_message(message)?,
+            model: model.to_string(),
+            usage,
+            retries: 0,
+        })
+    }
+
+    fn encode_message(message: &ChatMessage) -> eyre::Result<serde_json::Value> {
+        let images = message
+            .content
+            .iter()
+            .filter_map(|part| match part {
+                ContentPart::ImageUrl { url } => Some(Self::encode_image(url)),
+                ContentPart::Text { .. } => None,
+            })
+            .collect::<eyre::Result<Vec<_>>>()?;
+
+        let mut encoded = json!({ "role": message.role, "content": message.text() });
+
+        if !images.is_empty() {
+            encoded["images"] = json!(images);
+        }
+        if !message.tool_calls.is_empty() {
+            encoded["tool_calls"] = message
+                .tool_calls
+                .iter()
+                .map(|call| json!({ "function": { "name": call.name, "arguments": call.arguments } }))
+                .collect();
+        }
+
+        Ok(encoded)
+    }
+
+    /// Ollama accepts only base64 images, so only `data:<type>;base64,<data>` URLs are supported.
+    fn encode_image(url: &str) -> eyre::Result<&str> {
+        url.strip_prefix("data:")
+            .and_then(|rest| rest.split_once(";base64,"))
+            .map(|(_, data)| data)
+            .with_context(|| {
+                format!("Ollama provider supports only base64 data URL images, got {url:?}")
+            })
+    }
+
+    fn decode_message(message: &serde_json::Value) -> eyre::Result<ChatMessage> {
+        let mut decoded = ChatMessage::assistant(message["content"].as_str().unwrap_or_default());
+
+        for (index, call) in message["tool_calls"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .enumerate()
+        {
+            decoded = decoded.with_tool_call(ToolCall {
+                // ollama does not assign ids to tool calls
+                id: format!("call_{index}"),
+                name: call["function"]["name"]
+                    .as_str()
+                    .context("Tool call without function name")?
+                    .to_string(),
+                arguments: call["function"]["arguments"].clone(),
+            });
+        }
+
+        Ok(decoded)
+    }
+}
+
+impl LlmProvider for OllamaProvider {
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn complete<'a>(&'a self, request: &'a LlmRequest) -> LlmResult<'a> {
+        Box::pin(self.complete_impl(request))
+    }
+}
@@ -0,0 +1,161 @@
+use crate::*;
+use eyre::ContextCompat;
+use node::*;
+use serde_json::json;
+
+/// Provider for OpenAI compatible chat completions API.
+pub struct OpenAiProvider {
+    http: reqwest::Client,
+    api_url: String,
+    api_token: Option<String>,
+    model: String,
+}
+
+impl OpenAiProvider {
+    pub const DEFAULT_API_URL: &str = "https://api.openai.com/v1";
+
+    pub fn new(config: &ProviderConfig) -> Self {
+        let api_url = config.api_url.as_deref().unwrap_or(Self::DEFAULT_API_URL);
+
+        Self {
+            http: reqwest::Client::new(),
+            api_url: api_url.trim_end_matches('/').to_string(),
+            api_token: config.api_token.clone(),
+            model: config.model.clone(),
+        }
+    }
+
+    #[tracing::instrument(skip_all, fields(model))]
+    async fn complete_impl(&self, request: &LlmRequest) -> eyre::Result<LlmResponse> {
+        let model = request.model.as_deref().unwrap_or(&self.model);
+        tracing::Span::current().record("model", model);
+
+        let mut body = json!({
+            "model": model,
+            "messages": request
+                .conversation
+                .messages
+                .iter()
+                .map(Self::encode_message)
+                .collect::<Vec<_>>(),
+        });
+
+        if let Some(temperature) = request.temperature {
+            body["temperature"] = json!(temperature);
+        }
+        if let Some(max_tokens) = request.max_tokens {
+            body["max_tokens"] = json!(max_tokens);
+        }
+        if let Some(ResponseFormat::JsonSchema { name, schema }) = &request.response_format {
+            body["response_format"] = json!({
+                "type": "json_schema",
+                "json_schema": { "name": name, "schema": schema },
+            });
+        }
+
+        let mut http_request = self
+            .http
+            .post(format!("{}/chat/completions", self.api_url))
+            .json(&body);
+        if let Some(api_token) = &self.api_token {
+            http_request = http_request.bearer_auth(api_token);
+        }
+
+        let response = send_json(http_request).await?;
+
+        let message = response
+            .pointer("/choices/0/message")
+            .context("LLM response has no choices")?;
+
+        let usage = response.get("usage").map(|usage| {
+            TokenUsage::new(
+                usage["prompt_tokens"].as_u64().unwrap_or_default(),
+                usage["completion_tokens"].as_u64().unwrap_or_default(),
+            )
+        });
+
+        Ok(LlmResponse {
+            message: Self::decode_message(message)?,
+            model: response["model"].as_str().unwrap_or(model).to_string(),
+            usage,
+            retries: 0,
+        })
+    }
+
+    fn encode_message(message: &ChatMessage) -> serde_json::Value {
+        let is_text_only = message
+            .content
+            .iter()
+            .all(|part| matches!(part, ContentPart::Text { .. }));
+
+        let content = if is_text_only {
+            json!(message.text())
+        } else {
+            message
+                .content
+                .iter()
+                .map(|part| match part {
+                    ContentPart::Text { text } => json!({ "type": "text", "text": text }),
+                    ContentPart::ImageUrl { url } => {
+                        json!({ "type": "image_url", "image_url": { "url": url } })
+                    }
+                })
+                .collect()
+        };
+
+        let mut encoded = json!({ "role": message.role, "content": content });
+
+        if let Some(name) = &message.name {
+            encoded["name"] = json!(name);
+        }
+        if let Some(tool_call_id) = &message.tool_call_id {
+            encoded["tool_call_id"] = json!(tool_call_id);
+        }
+        if !message.tool_calls.is_empty() {
+            encoded["tool_calls"] = message
+                .tool_calls
+                .iter()
+                .map(|call| {
+                    json!({
+                        "id": call.id,
+                        "type": "function",
+                        "function": { "name": call.name, "arguments": call.arguments.to_string() },
+                    })
+                })
+                .collect();
+        }
+
+        encoded
+    }
+
+    fn decode_message(message: &serde_json::Value) -> eyre::Result<ChatMessage> {
+        let text = message["content"].as_str().unwrap_or_default();
+        let mut decoded = ChatMessage::assistant(text);
+
+        for call in message["tool_calls"].as_array().into_iter().flatten() {
+            let arguments = call["function"]["arguments"].as_str().unwrap_or("{}");
+
+            decoded = decoded.with_tool_call(ToolCall {
+                id: call["id"].as_str().unwrap_or_default().to_string(),
+                name: call["function"]["name"]
+                    .as_str()
+                    .context("Tool call without function name")?
+                    .to_string(),
+                arguments: serde_json::from_str(arguments)
+                    .unwrap_or_else(|_| serde_json::Value::String(arguments.to_string())),
+            });
+        }
+
+        Ok(decoded)
+    }
+}
+
+impl LlmProvider for OpenAiProvider {
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn complete<'a>(&'a self, request: &'a LlmRequest) -> LlmResult<'a> {
+        Box::pin(self.complete_impl(request))
+    }
+}
@@ -0,0 +1,194 @@
+use crate::*;
+use eyre::{ContextCompat, WrapErr};
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+
+pub type LlmResult<'a> = Pin<Box<dyn Future<Output = eyre::Result<LlmResponse>> + Send + 'a>>;
+
+/// Backend able to complete a conversation.
+pub trait LlmProvider: Send + Sync + 'static {
+    /// Model used for requests not overriding it.
+    fn model(&self) -> &str;
+
+    fn complete<'a>(&'a self, request: &'a LlmRequest) -> LlmResult<'a>;
+}
+
+/// Named providers available to LLM nodes.
+#[derive(Clone)]
+pub struct LlmProviders {
+    providers: BTreeMap<String, Arc<dyn LlmProvider>>,
+    default: String,
+    prices: PriceTable,
+}
+
+impl LlmProviders {
+    pub fn new(default: impl Into<String>) -> Self {
+        Self {
+            providers: BTreeMap::new(),
+            default: default.into(),
+            prices: PriceTable::new(),
+        }
+    }
+
+    pub fn from_config(config: &Config) -> eyre::Result<Self> {
+        let mut providers = Self::new(&config.default_provider).with_prices(config.prices.clone());
+
+        for (name, config_provider) in config.all_providers() {
+            let mut provider = create_provider(&config_provider);
+            if !config_provider.rate_limit.is_unlimited() {
+                provider = Arc::new(RateLimitedProvider::new(
+                    &name,
+                    provider,
+                    &config_provider.rate_limit,
+                ));
+            }
+            // replayed responses are not rate limited
+            if config.llm_cache_mode != CacheMode::Live {
+                provider = Arc::new(CachedProvider::new(
+                    &name,
+                    provider,
+                    config.llm_cache_mode,
+                    &config.llm_cache_dir,
+                ));
+            }
+
+            providers = providers.with_provider(name, provider);
+        }
+
+        Ok(providers)
+    }
+
+    pub fn with_provider(
+        mut self,
+        name: impl Into<String>,
+        provider: Arc<dyn LlmProvider>,
+    ) -> Self {
+        self.providers.insert(name.into(), provider);
+        self
+    }
+
+    pub fn with_prices(mut self, prices: PriceTable) -> Self {
+        self.prices = prices;
+        self
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.providers.keys().map(String::as_str)
+    }
+
+    /// Get provider by name or the default one.
+    #[tracing::instrument(skip(self))]
+    pub fn get(&self, name: Option<&str>) -> eyre::Result<Arc<dyn LlmProvider>> {
+        let name = name.unwrap_or(&self.default);
+
+        self.providers
+            .get(name)
+            .cloned()
+            .with_context(|| format!("LLM provider {name:?} is not configured"))
+    }
+}
+
+impl LlmProviders {
+    /// Complete the request with the named (or default) provider.
+    ///
+    /// Usage of the response is always set: estimated if the provider did not report it, with
+    /// the cost taken from the price table.
+    #[tracing::instrument(skip(self, request))]
+    pub async fn complete(
+        &self,
+        provider: Option<&str>,
+        request: &LlmRequest,
+    ) -> eyre::Result<LlmResponse> {
+        let mut response = self.get(provider)?.complete(request).await?;
+
+        let mut usage = response.usage_or_estimate(request);
+        match self.prices.get(&response.model) {
+            Some(price) => usage.cost = price.cost(&usage),
+            None => tracing::debug!(model = response.model, "Price of the model is unknown"),
+        }
+        response.usage = Some(usage);
+
+        tracing::info!(
+            model = response.model,
+            prompt_tokens = usage.prompt_tokens,
+            completion_tokens = usage.completion_tokens,
+            cost = usage.cost,
+            estimated = usage.estimated,
+            "LLM usage"
+        );
+
+        Ok(response)
+    }
+}
+
+fn create_provider(config: &ProviderConfig) -> Arc<dyn LlmProvider> {
+    match config.kind {
+        ProviderKind::OpenAi => Arc::new(OpenAiProvider::new(config)),
+        ProviderKind::Anthropic => Arc::new(AnthropicProvider::new(config)),
+        ProviderKind::Ollama => Arc::new(OllamaProvider::new(config)),
+    }
+}
+
+/// Non-success response of the provider API, can be extracted from LLM errors with
+/// [`eyre::Report::downcast_ref`].
+#[derive(Clone, Debug)]
+pub struct LlmHttpError {
+    pub status: reqwest::StatusCode,
+    /// Delay requested by the `Retry-After` header.
+    pub retry_after: Option<Duration>,
+    pub body: String,
+}
+
+impl LlmHttpError {
+    /// Whether the provider asked to slow down, the request can be retried later.
+    pub fn is_rate_limited(&self) -> bool {
+        self.status == reqwest::StatusCode::TOO_MANY_REQUESTS
+            || (self.status == reqwest::StatusCode::SERVICE_UNAVAILABLE
+                && self.retry_after.is_some())
+    }
+}
+
+impl std::fmt::Display for LlmHttpError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "LLM request failed with {}: {}", self.status, self.body)
+    }
+}
+
+impl std::error::Error for LlmHttpError {}
+
+/// Send request and parse JSON response, failing with [`LlmHttpError`] on non-success status
+/// codes.
+pub(crate) async fn send_json(request: reqwest::RequestBuilder) -> eyre::Result<serde_json::Value> {
+    let response = request
+        .send()
+        .await
+        .wrap_err("Failed to send LLM request")?;
+
+    let status = response.status();
+    // only delay in seconds is supported, HTTP dates are ignored
+    let retry_after = response
+        .headers()
+        .get(reqwest::header::RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse::<f64>().ok())
+        .and_then(|secs| Duration::try_from_secs_f64(secs).ok());
+
+    let response = response
+        .text()
+        .await
+        .wrap_err("Failed to read LLM response")?;
+
+    if !status.is_success() {
+        return Err(LlmHttpError {
+            status,
+            retry_after,
+            body: response,
+        }
+        .into());
+    }
+
+    serde_json::from_str(&response).wrap_err("Failed to parse LLM response")
+}
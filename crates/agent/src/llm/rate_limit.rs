@@ -0,0 +1,252 @@
+use crate::*;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{Mutex, Semaphore};
+use tokio::time::Instant;
+
+/// Delay before retrying a rejected call if the provider did not send `Retry-After`.
+const DEFAULT_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// Upper bound of the retry delay, for both the backoff and `Retry-After`.
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60);
+
+/// Exponential backoff of the retry `attempt`, starting at 1.
+fn backoff(attempt: u32) -> Duration {
+    2u32.checked_pow(attempt.saturating_sub(1))
+        .map_or(MAX_RETRY_DELAY, |factor| {
+            DEFAULT_RETRY_DELAY.saturating_mul(factor)
+        })
+        .min(MAX_RETRY_DELAY)
+}
+
+/// Token bucket refilled continuously to `capacity` per minute.
+struct Bucket {
+    capacity: f64,
+    available: f64,
+    updated_at: Instant,
+}
+
+impl Bucket {
+    fn per_minute(capacity: u32) -> Self {
+        Self {
+            capacity: capacity as f64,
+            available: capacity as f64,
+            updated_at: Instant::now(),
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated_at).as_secs_f64();
+        self.available = (self.available + elapsed * self.capacity / 60.0).min(self.capacity);
+        self.updated_at = now;
+    }
+
+    /// Time until `amount` is available, amounts above the capacity wait for the full bucket.
+    fn wait_time(&self, amount: f64) -> Duration {
+        let missing = amount.min(self.capacity) - self.available;
+        if missing <= 0.0 {
+            return Duration::ZERO;
+        }
+
+        Duration::from_secs_f64(missing * 60.0 / self.capacity)
+    }
+
+    /// Take `amount` from the bucket, it may go below zero to account for underestimates.
+    fn take(&mut self, amount: f64) {
+        self.available -= amount.min(self.capacity);
+    }
+}
+
+#[derive(Default)]
+struct LimiterState {
+    requests: Option<Bucket>,
+    tokens: Option<Bucket>,
+    /// Set by `Retry-After` of the provider, blocks all calls.
+    paused_until: Option<Instant>,
+}
+
+/// Rate limiter shared by all calls to the provider.
+pub struct RateLimiter {
+    state: Mutex<LimiterState>,
+    in_flight: Option<Semaphore>,
+}
+
+impl RateLimiter {
+    pub fn new(config: &RateLimitConfig) -> Self {
+        Self {
+            state: Mutex::new(LimiterState {
+                requests: config.requests_per_minute.map(Bucket::per_minute),
+                tokens: config.tokens_per_minute.map(Bucket::per_minute),
+                paused_until: None,
+            }),
+            in_flight: config.max_in_flight.map(Semaphore::new),
+        }
+    }
+
+    /// Wait until the call with the estimated amount of tokens is allowed.
+    ///
+    /// The returned permit must be held for the duration of the call.
+    #[tracing::instrument(skip(self))]
+    pub async fn acquire(
+        &self,
+        tokens: u64,
+    ) -> eyre::Result<Option<tokio::sync::SemaphorePermit<'_>>> {
+        let permit = match &self.in_flight {
+            Some(in_flight) => {
+                let permit = match in_flight.try_acquire() {
+                    Ok(permit) => permit,
+                    Err(_) => {
+                        tracing::info!(reason = "max_in_flight", "LLM call throttled");
+                        in_flight.acquire().await?
+                    }
+                };
+
+                Some(permit)
+            }
+            None => None,
+        };
+
+        loop {
+            let mut state = self.state.lock().await;
+
+            let now = Instant::now();
+            let paused = state
+                .paused_until
+                .map(|until| (until.saturating_duration_since(now), "retry_after"));
+            let requests = state.requests.as_mut().map(|bucket| {
+                bucket.refill(now);
+                (bucket.wait_time(1.0), "requests_per_minute")
+            });
+            let tokens_wait = state.tokens.as_mut().map(|bucket| {
+                bucket.refill(now);
+                (bucket.wait_time(tokens as f64), "tokens_per_minute")
+            });
+
+            let wait = [paused, requests, tokens_wait]
+                .into_iter()
+                .flatten()
+                .max_by_key(|(wait, _)| *wait);
+
+            match wait {
+                Some((wait, reason)) if !wait.is_zero() => {
+                    drop(state);
+
+                    tracing::info!(reason, wait = ?wait, "LLM call throttled");
+                    tokio::time::sleep(wait).await;
+                }
+                _ => {
+                    state.paused_until = None;
+                    if let Some(bucket) = &mut state.requests {
+                        bucket.take(1.0);
+                    }
+                    if let Some(bucket) = &mut state.tokens {
+                        bucket.take(tokens as f64);
+                    }
+
+                    return Ok(permit);
+                }
+            }
+        }
+    }
+
+    /// Account tokens used above the estimate passed to [`Self::acquire`].
+    pub async fn record_tokens(&self, extra_tokens: u64) {
+        if let Some(bucket) = &mut self.state.lock().await.tokens {
+            bucket.take(extra_tokens as f64);
+        }
+    }
+
+    /// Block all calls for the delay requested by the provider.
+    pub async fn pause(&self, delay: Duration) {
+        let until = Instant::now() + delay;
+        let mut state = self.state.lock().await;
+
+        state.paused_until = Some(
+            state
+                .paused_until
+                .map_or(until, |current| current.max(until)),
+        );
+    }
+}
+
+/// Provider wrapper applying [`RateLimitConfig`] and retrying calls rejected with
+/// `429 Too Many Requests` after the `Retry-After` delay, capped at a minute.
+pub struct RateLimitedProvider {
+    inner: Arc<dyn LlmProvider>,
+    name: String,
+    limiter: RateLimiter,
+    max_retries: u32,
+}
+
+impl RateLimitedProvider {
+    pub fn new(
+        name: impl Into<String>,
+        inner: Arc<dyn LlmProvider>,
+        config: &RateLimitConfig,
+    ) -> Self {
+        Self {
+            inner,
+            name: name.into(),
+            limiter: RateLimiter::new(config),
+            max_retries: config.max_retries,
+        }
+    }
+
+    #[tracing::instrument(skip_all, fields(provider = self.name))]
+    async fn complete_impl(&self, request: &LlmRequest) -> eyre::Result<LlmResponse> {
+        let estimate = request.conversation.estimate_tokens() as u64
+            + request.max_tokens.unwrap_or_default() as u64;
+
+        let mut attempt = 0;
+        loop {
+            let permit = self.limiter.acquire(estimate).await?;
+            let result = self.inner.complete(request).await;
+            drop(permit);
+
+            let err = match result {
+                Ok(mut response) => {
+                    response.retries += attempt;
+                    if let Some(usage) = response.usage {
+                        self.limiter
+                            .record_tokens(usage.total_tokens().saturating_sub(estimate))
+                            .await;
+                    }
+
+                    return Ok(response);
+                }
+                Err(err) => err,
+            };
+
+            let Some(http_err) = err.downcast_ref::<LlmHttpError>() else {
+                return Err(err);
+            };
+            if !http_err.is_rate_limited() || attempt >= self.max_retries {
+                return Err(err);
+            }
+
+            attempt += 1;
+            let delay = http_err
+                .retry_after
+                .unwrap_or_else(|| backoff(attempt))
+                .min(MAX_RETRY_DELAY);
+
+            tracing::warn!(
+                status = %http_err.status,
+                delay = ?delay,
+                attempt,
+                "LLM call rejected by the provider, retrying"
+            );
+            self.limiter.pause(delay).await;
+        }
+    }
+}
+
+impl LlmProvider for RateLimitedProvider {
+    fn model(&self) -> &str {
+        self.inner.model()
+    }
+
+    fn complete<'a>(&'a self, request: &'a LlmRequest) -> LlmResult<'a> {
+        Box::pin(self.complete_impl(request))
+    }
+}
@@ -0,0 +1,77 @@
+use node::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct LlmRequest {
+    /// Model to use, client default model is used if not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    pub conversation: Conversation,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response_format: Option<ResponseFormat>,
+}
+
+impl LlmRequest {
+    pub fn new(conversation: Conversation) -> Self {
+        Self {
+            conversation,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_model(mut self, model: impl Into<String>) -> Self {
+        self.model = Some(model.into());
+        self
+    }
+
+    pub fn with_response_format(mut self, response_format: ResponseFormat) -> Self {
+        self.response_format = Some(response_format);
+        self
+    }
+}
+
+/// Constraint on the model reply.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ResponseFormat {
+    Text,
+    /// Reply must be a JSON document matching the schema.
+    JsonSchema {
+        name: String,
+        schema: serde_json::Value,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LlmResponse {
+    pub message: ChatMessage,
+    /// Model which produced the response.
+    pub model: String,
+    /// Usage reported by the provider.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<TokenUsage>,
+    /// Calls rejected by the provider and retried before this response.
+    #[serde(skip)]
+    pub retries: u32,
+}
+
+impl LlmResponse {
+    pub fn text(&self) -> String {
+        self.message.text()
+    }
+
+    /// Usage reported by the provider or estimated locally from the request and reply.
+    pub fn usage_or_estimate(&self, request: &LlmRequest) -> TokenUsage {
+        self.usage.unwrap_or_else(|| TokenUsage {
+            estimated: true,
+            ..TokenUsage::new(
+                request.conversation.estimate_tokens() as u64,
+                self.message.estimate_tokens() as u64,
+            )
+        })
+    }
+}
@@ -1,26 +1,351 @@
 use agent::*;
+use clap::{Parser, Subcommand};
+use eyre::WrapErr;
 use init_log::init_logging;
+use node::*;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
 use std::sync::Arc;
+use std::time::Duration;
+
+/// Exit code of failed runs and other runtime errors.
+const EXIT_RUN_FAILED: u8 = 1;
+/// Exit code of invalid graphs and arguments, same as for command line usage errors.
+const EXIT_INVALID: u8 = 2;
+
+/// Build and run agent graphs.
+#[derive(Parser)]
+#[command(
+    version,
+    about,
+    after_help = "Exit codes: 0 on success, 1 if the run failed, 2 if the graph or arguments are invalid."
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Run the graph file.
+    Run {
+        graph: PathBuf,
+        /// Value of a graph input.
+        #[arg(long = "input", value_name = "NAME=VALUE", value_parser = parse_input)]
+        inputs: Vec<(String, String)>,
+        /// JSON value of a graph input.
+        #[arg(long = "input-json", value_name = "NAME=JSON", value_parser = parse_input)]
+        json_inputs: Vec<(String, String)>,
+        /// Stop the run after spending this many tokens.
+        #[arg(long)]
+        max_tokens: Option<u64>,
+        /// Stop the run after spending this many USD.
+        #[arg(long)]
+        max_cost: Option<f64>,
+        /// Stop the run after this many seconds.
+        #[arg(long, value_name = "SECS")]
+        timeout: Option<f64>,
+        /// Print the run report as JSON.
+        #[arg(long)]
+        report: bool,
+        /// Write the graph colored by the run result, `.mmd` and `.md` files are written as
+        /// Mermaid, other files as Graphviz DOT.
+        #[arg(long, value_name = "PATH")]
+        export: Option<PathBuf>,
+        /// Pause before the instance id or every instance of the node id and read debugger
+        /// commands from stdin.
+        #[arg(long = "break", value_name = "INSTANCE|NODE", value_parser = parse_breakpoint)]
+        breakpoints: Vec<Breakpoint>,
+        /// Pause before the first instance and read debugger commands from stdin.
+        #[arg(long)]
+        step: bool,
+    },
+    /// Check that the graph file can be loaded and all connections are valid.
+    Validate { graph: PathBuf },
+    /// Inspect registered nodes.
+    Nodes {
+        #[command(subcommand)]
+        command: NodesCommand,
+    },
+    /// Serve the HTTP API to upload graphs, start runs and stream their events.
+    Serve {
+        /// Address to listen on, `AGENT_SERVER_ADDR` by default.
+        #[arg(long)]
+        addr: Option<String>,
+    },
+    /// Build and run a graph interactively, with tab completion of nodes and ports.
+    Repl,
+    /// Export graph files.
+    Graph {
+        #[command(subcommand)]
+        command: GraphCommand,
+    },
+}
+
+#[derive(Subcommand)]
+enum NodesCommand {
+    /// List registered nodes with their versions and ports.
+    List {
+        /// Print as JSON.
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum GraphCommand {
+    /// Print the graph in Graphviz DOT format.
+    Dot { graph: PathBuf },
+    /// Print the graph as Mermaid flowchart.
+    Mermaid { graph: PathBuf },
+}
+
+fn parse_input(arg: &str) -> Result<(String, String), String> {
+    arg.split_once('=')
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .ok_or_else(|| format!("expected NAME=VALUE, got {arg:?}"))
+}
+
+fn parse_breakpoint(arg: &str) -> Result<Breakpoint, String> {
+    arg.parse().map_err(|err| format!("{err}"))
+}
+
+/// Error of a command, determines the exit code.
+enum Failure {
+    Invalid(eyre::Report),
+    Run(eyre::Report),
+}
+
+impl From<eyre::Report> for Failure {
+    fn from(err: eyre::Report) -> Self {
+        Self::Run(err)
+    }
+}
 
 #[tokio::main]
-pub async fn main() -> eyre::Result<()> {
-    init_logging(true);
+pub async fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let _log_guard = match init_logging(true) {
+        Ok(guard) => guard,
+        Err(err) => {
+            // color_eyre is not installed, print only the causes
+            eprintln!("Error: {err:#}");
+            return ExitCode::from(EXIT_INVALID);
+        }
+    };
 
-    let _config = Arc::new(Config::init()?);
+    match execute(cli.command).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(Failure::Invalid(err)) => {
+            eprintln!("Error: {err:?}");
+            ExitCode::from(EXIT_INVALID)
+        }
+        Err(Failure::Run(err)) => {
+            eprintln!("Error: {err:?}");
+            ExitCode::from(EXIT_RUN_FAILED)
+        }
+    }
+}
+
+async fn execute(command: Command) -> Result<(), Failure> {
+    let config = Config::init().map_err(Failure::Invalid)?;
+
+    match command {
+        Command::Run {
+            graph,
+            inputs,
+            json_inputs,
+            max_tokens,
+            max_cost,
+            timeout,
+            report,
+            export,
+            breakpoints,
+            step,
+        } => {
+            let (mut task, graph) = load_task(&graph, &config)?;
+
+            let mut values = inputs
+                .into_iter()
+                .map(|(name, value)| (name, Value::new(value)))
+                .collect::<BTreeMap<_, _>>();
+            for (name, json) in json_inputs {
+                let json = serde_json::from_str(&json)
+                    .wrap_err_with(|| format!("Invalid JSON of the input {name:?}"))
+                    .map_err(Failure::Invalid)?;
+                values.insert(name, Value::from_json(json));
+            }
+            task.set_graph_inputs(&graph, values)
+                .map_err(Failure::Invalid)?;
+            task.validate().map_err(Failure::Invalid)?;
+
+            let mut budget = RunBudget::unlimited();
+            if let Some(max_tokens) = max_tokens {
+                budget = budget.with_max_tokens(max_tokens);
+            }
+            if let Some(max_cost) = max_cost {
+                budget = budget.with_max_cost(max_cost);
+            }
+            if let Some(timeout) = timeout {
+                let timeout = Duration::try_from_secs_f64(timeout)
+                    .wrap_err("Invalid timeout")
+                    .map_err(Failure::Invalid)?;
+                budget = budget.with_max_duration(timeout);
+            }
 
-    let mut task = node::Task::new();
+            // collected for the summary printed after the run
+            let recorder = metrics_util::debugging::DebuggingRecorder::new();
+            let snapshotter = recorder.snapshotter();
+            if let Err(err) = recorder.install() {
+                tracing::warn!("Failed to install metrics recorder: {err}");
+            }
 
-    let node_text = task.register_node(node::NodeText)?;
-    let node_print = task.register_node(node::NodePrint)?;
+            let result = if breakpoints.is_empty() && !step {
+                task.run(&budget).await
+            } else {
+                let debugger = Debugger::new()
+                    .with_breakpoints(breakpoints)
+                    .with_step(step);
+                let (session, run) = task.run_debug(&budget, debugger);
+                let console = DebugConsole::new(
+                    tokio::io::BufReader::new(tokio::io::stdin()),
+                    std::io::stderr(),
+                );
 
-    let node_text = task.instantiate(&node_text)?;
-    let node_print = task.instantiate(&node_print)?;
+                let (result, console_result) = tokio::join!(run, console.run(session));
+                console_result?;
+                result
+            };
+            eprint!("{}", MetricsSummary::from_snapshot(snapshotter.snapshot()));
+            let last_report = task.last_report();
+            if let (true, Some(last_report)) = (report, &last_report) {
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(last_report).map_err(eyre::Report::from)?
+                );
+            }
+            if let Some(path) = export {
+                let exported = export_graph(&task, &graph)?;
+                let mut export = GraphExport::new(&exported);
+                if let Some(last_report) = &last_report {
+                    export = export.with_report(last_report);
+                }
 
-    task.set_instance_memory(node_text, "text", "Test!".to_string())?;
+                let is_mermaid = path
+                    .extension()
+                    .is_some_and(|extension| extension == "mmd" || extension == "md");
+                let text = if is_mermaid {
+                    export.to_mermaid()
+                } else {
+                    export.to_dot()
+                };
+                std::fs::write(&path, text)
+                    .wrap_err_with(|| format!("Failed to write {}", path.display()))?;
+            }
+            result?;
+        }
+        Command::Validate { graph } => {
+            let (task, _) = load_task(&graph, &config)?;
+            task.validate().map_err(Failure::Invalid)?;
 
-    task.connect(node_text, "text", node_print, "text")?;
+            println!("{} is valid", graph.display());
+        }
+        Command::Nodes {
+            command: NodesCommand::List { json },
+        } => {
+            let mut task = Task::new();
+            register_nodes(&mut task, &config)?;
 
-    task.run().await?;
+            let nodes = task.nodes();
+
+            if json {
+                let nodes = nodes
+                    .into_iter()
+                    .map(|node| {
+                        serde_json::json!({
+                            "id": node.id(),
+                            "version": node.get_meta().version,
+                            "ports": PortsGraph::from(&node_ports(node)),
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&nodes).map_err(eyre::Report::from)?
+                );
+            } else {
+                for node in nodes {
+                    println!("{} {}", node.id(), node.get_meta().version);
+                    for (name, arg) in node.input_args() {
+                        let optional = if arg.is_optional { " (optional)" } else { "" };
+                        println!("    in  {name}: {}{optional}", arg.value_type.type_name);
+                    }
+                    for (name, arg) in node.output_args() {
+                        println!("    out {name}: {}", arg.value_type.type_name);
+                    }
+                }
+            }
+        }
+        Command::Serve { addr } => {
+            let addr = addr.unwrap_or_else(|| config.server_addr.clone());
+            let server = Server::new(Arc::new(config))?;
+
+            let listener = tokio::net::TcpListener::bind(&addr)
+                .await
+                .wrap_err_with(|| format!("Failed to listen on {addr}"))?;
+            server.serve(listener).await?;
+        }
+        Command::Repl => run_repl(Arc::new(config)).await?,
+        Command::Graph { command } => {
+            let path = match &command {
+                GraphCommand::Dot { graph } | GraphCommand::Mermaid { graph } => graph,
+            };
+            let (task, graph) = load_task(path, &config)?;
+            let exported = export_graph(&task, &graph)?;
+
+            match command {
+                GraphCommand::Dot { .. } => print!("{}", GraphExport::new(&exported).to_dot()),
+                GraphCommand::Mermaid { .. } => {
+                    print!("{}", GraphExport::new(&exported).to_mermaid())
+                }
+            }
+        }
+    }
 
     Ok(())
 }
+
+/// Read the graph file and load it into a task with all nodes registered.
+fn load_task(path: &Path, config: &Config) -> Result<(Task, TaskGraph), Failure> {
+    let text = std::fs::read_to_string(path)
+        .wrap_err_with(|| format!("Failed to read {}", path.display()))
+        .map_err(Failure::Invalid)?;
+    let graph = serde_json::from_str::<TaskGraph>(&text)
+        .wrap_err_with(|| format!("Failed to parse {}", path.display()))
+        .map_err(Failure::Invalid)?;
+
+    let mut task = Task::new();
+    register_nodes(&mut task, config)?;
+    task.load_graph(&graph)
+        .wrap_err_with(|| format!("Failed to load {}", path.display()))
+        .map_err(Failure::Invalid)?;
+
+    Ok((task, graph))
+}
+
+/// Graph of the task with effective ports and inputs of the graph file.
+fn export_graph(task: &Task, graph: &TaskGraph) -> eyre::Result<TaskGraph> {
+    let mut exported = task.to_graph()?;
+    exported.inputs = graph.inputs.clone();
+
+    Ok(exported)
+}
+
+/// Ports declared by the node, without instance specific ones.
+fn node_ports(node: &Node) -> NodePorts {
+    NodePorts {
+        input_args: node.input_args().clone(),
+        output_args: node.output_args().clone(),
+    }
+}
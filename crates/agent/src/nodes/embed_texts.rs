@@ -0,0 +1,60 @@
+use crate::*;
+use eyre::ContextCompat;
+use node::*;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+/// Embed every input text with the embeddings provider.
+pub struct NodeEmbedTexts {
+    embeddings: Arc<dyn EmbeddingProvider>,
+}
+
+impl NodeEmbedTexts {
+    pub const INPUT_ARG_TEXTS: &str = "texts";
+    pub const OUTPUT_ARG_EMBEDDINGS: &str = "embeddings";
+
+    pub fn new(embeddings: Arc<dyn EmbeddingProvider>) -> Self {
+        Self { embeddings }
+    }
+}
+
+impl NodeTrait for NodeEmbedTexts {
+    fn run<'a>(
+        &'a self,
+        instance: &'a NodeInstance,
+        ctx: &'a RunContext,
+        input: &'a InstanceRefArgs,
+    ) -> RunResult<'a> {
+        Box::pin(async {
+            let texts = input
+                .get(Self::INPUT_ARG_TEXTS)
+                .context("Embed texts node: missing input argument")?
+                .downcast::<Vec<String>>()?;
+
+            let vectors = if texts.is_empty() {
+                Vec::new()
+            } else {
+                let embeddings = self.embeddings.embed(texts).await?;
+                ctx.record_usage(instance.instance_id, embeddings.usage_or_estimate(texts));
+
+                embeddings.vectors
+            };
+
+            Ok(BTreeMap::from([(
+                Self::OUTPUT_ARG_EMBEDDINGS.to_string(),
+                Value::new(vectors),
+            )]))
+        })
+    }
+}
+
+impl NodeMetaTrait for NodeEmbedTexts {
+    fn get_meta(&self) -> NodeMeta {
+        NodeMeta::new("embed_texts", "0.1.0")
+            .with_input_arg(Self::INPUT_ARG_TEXTS, InputArgMeta::new::<Vec<String>>())
+            .with_output_arg(
+                Self::OUTPUT_ARG_EMBEDDINGS,
+                OutputArgMeta::new::<Vec<Vec<f32>>>(),
+            )
+    }
+}
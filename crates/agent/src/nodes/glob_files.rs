@@ -0,0 +1,155 @@
+use crate::*;
+use eyre::{ContextCompat, WrapErr};
+use node::*;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Find files in the sandbox matching the glob pattern, e.g. `docs/**/*.md`.
+///
+/// Symlinked directories are not traversed and symlinks leading out of the sandbox are
+/// skipped. Unreadable directories are skipped with a warning.
+pub struct NodeGlobFiles {
+    sandbox: Arc<Sandbox>,
+}
+
+impl NodeGlobFiles {
+    /// Pattern relative to the sandbox root, taken from memory if the input is not connected.
+    pub const INPUT_ARG_PATTERN: &str = "pattern";
+    /// Sorted paths of matching files relative to the sandbox root.
+    pub const OUTPUT_ARG_PATHS: &str = "paths";
+    pub const MEMORY_PATTERN: &str = "pattern";
+
+    pub fn new(sandbox: Arc<Sandbox>) -> Self {
+        Self { sandbox }
+    }
+
+    /// Directory components of the pattern before the first one with wildcards, the walk
+    /// starts there instead of the sandbox root.
+    fn literal_prefix(pattern: &str) -> String {
+        let parts = pattern.split('/').collect::<Vec<_>>();
+
+        parts[..parts.len() - 1]
+            .iter()
+            .take_while(|part| !part.contains(['*', '?', '[']))
+            .copied()
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    /// Paths of all files below `dir`, relative to the sandbox root.
+    ///
+    /// Unreadable directories and entries are skipped with a warning.
+    fn walk(sandbox: &Sandbox, dir: &Path, files: &mut Vec<String>) -> eyre::Result<()> {
+        let entries = match std::fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(err) => {
+                tracing::warn!("Skipping unreadable directory {}: {err}", dir.display());
+                return Ok(());
+            }
+        };
+
+        for entry in entries {
+            let entry = entry.and_then(|entry| Ok((entry.file_type()?, entry)));
+            let (file_type, entry) = match entry {
+                Ok(entry) => entry,
+                Err(err) => {
+                    tracing::warn!("Skipping unreadable entry in {}: {err}", dir.display());
+                    continue;
+                }
+            };
+            let relative = sandbox.relative(&entry.path())?;
+
+            if file_type.is_dir() {
+                Self::walk(sandbox, &entry.path(), files)?;
+            } else if file_type.is_file()
+                || (file_type.is_symlink()
+                    && sandbox.resolve(&relative).is_ok()
+                    && entry.path().is_file())
+            {
+                files.push(relative);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn glob(sandbox: &Sandbox, pattern: &str) -> eyre::Result<Vec<String>> {
+        if Path::new(pattern)
+            .components()
+            .any(|component| !matches!(component, std::path::Component::Normal(_)))
+        {
+            return Err(eyre::eyre!(
+                "Pattern {pattern:?} must be relative to the sandbox root"
+            ));
+        }
+
+        let matcher = glob::Pattern::new(pattern)?;
+        let options = glob::MatchOptions {
+            require_literal_separator: true,
+            ..Default::default()
+        };
+
+        // a missing prefix matches nothing, a symlinked one is not traversed like any other
+        // symlinked directory
+        let prefix = Self::literal_prefix(pattern);
+        let start = sandbox.root().join(&prefix);
+        let is_dir = start
+            .symlink_metadata()
+            .is_ok_and(|metadata| metadata.is_dir())
+            && sandbox
+                .resolve(&prefix)
+                .is_ok_and(|resolved| resolved == start);
+        if !is_dir {
+            return Ok(Vec::new());
+        }
+
+        let mut files = Vec::new();
+        Self::walk(sandbox, &start, &mut files)?;
+        files.retain(|path| matcher.matches_with(path, options));
+        files.sort();
+
+        Ok(files)
+    }
+}
+
+impl NodeTrait for NodeGlobFiles {
+    fn run<'a>(
+        &'a self,
+        instance: &'a NodeInstance,
+        _ctx: &'a RunContext,
+        input: &'a InstanceRefArgs,
+    ) -> RunResult<'a> {
+        Box::pin(async {
+            let pattern = match input.get(Self::INPUT_ARG_PATTERN) {
+                Some(pattern) => pattern.downcast::<String>()?.clone(),
+                None => instance
+                    .get_memory_parsed::<String>(Self::MEMORY_PATTERN)?
+                    .context("Glob files node: pattern is not set")?,
+            };
+
+            // walking a large tree blocks, so it runs off the async workers
+            let sandbox = self.sandbox.clone();
+            let paths = tokio::task::spawn_blocking(move || Self::glob(&sandbox, &pattern))
+                .await
+                .wrap_err("Glob files node: file walk panicked")?
+                .wrap_err("Glob files node: failed to match files")?;
+
+            Ok(BTreeMap::from([(
+                Self::OUTPUT_ARG_PATHS.to_string(),
+                Value::new(paths),
+            )]))
+        })
+    }
+}
+
+impl NodeMetaTrait for NodeGlobFiles {
+    fn get_meta(&self) -> NodeMeta {
+        NodeMeta::new("glob_files", "0.1.0")
+            .with_input_arg(
+                Self::INPUT_ARG_PATTERN,
+                InputArgMeta::new::<String>().with_optional(true),
+            )
+            .with_output_arg(Self::OUTPUT_ARG_PATHS, OutputArgMeta::new::<Vec<String>>())
+    }
+}
@@ -0,0 +1,277 @@
+use crate::*;
+use eyre::{ContextCompat, WrapErr};
+use node::*;
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Restrictions of requests sent by [`NodeHttpRequest`].
+#[derive(Clone, Debug)]
+pub struct HttpPolicy {
+    /// Allowed hosts, `*.example.com` allows all subdomains of `example.com`.
+    pub allowed_hosts: BTreeSet<String>,
+    pub timeout: Duration,
+    pub max_response_bytes: usize,
+}
+
+impl HttpPolicy {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            allowed_hosts: config.http_allowed_hosts.iter().cloned().collect(),
+            timeout: config.http_timeout,
+            max_response_bytes: config.http_max_response_bytes,
+        }
+    }
+
+    pub fn is_allowed(&self, url: &reqwest::Url) -> bool {
+        let Some(host) = url.host_str() else {
+            return false;
+        };
+        let host = host.to_lowercase();
+
+        self.allowed_hosts.iter().any(|allowed| {
+            let allowed = allowed.to_lowercase();
+            match allowed.strip_prefix("*.") {
+                Some(domain) => host
+                    .strip_suffix(domain)
+                    .is_some_and(|subdomain| subdomain.ends_with('.')),
+                None => host == allowed,
+            }
+        })
+    }
+}
+
+/// Send an HTTP request to an allowlisted host.
+///
+/// Responses with error status codes are returned as the output, not as errors. Redirects are
+/// followed only to allowed hosts.
+pub struct NodeHttpRequest {
+    policy: Arc<HttpPolicy>,
+    http: reqwest::Client,
+}
+
+impl NodeHttpRequest {
+    /// HTTP method, taken from memory if the input is not connected, `GET` by default.
+    pub const INPUT_ARG_METHOD: &str = "method";
+    /// Taken from memory if the input is not connected.
+    pub const INPUT_ARG_URL: &str = "url";
+    /// JSON object with header values, merged over the headers from memory.
+    pub const INPUT_ARG_HEADERS: &str = "headers";
+    pub const INPUT_ARG_BODY: &str = "body";
+    /// JSON body, sent with the `application/json` content type.
+    pub const INPUT_ARG_JSON: &str = "json";
+    pub const OUTPUT_ARG_STATUS: &str = "status";
+    /// JSON object with response header values.
+    pub const OUTPUT_ARG_HEADERS: &str = "headers";
+    pub const OUTPUT_ARG_BODY: &str = "body";
+    /// Parsed body, `null` if the body is not JSON.
+    pub const OUTPUT_ARG_JSON: &str = "json";
+    pub const MEMORY_METHOD: &str = "method";
+    pub const MEMORY_URL: &str = "url";
+    pub const MEMORY_HEADERS: &str = "headers";
+
+    pub fn new(policy: Arc<HttpPolicy>) -> eyre::Result<Self> {
+        let redirect_policy = policy.clone();
+        let http = reqwest::Client::builder()
+            .timeout(policy.timeout)
+            .redirect(reqwest::redirect::Policy::custom(move |attempt| {
+                if attempt.previous().len() >= 10 {
+                    attempt.error("Too many redirects")
+                } else if redirect_policy.is_allowed(attempt.url()) {
+                    attempt.follow()
+                } else {
+                    let error = format!("Redirect to {} is not allowed", attempt.url());
+                    attempt.error(error)
+                }
+            }))
+            .build()
+            .wrap_err("Failed to create HTTP client")?;
+
+        Ok(Self { policy, http })
+    }
+
+    fn string_arg(
+        instance: &NodeInstance,
+        input: &InstanceRefArgs,
+        arg_name: &str,
+    ) -> eyre::Result<Option<String>> {
+        match input.get(arg_name) {
+            Some(value) => Ok(Some(value.downcast::<String>()?.clone())),
+            None => instance.get_memory_parsed::<String>(arg_name),
+        }
+    }
+
+    fn headers(
+        instance: &NodeInstance,
+        input: &InstanceRefArgs,
+    ) -> eyre::Result<reqwest::header::HeaderMap> {
+        let mut values = instance
+            .get_memory_parsed::<BTreeMap<String, String>>(Self::MEMORY_HEADERS)?
+            .unwrap_or_default();
+        if let Some(headers) = input.get(Self::INPUT_ARG_HEADERS) {
+            values.extend(
+                serde_json::from_value::<BTreeMap<String, String>>(
+                    headers.downcast::<serde_json::Value>()?.clone(),
+                )
+                .wrap_err("Headers must be an object with string values")?,
+            );
+        }
+
+        let mut headers = reqwest::header::HeaderMap::new();
+        for (name, value) in values {
+            headers.insert(
+                reqwest::header::HeaderName::from_bytes(name.as_bytes())
+                    .wrap_err_with(|| format!("Invalid header name {name:?}"))?,
+                value
+                    .parse()
+                    .wrap_err_with(|| format!("Invalid value of the header {name:?}"))?,
+            );
+        }
+
+        Ok(headers)
+    }
+
+    /// Read the body, failing if it exceeds the max response size.
+    async fn read_body(&self, mut response: reqwest::Response) -> eyre::Result<Vec<u8>> {
+        let limit = self.policy.max_response_bytes;
+        if response
+            .content_length()
+            .is_some_and(|len| len > limit as u64)
+        {
+            return Err(eyre::eyre!("Response exceeds {limit} bytes"));
+        }
+
+        let mut body = Vec::new();
+        while let Some(chunk) = response.chunk().await? {
+            if body.len() + chunk.len() > limit {
+                return Err(eyre::eyre!("Response exceeds {limit} bytes"));
+            }
+            body.extend_from_slice(&chunk);
+        }
+
+        Ok(body)
+    }
+}
+
+impl NodeTrait for NodeHttpRequest {
+    fn run<'a>(
+        &'a self,
+        instance: &'a NodeInstance,
+        _ctx: &'a RunContext,
+        input: &'a InstanceRefArgs,
+    ) -> RunResult<'a> {
+        Box::pin(async {
+            let method = Self::string_arg(instance, input, Self::INPUT_ARG_METHOD)?
+                .unwrap_or_else(|| "GET".to_string());
+            let method = reqwest::Method::from_bytes(method.to_uppercase().as_bytes())
+                .wrap_err_with(|| format!("HTTP request node: invalid method {method:?}"))?;
+            let url = Self::string_arg(instance, input, Self::INPUT_ARG_URL)?
+                .context("HTTP request node: url is not set")?;
+            let url = reqwest::Url::parse(&url)
+                .wrap_err_with(|| format!("HTTP request node: invalid url {url:?}"))?;
+
+            if !self.policy.is_allowed(&url) {
+                return Err(eyre::eyre!(
+                    "HTTP request node: host of {url} is not allowed"
+                ));
+            }
+
+            let mut request = self
+                .http
+                .request(method.clone(), url.clone())
+                .headers(Self::headers(instance, input).wrap_err("HTTP request node")?);
+            request = match (
+                input.get(Self::INPUT_ARG_BODY),
+                input.get(Self::INPUT_ARG_JSON),
+            ) {
+                (Some(_), Some(_)) => {
+                    return Err(eyre::eyre!(
+                        "HTTP request node: only one of body or json can be connected"
+                    ))
+                }
+                (Some(body), None) => request.body(body.downcast::<String>()?.clone()),
+                (None, Some(json)) => request.json(json.downcast::<serde_json::Value>()?),
+                (None, None) => request,
+            };
+
+            tracing::info!(%method, %url, "Sending HTTP request");
+            let response = request
+                .send()
+                .await
+                .wrap_err_with(|| format!("HTTP request node: {method} {url} failed"))?;
+
+            let status = response.status();
+            let mut headers = serde_json::Map::new();
+            for (name, value) in response.headers() {
+                let value = String::from_utf8_lossy(value.as_bytes()).into_owned();
+                headers
+                    .entry(name.as_str())
+                    .and_modify(|existing| {
+                        if let serde_json::Value::String(existing) = existing {
+                            existing.push_str(", ");
+                            existing.push_str(&value);
+                        }
+                    })
+                    .or_insert_with(|| serde_json::Value::String(value.clone()));
+            }
+
+            let body = self
+                .read_body(response)
+                .await
+                .wrap_err_with(|| format!("HTTP request node: failed to read {url}"))?;
+            let body = String::from_utf8_lossy(&body).into_owned();
+            let json = serde_json::from_str(&body).unwrap_or(serde_json::Value::Null);
+
+            tracing::info!(%method, %url, status = status.as_u16(), "HTTP request finished");
+
+            Ok(BTreeMap::from([
+                (
+                    Self::OUTPUT_ARG_STATUS.to_string(),
+                    Value::new(i64::from(status.as_u16())),
+                ),
+                (
+                    Self::OUTPUT_ARG_HEADERS.to_string(),
+                    Value::new(serde_json::Value::Object(headers)),
+                ),
+                (Self::OUTPUT_ARG_BODY.to_string(), Value::new(body)),
+                (Self::OUTPUT_ARG_JSON.to_string(), Value::new(json)),
+            ]))
+        })
+    }
+}
+
+impl NodeMetaTrait for NodeHttpRequest {
+    fn get_meta(&self) -> NodeMeta {
+        NodeMeta::new("http_request", "0.1.0")
+            .with_input_arg(
+                Self::INPUT_ARG_METHOD,
+                InputArgMeta::new::<String>().with_optional(true),
+            )
+            .with_input_arg(
+                Self::INPUT_ARG_URL,
+                InputArgMeta::new::<String>().with_optional(true),
+            )
+            .with_input_arg(
+                Self::INPUT_ARG_HEADERS,
+                InputArgMeta::new::<serde_json::Value>().with_optional(true),
+            )
+            .with_input_arg(
+                Self::INPUT_ARG_BODY,
+                InputArgMeta::new::<String>().with_optional(true),
+            )
+            .with_input_arg(
+                Self::INPUT_ARG_JSON,
+                InputArgMeta::new::<serde_json::Value>().with_optional(true),
+            )
+            .with_output_arg(Self::OUTPUT_ARG_STATUS, OutputArgMeta::new::<i64>())
+            .with_output_arg(
+                Self::OUTPUT_ARG_HEADERS,
+                OutputArgMeta::new::<serde_json::Value>(),
+            )
+            .with_output_arg(Self::OUTPUT_ARG_BODY, OutputArgMeta::new::<String>())
+            .with_output_arg(
+                Self::OUTPUT_ARG_JSON,
+                OutputArgMeta::new::<serde_json::Value>(),
+            )
+    }
+}
@@ -0,0 +1,75 @@
+use crate::*;
+use eyre::WrapErr;
+use node::*;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+/// List the directory in the sandbox, the sandbox root is listed if no path is set.
+pub struct NodeListDir {
+    sandbox: Arc<Sandbox>,
+}
+
+impl NodeListDir {
+    /// Path relative to the sandbox root, taken from memory if the input is not connected.
+    pub const INPUT_ARG_PATH: &str = "path";
+    /// Sorted paths of the entries relative to the sandbox root, directories end with `/`.
+    pub const OUTPUT_ARG_ENTRIES: &str = "entries";
+    pub const MEMORY_PATH: &str = "path";
+
+    pub fn new(sandbox: Arc<Sandbox>) -> Self {
+        Self { sandbox }
+    }
+}
+
+impl NodeTrait for NodeListDir {
+    fn run<'a>(
+        &'a self,
+        instance: &'a NodeInstance,
+        _ctx: &'a RunContext,
+        input: &'a InstanceRefArgs,
+    ) -> RunResult<'a> {
+        Box::pin(async {
+            let is_set = input.contains_key(Self::INPUT_ARG_PATH)
+                || instance.get_memory::<String>(Self::MEMORY_PATH)?.is_some();
+            let path = if is_set {
+                self.sandbox
+                    .path_arg(instance, input, Self::INPUT_ARG_PATH)
+                    .wrap_err("List dir node: invalid path")?
+            } else {
+                self.sandbox.root().to_path_buf()
+            };
+
+            let mut entries = Vec::new();
+            let mut dir = tokio::fs::read_dir(&path)
+                .await
+                .wrap_err_with(|| format!("List dir node: failed to read {}", path.display()))?;
+            while let Some(entry) = dir.next_entry().await? {
+                let mut name = self.sandbox.relative(&entry.path())?;
+                if entry.file_type().await?.is_dir() {
+                    name.push('/');
+                }
+                entries.push(name);
+            }
+            entries.sort();
+
+            Ok(BTreeMap::from([(
+                Self::OUTPUT_ARG_ENTRIES.to_string(),
+                Value::new(entries),
+            )]))
+        })
+    }
+}
+
+impl NodeMetaTrait for NodeListDir {
+    fn get_meta(&self) -> NodeMeta {
+        NodeMeta::new("list_dir", "0.1.0")
+            .with_input_arg(
+                Self::INPUT_ARG_PATH,
+                InputArgMeta::new::<String>().with_optional(true),
+            )
+            .with_output_arg(
+                Self::OUTPUT_ARG_ENTRIES,
+                OutputArgMeta::new::<Vec<String>>(),
+            )
+    }
+}
@@ -1,27 +1,74 @@
-use eyre::ContextCompat;
+use crate::*;
 use node::*;
 use std::collections::BTreeMap;
+use std::sync::Arc;
 
-pub struct NodeLLM {}
+pub struct NodeLLM {
+    providers: Arc<LlmProviders>,
+}
 
 impl NodeLLM {
     pub const INPUT_ARG_CONTEXT: &str = "context";
+    pub const INPUT_ARG_CONVERSATION: &str = "conversation";
     pub const OUTPUT_ARG_TEXT: &str = "text";
+    /// Name of the provider from [`LlmProviders`], default provider is used if not set
+    pub const MEMORY_PROVIDER: &str = "provider";
+    /// Optional model override
+    pub const MEMORY_MODEL: &str = "model";
+
+    pub fn new(providers: Arc<LlmProviders>) -> Self {
+        Self { providers }
+    }
+
+    /// Build conversation to send to the model.
+    ///
+    /// If both inputs are connected, `context` is appended to the conversation as a user message.
+    pub fn build_conversation(input: &InstanceRefArgs) -> eyre::Result<Conversation> {
+        let mut conversation = match input.get(Self::INPUT_ARG_CONVERSATION) {
+            Some(conversation) => conversation.downcast::<Conversation>()?.clone(),
+            None => Conversation::new(),
+        };
+
+        if let Some(context) = input.get(Self::INPUT_ARG_CONTEXT) {
+            conversation.push(ChatMessage::user(context.downcast::<String>()?.clone()));
+        }
+
+        if conversation.is_empty() {
+            return Err(eyre::eyre!(
+                "LLM node: either context or conversation input must be provided"
+            ));
+        }
+
+        Ok(conversation)
+    }
 }
 
 impl NodeTrait for NodeLLM {
     fn run<'a>(
         &'a self,
-        _instance: &'a NodeInstance,
-        _state: &'a Task,
+        instance: &'a NodeInstance,
+        ctx: &'a RunContext,
         input: &'a InstanceRefArgs,
     ) -> RunResult<'a> {
         Box::pin(async {
-            let _context = *input
-                .get(Self::INPUT_ARG_CONTEXT)
-                .context("Print node: missing input argument")?;
+            let provider = instance.get_memory::<String>(Self::MEMORY_PROVIDER)?;
+
+            let mut request = LlmRequest::new(Self::build_conversation(input)?);
+            if let Some(model) = instance.get_memory::<String>(Self::MEMORY_MODEL)? {
+                request = request.with_model(model.clone());
+            }
+
+            let response = self
+                .providers
+                .complete(provider.map(String::as_str), &request)
+                .await?;
+            ctx.record_usage(instance.instance_id, response.usage.unwrap_or_default());
+            ctx.record_retries(instance.instance_id, response.retries);
 
-            Ok(BTreeMap::default())
+            Ok(BTreeMap::from([(
+                Self::OUTPUT_ARG_TEXT.to_string(),
+                Value::new(response.text()),
+            )]))
         })
     }
 }
@@ -29,7 +76,14 @@ impl NodeTrait for NodeLLM {
 impl NodeMetaTrait for NodeLLM {
     fn get_meta(&self) -> NodeMeta {
         NodeMeta::new("llm", "0.1.0")
-            .with_input_arg(Self::INPUT_ARG_CONTEXT, InputArgMeta::new::<String>())
+            .with_input_arg(
+                Self::INPUT_ARG_CONTEXT,
+                InputArgMeta::new::<String>().with_optional(true),
+            )
+            .with_input_arg(
+                Self::INPUT_ARG_CONVERSATION,
+                InputArgMeta::new::<Conversation>().with_optional(true),
+            )
             .with_output_arg(Self::OUTPUT_ARG_TEXT, OutputArgMeta::new::<String>())
     }
 }
@@ -1,3 +1,23 @@
+mod embed_texts;
+mod glob_files;
+mod http_request;
+mod list_dir;
 mod llm;
+mod query_store;
+mod read_file;
+mod run_command;
+mod structured_output;
+mod upsert_store;
+mod write_file;
 
+pub use embed_texts::*;
+pub use glob_files::*;
+pub use http_request::*;
+pub use list_dir::*;
 pub use llm::*;
+pub use query_store::*;
+pub use read_file::*;
+pub use run_command::*;
+pub use structured_output::*;
+pub use upsert_store::*;
+pub use write_file::*;
@@ -0,0 +1,112 @@
+use crate::*;
+use eyre::{ContextCompat, WrapErr};
+use node::*;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+/// Find documents most similar to the query.
+///
+/// The store comes from the `store` input or, if it is not connected, is loaded from the file
+/// in [`NodeQueryStore::MEMORY_PATH`].
+pub struct NodeQueryStore {
+    embeddings: Arc<dyn EmbeddingProvider>,
+    sandbox: Arc<Sandbox>,
+}
+
+impl NodeQueryStore {
+    pub const INPUT_ARG_STORE: &str = "store";
+    pub const INPUT_ARG_QUERY: &str = "query";
+    /// Ranked documents, best first.
+    pub const OUTPUT_ARG_DOCUMENTS: &str = "documents";
+    /// Texts of the ranked documents.
+    pub const OUTPUT_ARG_TEXTS: &str = "texts";
+    pub const MEMORY_TOP_K: &str = "top_k";
+    /// Store file relative to the sandbox root.
+    pub const MEMORY_PATH: &str = "path";
+
+    const DEFAULT_TOP_K: usize = 4;
+
+    pub fn new(embeddings: Arc<dyn EmbeddingProvider>, sandbox: Arc<Sandbox>) -> Self {
+        Self {
+            embeddings,
+            sandbox,
+        }
+    }
+}
+
+impl NodeTrait for NodeQueryStore {
+    fn run<'a>(
+        &'a self,
+        instance: &'a NodeInstance,
+        ctx: &'a RunContext,
+        input: &'a InstanceRefArgs,
+    ) -> RunResult<'a> {
+        Box::pin(async {
+            let query = input
+                .get(Self::INPUT_ARG_QUERY)
+                .context("Query store node: missing query")?
+                .downcast::<String>()?;
+
+            let store = match input.get(Self::INPUT_ARG_STORE) {
+                Some(store) => store.downcast::<VectorStore>()?.clone(),
+                None => {
+                    let path = instance
+                        .get_memory_parsed::<String>(Self::MEMORY_PATH)?
+                        .context("Query store node: neither store nor path is set")?;
+                    let path = self
+                        .sandbox
+                        .resolve(&path)
+                        .wrap_err("Query store node: invalid path")?;
+
+                    VectorStore::load(&path).await?
+                }
+            };
+
+            let top_k = instance
+                .get_memory_parsed::<usize>(Self::MEMORY_TOP_K)?
+                .unwrap_or(Self::DEFAULT_TOP_K);
+
+            let texts = [query.clone()];
+            let embeddings = self.embeddings.embed(&texts).await?;
+            ctx.record_usage(instance.instance_id, embeddings.usage_or_estimate(&texts));
+
+            let embedding = embeddings
+                .vectors
+                .first()
+                .context("Query store node: query was not embedded")?;
+            let documents = if store.is_empty() {
+                Vec::new()
+            } else {
+                store.search(embedding, top_k)?
+            };
+            let texts = documents
+                .iter()
+                .map(|scored| scored.document.text.clone())
+                .collect::<Vec<_>>();
+
+            Ok(BTreeMap::from([
+                (
+                    Self::OUTPUT_ARG_DOCUMENTS.to_string(),
+                    Value::new(documents),
+                ),
+                (Self::OUTPUT_ARG_TEXTS.to_string(), Value::new(texts)),
+            ]))
+        })
+    }
+}
+
+impl NodeMetaTrait for NodeQueryStore {
+    fn get_meta(&self) -> NodeMeta {
+        NodeMeta::new("query_store", "0.1.0")
+            .with_input_arg(
+                Self::INPUT_ARG_STORE,
+                InputArgMeta::new::<VectorStore>().with_optional(true),
+            )
+            .with_input_arg(Self::INPUT_ARG_QUERY, InputArgMeta::new::<String>())
+            .with_output_arg(
+                Self::OUTPUT_ARG_DOCUMENTS,
+                OutputArgMeta::new::<Vec<ScoredDocument>>(),
+            )
+            .with_output_arg(Self::OUTPUT_ARG_TEXTS, OutputArgMeta::new::<Vec<String>>())
+    }
+}
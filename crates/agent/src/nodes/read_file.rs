@@ -0,0 +1,85 @@
+use crate::*;
+use eyre::WrapErr;
+use node::*;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+/// Read the file from the sandbox as text, or as bytes if [`NodeReadFile::MEMORY_BINARY`] is
+/// set.
+pub struct NodeReadFile {
+    sandbox: Arc<Sandbox>,
+}
+
+impl NodeReadFile {
+    /// Path relative to the sandbox root, taken from memory if the input is not connected.
+    pub const INPUT_ARG_PATH: &str = "path";
+    pub const OUTPUT_ARG_TEXT: &str = "text";
+    /// Replaces [`Self::OUTPUT_ARG_TEXT`] in binary mode.
+    pub const OUTPUT_ARG_BYTES: &str = "bytes";
+    pub const MEMORY_PATH: &str = "path";
+    pub const MEMORY_BINARY: &str = "binary";
+
+    pub fn new(sandbox: Arc<Sandbox>) -> Self {
+        Self { sandbox }
+    }
+
+    fn is_binary(instance: &NodeInstance) -> eyre::Result<bool> {
+        Ok(instance
+            .get_memory_parsed::<bool>(Self::MEMORY_BINARY)?
+            .unwrap_or(false))
+    }
+}
+
+impl NodeTrait for NodeReadFile {
+    fn run<'a>(
+        &'a self,
+        instance: &'a NodeInstance,
+        _ctx: &'a RunContext,
+        input: &'a InstanceRefArgs,
+    ) -> RunResult<'a> {
+        Box::pin(async {
+            let path = self
+                .sandbox
+                .path_arg(instance, input, Self::INPUT_ARG_PATH)
+                .wrap_err("Read file node: invalid path")?;
+
+            let bytes = tokio::fs::read(&path)
+                .await
+                .wrap_err_with(|| format!("Read file node: failed to read {}", path.display()))?;
+
+            let output = if Self::is_binary(instance)? {
+                (Self::OUTPUT_ARG_BYTES.to_string(), Value::new(bytes))
+            } else {
+                let text = String::from_utf8(bytes).wrap_err_with(|| {
+                    format!("Read file node: {} is not UTF-8 text", path.display())
+                })?;
+                (Self::OUTPUT_ARG_TEXT.to_string(), Value::new(text))
+            };
+
+            Ok(BTreeMap::from([output]))
+        })
+    }
+
+    fn instance_ports(&self, instance: &NodeInstance, ports: &mut NodePorts) -> eyre::Result<()> {
+        if Self::is_binary(instance)? {
+            ports.output_args.remove(Self::OUTPUT_ARG_TEXT);
+            ports.output_args.insert(
+                Self::OUTPUT_ARG_BYTES.to_string(),
+                OutputArgMeta::new::<Vec<u8>>(),
+            );
+        }
+
+        Ok(())
+    }
+}
+
+impl NodeMetaTrait for NodeReadFile {
+    fn get_meta(&self) -> NodeMeta {
+        NodeMeta::new("read_file", "0.1.0")
+            .with_input_arg(
+                Self::INPUT_ARG_PATH,
+                InputArgMeta::new::<String>().with_optional(true),
+            )
+            .with_output_arg(Self::OUTPUT_ARG_TEXT, OutputArgMeta::new::<String>())
+    }
+}
@@ -0,0 +1,280 @@
+use crate::*;
+use eyre::{ContextCompat, WrapErr};
+use node::*;
+use std::collections::{BTreeMap, BTreeSet};
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+
+/// Restrictions of commands executed by [`NodeRunCommand`].
+#[derive(Clone, Debug)]
+pub struct CommandPolicy {
+    /// Allowed programs, either names looked up in `PATH` or exact paths.
+    pub allowlist: BTreeSet<String>,
+    pub timeout: Duration,
+    /// Max amount of bytes kept of stdout and stderr each, the rest is dropped.
+    pub max_output_bytes: usize,
+    /// Environment variables passed to commands, all others are removed.
+    pub env: Vec<String>,
+    /// Working directories of commands are restricted to the sandbox.
+    pub sandbox: Arc<Sandbox>,
+}
+
+impl CommandPolicy {
+    pub fn from_config(config: &Config, sandbox: Arc<Sandbox>) -> Self {
+        Self {
+            allowlist: config.command_allowlist.iter().cloned().collect(),
+            timeout: config.command_timeout,
+            max_output_bytes: config.command_max_output_bytes,
+            env: config.command_env.clone(),
+            sandbox,
+        }
+    }
+
+    pub fn is_allowed(&self, program: &str) -> bool {
+        self.allowlist.contains(program)
+    }
+}
+
+/// Run an allowlisted program and return its output.
+///
+/// A non-zero exit code is returned as the output, not as an error. Commands exceeding the
+/// timeout are killed and fail the node.
+pub struct NodeRunCommand {
+    policy: Arc<CommandPolicy>,
+}
+
+impl NodeRunCommand {
+    /// Program to run, taken from memory if the input is not connected.
+    pub const INPUT_ARG_PROGRAM: &str = "program";
+    /// Arguments of the program, taken from memory if the input is not connected.
+    pub const INPUT_ARG_ARGS: &str = "args";
+    pub const INPUT_ARG_STDIN: &str = "stdin";
+    pub const OUTPUT_ARG_STDOUT: &str = "stdout";
+    pub const OUTPUT_ARG_STDERR: &str = "stderr";
+    pub const OUTPUT_ARG_EXIT_CODE: &str = "exit_code";
+    pub const MEMORY_PROGRAM: &str = "program";
+    pub const MEMORY_ARGS: &str = "args";
+    /// Working directory relative to the sandbox root, the root by default.
+    pub const MEMORY_CWD: &str = "cwd";
+
+    pub fn new(policy: Arc<CommandPolicy>) -> Self {
+        Self { policy }
+    }
+
+    /// Read the stream to the end, keeping at most `limit` bytes.
+    ///
+    /// Kept output is published as stream chunks of the output argument `arg` as it arrives.
+    async fn read_capped(
+        mut stream: impl AsyncRead + Unpin,
+        limit: usize,
+        ctx: &RunContext<'_>,
+        instance_id: NodeInstanceId,
+        arg: &str,
+    ) -> std::io::Result<(Vec<u8>, bool)> {
+        let mut output = Vec::new();
+        let mut truncated = false;
+        let mut buf = [0u8; 8192];
+        // start of the output not published yet, may end with an incomplete UTF-8 character
+        let mut published = 0;
+
+        loop {
+            let len = stream.read(&mut buf).await?;
+            if len == 0 {
+                if published < output.len() {
+                    let rest = String::from_utf8_lossy(&output[published..]);
+                    ctx.stream_chunk(instance_id, arg, rest);
+                }
+
+                return Ok((output, truncated));
+            }
+
+            let keep = len.min(limit - output.len());
+            output.extend_from_slice(&buf[..keep]);
+            truncated |= keep < len;
+
+            let pending = &output[published..];
+            let valid = match std::str::from_utf8(pending) {
+                Ok(_) => pending.len(),
+                Err(err) if err.error_len().is_none() => err.valid_up_to(),
+                // invalid bytes are published lossily
+                Err(_) => pending.len(),
+            };
+            if valid > 0 {
+                let chunk = String::from_utf8_lossy(&pending[..valid]);
+                ctx.stream_chunk(instance_id, arg, chunk);
+                published += valid;
+            }
+        }
+    }
+
+    async fn write_stdin(
+        stdin: Option<tokio::process::ChildStdin>,
+        input: &[u8],
+    ) -> std::io::Result<()> {
+        let Some(mut stdin) = stdin else {
+            return Ok(());
+        };
+
+        match stdin.write_all(input).await {
+            // the program does not read its input
+            Err(err) if err.kind() == std::io::ErrorKind::BrokenPipe => Ok(()),
+            result => result,
+        }
+    }
+
+    fn output_text(name: &str, (output, truncated): (Vec<u8>, bool)) -> String {
+        if truncated {
+            tracing::warn!(stream = name, "Command output truncated");
+        }
+
+        String::from_utf8_lossy(&output).into_owned()
+    }
+}
+
+impl NodeTrait for NodeRunCommand {
+    fn run<'a>(
+        &'a self,
+        instance: &'a NodeInstance,
+        ctx: &'a RunContext,
+        input: &'a InstanceRefArgs,
+    ) -> RunResult<'a> {
+        Box::pin(async {
+            let program = match input.get(Self::INPUT_ARG_PROGRAM) {
+                Some(program) => program.downcast::<String>()?.clone(),
+                None => instance
+                    .get_memory_parsed::<String>(Self::MEMORY_PROGRAM)?
+                    .context("Run command node: program is not set")?,
+            };
+            let args = match input.get(Self::INPUT_ARG_ARGS) {
+                Some(args) => args.downcast::<Vec<String>>()?.clone(),
+                None => instance
+                    .get_memory_parsed::<Vec<String>>(Self::MEMORY_ARGS)?
+                    .unwrap_or_default(),
+            };
+            let stdin = match input.get(Self::INPUT_ARG_STDIN) {
+                Some(stdin) => Some(stdin.downcast::<String>()?.as_bytes()),
+                None => None,
+            };
+
+            if !self.policy.is_allowed(&program) {
+                return Err(eyre::eyre!(
+                    "Run command node: program {program:?} is not allowed"
+                ));
+            }
+
+            let cwd = match instance.get_memory_parsed::<String>(Self::MEMORY_CWD)? {
+                Some(cwd) => self
+                    .policy
+                    .sandbox
+                    .resolve(&cwd)
+                    .wrap_err("Run command node: invalid working directory")?,
+                None => self.policy.sandbox.root().to_path_buf(),
+            };
+
+            let mut command = tokio::process::Command::new(&program);
+            command
+                .args(&args)
+                .current_dir(&cwd)
+                .env_clear()
+                .envs(
+                    self.policy
+                        .env
+                        .iter()
+                        .filter_map(|name| Some((name, std::env::var_os(name)?))),
+                )
+                .stdin(if stdin.is_some() {
+                    Stdio::piped()
+                } else {
+                    Stdio::null()
+                })
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                .kill_on_drop(true);
+
+            tracing::info!(program, ?args, cwd = %cwd.display(), "Running command");
+            let mut child = command
+                .spawn()
+                .wrap_err_with(|| format!("Run command node: failed to start {program:?}"))?;
+
+            let child_stdin = child.stdin.take();
+            let stdout = child.stdout.take().context("Stdout is not captured")?;
+            let stderr = child.stderr.take().context("Stderr is not captured")?;
+            let limit = self.policy.max_output_bytes;
+
+            let run = async {
+                tokio::try_join!(
+                    Self::write_stdin(child_stdin, stdin.unwrap_or_default()),
+                    Self::read_capped(
+                        stdout,
+                        limit,
+                        ctx,
+                        instance.instance_id,
+                        Self::OUTPUT_ARG_STDOUT,
+                    ),
+                    Self::read_capped(
+                        stderr,
+                        limit,
+                        ctx,
+                        instance.instance_id,
+                        Self::OUTPUT_ARG_STDERR,
+                    ),
+                    child.wait(),
+                )
+            };
+
+            let (_, stdout, stderr, status) =
+                match tokio::time::timeout(self.policy.timeout, run).await {
+                    Ok(result) => result.wrap_err("Run command node: failed to run the command")?,
+                    Err(_) => {
+                        child.start_kill().ok();
+                        return Err(eyre::eyre!(
+                            "Run command node: {program:?} timed out after {:?}",
+                            self.policy.timeout
+                        ));
+                    }
+                };
+
+            // killed by a signal
+            let exit_code = status.code().map_or(-1, i64::from);
+            tracing::info!(program, exit_code, "Command finished");
+
+            Ok(BTreeMap::from([
+                (
+                    Self::OUTPUT_ARG_STDOUT.to_string(),
+                    Value::new(Self::output_text("stdout", stdout)),
+                ),
+                (
+                    Self::OUTPUT_ARG_STDERR.to_string(),
+                    Value::new(Self::output_text("stderr", stderr)),
+                ),
+                (
+                    Self::OUTPUT_ARG_EXIT_CODE.to_string(),
+                    Value::new(exit_code),
+                ),
+            ]))
+        })
+    }
+}
+
+impl NodeMetaTrait for NodeRunCommand {
+    fn get_meta(&self) -> NodeMeta {
+        NodeMeta::new("run_command", "0.1.0")
+            .with_input_arg(
+                Self::INPUT_ARG_PROGRAM,
+                InputArgMeta::new::<String>().with_optional(true),
+            )
+            .with_input_arg(
+                Self::INPUT_ARG_ARGS,
+                InputArgMeta::new::<Vec<String>>().with_optional(true),
+            )
+            .with_input_arg(
+                Self::INPUT_ARG_STDIN,
+                InputArgMeta::new::<String>().with_optional(true),
+            )
+            .with_output_arg(Self::OUTPUT_ARG_STDOUT, OutputArgMeta::new::<String>())
+            .with_output_arg(Self::OUTPUT_ARG_STDERR, OutputArgMeta::new::<String>())
+            .with_output_arg(Self::OUTPUT_ARG_EXIT_CODE, OutputArgMeta::new::<i64>())
+    }
+}
@@ -0,0 +1,228 @@
+use crate::*;
+use eyre::{ContextCompat, WrapErr};
+use node::*;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+/// Ask the model for a JSON reply matching the schema stored in memory.
+///
+/// Invalid replies are sent back to the model together with the validation errors, up to
+/// [`NodeStructuredOutput::MEMORY_MAX_RETRIES`] times. Besides the whole document, every
+/// top-level property of the schema gets its own typed output argument.
+pub struct NodeStructuredOutput {
+    providers: Arc<LlmProviders>,
+}
+
+impl NodeStructuredOutput {
+    pub const INPUT_ARG_CONTEXT: &str = NodeLLM::INPUT_ARG_CONTEXT;
+    pub const INPUT_ARG_CONVERSATION: &str = NodeLLM::INPUT_ARG_CONVERSATION;
+    pub const OUTPUT_ARG_JSON: &str = "json";
+    pub const MEMORY_SCHEMA: &str = "schema";
+    pub const MEMORY_MAX_RETRIES: &str = "max_retries";
+    pub const MEMORY_PROVIDER: &str = NodeLLM::MEMORY_PROVIDER;
+    pub const MEMORY_MODEL: &str = NodeLLM::MEMORY_MODEL;
+
+    const DEFAULT_MAX_RETRIES: usize = 2;
+
+    pub fn new(providers: Arc<LlmProviders>) -> Self {
+        Self { providers }
+    }
+
+    /// Parse model reply, tolerating markdown code fences around the JSON.
+    fn parse_reply(reply: &str) -> Result<serde_json::Value, String> {
+        let reply = reply.trim();
+        let reply = reply
+            .strip_prefix("```json")
+            .or_else(|| reply.strip_prefix("```"))
+            .and_then(|reply| reply.strip_suffix("```"))
+            .unwrap_or(reply);
+
+        serde_json::from_str(reply).map_err(|err| format!("Reply is not valid JSON: {err}"))
+    }
+
+    fn validate(
+        validator: &jsonschema::Validator,
+        value: &serde_json::Value,
+    ) -> Result<(), String> {
+        let errors = validator
+            .iter_errors(value)
+            .map(|err| format!("{}: {err}", err.instance_path))
+            .collect::<Vec<_>>();
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors.join("\n"))
+        }
+    }
+
+    /// Output type for the top-level schema property.
+    fn property_type(schema: &serde_json::Value) -> ValueType {
+        match schema["type"].as_str() {
+            Some("string") => ValueType::new::<String>(),
+            Some("integer") => ValueType::new::<i64>(),
+            Some("number") => ValueType::new::<f64>(),
+            Some("boolean") => ValueType::new::<bool>(),
+            _ => ValueType::new::<serde_json::Value>(),
+        }
+    }
+
+    /// Value of the property converted to the type of [`Self::property_type`], so that the
+    /// type does not depend on the JSON literal, e.g. `42` for a `number` is still `f64`.
+    fn property_value(
+        name: &str,
+        schema: &serde_json::Value,
+        value: &serde_json::Value,
+    ) -> eyre::Result<Value> {
+        let converted = match schema["type"].as_str() {
+            Some("string") => value.as_str().map(|text| Value::new(text.to_string())),
+            // integers may be written as `3.0`, which is valid for the schema
+            Some("integer") => value
+                .as_i64()
+                .or_else(|| {
+                    value
+                        .as_f64()
+                        .filter(|number| number.fract() == 0.0)
+                        .map(|number| number as i64)
+                })
+                .map(Value::new),
+            Some("number") => value.as_f64().map(Value::new),
+            Some("boolean") => value.as_bool().map(Value::new),
+            _ => Some(Value::new(value.clone())),
+        };
+
+        converted.with_context(|| {
+            format!(
+                "Structured output node: property {name:?} does not match type {}",
+                schema["type"]
+            )
+        })
+    }
+
+    fn properties(
+        schema: &serde_json::Value,
+    ) -> impl Iterator<Item = (&String, &serde_json::Value)> {
+        schema["properties"]
+            .as_object()
+            .into_iter()
+            .flatten()
+            .filter(|(name, _)| name.as_str() != Self::OUTPUT_ARG_JSON)
+    }
+}
+
+impl NodeTrait for NodeStructuredOutput {
+    fn run<'a>(
+        &'a self,
+        instance: &'a NodeInstance,
+        ctx: &'a RunContext,
+        input: &'a InstanceRefArgs,
+    ) -> RunResult<'a> {
+        Box::pin(async {
+            let schema = instance
+                .get_memory_parsed::<serde_json::Value>(Self::MEMORY_SCHEMA)?
+                .context("Structured output node: schema is not set")?;
+            let max_retries = instance
+                .get_memory_parsed::<usize>(Self::MEMORY_MAX_RETRIES)?
+                .unwrap_or(Self::DEFAULT_MAX_RETRIES);
+
+            let provider = instance.get_memory::<String>(Self::MEMORY_PROVIDER)?;
+
+            let validator = jsonschema::validator_for(&schema)
+                .map_err(|err| eyre::eyre!("Structured output node: invalid schema: {err}"))?;
+
+            let mut conversation = NodeLLM::build_conversation(input)?;
+            let response_format = ResponseFormat::JsonSchema {
+                name: "response".to_string(),
+                schema: schema.clone(),
+            };
+
+            let mut attempt = 0;
+            let value = loop {
+                let mut request = LlmRequest::new(conversation.clone())
+                    .with_response_format(response_format.clone());
+                if let Some(model) = instance.get_memory::<String>(Self::MEMORY_MODEL)? {
+                    request = request.with_model(model.clone());
+                }
+
+                let response = self
+                    .providers
+                    .complete(provider.map(String::as_str), &request)
+                    .await?;
+                ctx.record_usage(instance.instance_id, response.usage.unwrap_or_default());
+                ctx.record_retries(instance.instance_id, response.retries);
+
+                let reply = response.text();
+
+                let error = match Self::parse_reply(&reply) {
+                    Ok(value) => match Self::validate(&validator, &value) {
+                        Ok(()) => break value,
+                        Err(error) => error,
+                    },
+                    Err(error) => error,
+                };
+
+                if attempt >= max_retries {
+                    return Err(eyre::eyre!(
+                        "Structured output node: reply does not match the schema after {} attempts:\n{error}",
+                        attempt + 1
+                    ));
+                }
+
+                tracing::warn!(attempt, "Invalid structured output, retrying:\n{error}");
+                attempt += 1;
+                ctx.record_retries(instance.instance_id, 1);
+
+                conversation.push(ChatMessage::assistant(reply));
+                conversation.push(ChatMessage::user(format!(
+                    "The reply does not match the required JSON schema:\n{error}\n\nReply with corrected JSON only."
+                )));
+            };
+
+            let mut output = BTreeMap::new();
+            for (name, property) in Self::properties(&schema) {
+                if let Some(field) = value.get(name) {
+                    output.insert(name.clone(), Self::property_value(name, property, field)?);
+                }
+            }
+            output.insert(Self::OUTPUT_ARG_JSON.to_string(), Value::new(value));
+
+            Ok(output)
+        })
+    }
+
+    fn instance_ports(&self, instance: &NodeInstance, ports: &mut NodePorts) -> eyre::Result<()> {
+        let Some(schema) = instance
+            .get_memory_parsed::<serde_json::Value>(Self::MEMORY_SCHEMA)
+            .wrap_err("Structured output node: invalid schema")?
+        else {
+            return Ok(());
+        };
+
+        for (name, property) in Self::properties(&schema) {
+            let arg = OutputArgMeta {
+                value_type: Self::property_type(property),
+            };
+            ports.output_args.insert(name.clone(), arg);
+        }
+
+        Ok(())
+    }
+}
+
+impl NodeMetaTrait for NodeStructuredOutput {
+    fn get_meta(&self) -> NodeMeta {
+        NodeMeta::new("structured_output", "0.1.0")
+            .with_input_arg(
+                Self::INPUT_ARG_CONTEXT,
+                InputArgMeta::new::<String>().with_optional(true),
+            )
+            .with_input_arg(
+                Self::INPUT_ARG_CONVERSATION,
+                InputArgMeta::new::<Conversation>().with_optional(true),
+            )
+            .with_output_arg(
+                Self::OUTPUT_ARG_JSON,
+                OutputArgMeta::new::<serde_json::Value>(),
+            )
+    }
+}
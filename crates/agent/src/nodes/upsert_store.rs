@@ -0,0 +1,142 @@
+use crate::*;
+use eyre::{ContextCompat, WrapErr};
+use node::*;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+/// Insert embedded texts into the vector store.
+///
+/// The store comes from the `store` input or, if it is not connected, is loaded from the file
+/// in [`NodeUpsertStore::MEMORY_PATH`] (a new store is created if the file does not exist).
+/// With the path set, the store is saved back after the upsert.
+pub struct NodeUpsertStore {
+    sandbox: Arc<Sandbox>,
+}
+
+impl NodeUpsertStore {
+    pub const INPUT_ARG_STORE: &str = "store";
+    pub const INPUT_ARG_TEXTS: &str = "texts";
+    pub const INPUT_ARG_EMBEDDINGS: &str = "embeddings";
+    /// Document ids, defaults to a hash of the text so the same text is stored once.
+    pub const INPUT_ARG_IDS: &str = "ids";
+    /// Array with metadata of every document or an object shared by all of them.
+    pub const INPUT_ARG_METADATA: &str = "metadata";
+    pub const OUTPUT_ARG_STORE: &str = "store";
+    /// Store file relative to the sandbox root.
+    pub const MEMORY_PATH: &str = "path";
+
+    pub fn new(sandbox: Arc<Sandbox>) -> Self {
+        Self { sandbox }
+    }
+
+    pub fn text_id(text: &str) -> String {
+        Sha256::digest(text.as_bytes())
+            .iter()
+            .take(8)
+            .map(|byte| format!("{byte:02x}"))
+            .collect()
+    }
+}
+
+impl NodeTrait for NodeUpsertStore {
+    fn run<'a>(
+        &'a self,
+        instance: &'a NodeInstance,
+        _ctx: &'a RunContext,
+        input: &'a InstanceRefArgs,
+    ) -> RunResult<'a> {
+        Box::pin(async {
+            let texts = input
+                .get(Self::INPUT_ARG_TEXTS)
+                .context("Upsert store node: missing texts")?
+                .downcast::<Vec<String>>()?;
+            let embeddings = input
+                .get(Self::INPUT_ARG_EMBEDDINGS)
+                .context("Upsert store node: missing embeddings")?
+                .downcast::<Vec<Vec<f32>>>()?;
+            let ids = match input.get(Self::INPUT_ARG_IDS) {
+                Some(ids) => ids.downcast::<Vec<String>>()?.clone(),
+                None => texts.iter().map(|text| Self::text_id(text)).collect(),
+            };
+            let metadata = input
+                .get(Self::INPUT_ARG_METADATA)
+                .map(|metadata| metadata.downcast::<serde_json::Value>())
+                .transpose()?;
+
+            if embeddings.len() != texts.len() || ids.len() != texts.len() {
+                return Err(eyre::eyre!(
+                    "Upsert store node: got {} texts, {} embeddings and {} ids",
+                    texts.len(),
+                    embeddings.len(),
+                    ids.len()
+                ));
+            }
+
+            let path = instance
+                .get_memory_parsed::<String>(Self::MEMORY_PATH)?
+                .map(|path| self.sandbox.resolve(&path))
+                .transpose()
+                .wrap_err("Upsert store node: invalid path")?;
+            // the connected store is shared with other consumers of the upstream output
+            let store = match (input.get(Self::INPUT_ARG_STORE), &path) {
+                (Some(store), _) => store.downcast::<VectorStore>()?.deep_clone(),
+                (None, Some(path)) if path.exists() => VectorStore::load(path).await?,
+                (None, _) => VectorStore::new(),
+            };
+
+            let documents = ids.into_iter().zip(texts).zip(embeddings).enumerate().map(
+                |(index, ((id, text), embedding))| {
+                    let metadata = match metadata {
+                        Some(serde_json::Value::Array(items)) => {
+                            items.get(index).cloned().unwrap_or_default()
+                        }
+                        Some(metadata) => metadata.clone(),
+                        None => serde_json::Value::Null,
+                    };
+
+                    (
+                        Document::new(id, text.clone()).with_metadata(metadata),
+                        embedding.clone(),
+                    )
+                },
+            );
+            store
+                .upsert_all(documents)
+                .wrap_err("Upsert store node: failed to upsert")?;
+
+            if let Some(path) = path {
+                store.save(&path).await?;
+            }
+
+            Ok(BTreeMap::from([(
+                Self::OUTPUT_ARG_STORE.to_string(),
+                Value::new(store),
+            )]))
+        })
+    }
+}
+
+impl NodeMetaTrait for NodeUpsertStore {
+    fn get_meta(&self) -> NodeMeta {
+        NodeMeta::new("upsert_store", "0.1.0")
+            .with_input_arg(
+                Self::INPUT_ARG_STORE,
+                InputArgMeta::new::<VectorStore>().with_optional(true),
+            )
+            .with_input_arg(Self::INPUT_ARG_TEXTS, InputArgMeta::new::<Vec<String>>())
+            .with_input_arg(
+                Self::INPUT_ARG_EMBEDDINGS,
+                InputArgMeta::new::<Vec<Vec<f32>>>(),
+            )
+            .with_input_arg(
+                Self::INPUT_ARG_IDS,
+                InputArgMeta::new::<Vec<String>>().with_optional(true),
+            )
+            .with_input_arg(
+                Self::INPUT_ARG_METADATA,
+                InputArgMeta::new::<serde_json::Value>().with_optional(true),
+            )
+            .with_output_arg(Self::OUTPUT_ARG_STORE, OutputArgMeta::new::<VectorStore>())
+    }
+}
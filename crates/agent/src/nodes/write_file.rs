@@ -0,0 +1,102 @@
+use crate::*;
+use eyre::WrapErr;
+use node::*;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use tokio::io::AsyncWriteExt;
+
+/// Write text or bytes to the file in the sandbox, parent directories are created.
+pub struct NodeWriteFile {
+    sandbox: Arc<Sandbox>,
+}
+
+impl NodeWriteFile {
+    /// Path relative to the sandbox root, taken from memory if the input is not connected.
+    pub const INPUT_ARG_PATH: &str = "path";
+    pub const INPUT_ARG_TEXT: &str = "text";
+    pub const INPUT_ARG_BYTES: &str = "bytes";
+    /// Path of the written file relative to the sandbox root.
+    pub const OUTPUT_ARG_PATH: &str = "path";
+    pub const MEMORY_PATH: &str = "path";
+    /// Append to the file instead of replacing it.
+    pub const MEMORY_APPEND: &str = "append";
+
+    pub fn new(sandbox: Arc<Sandbox>) -> Self {
+        Self { sandbox }
+    }
+}
+
+impl NodeTrait for NodeWriteFile {
+    fn run<'a>(
+        &'a self,
+        instance: &'a NodeInstance,
+        _ctx: &'a RunContext,
+        input: &'a InstanceRefArgs,
+    ) -> RunResult<'a> {
+        Box::pin(async {
+            let path = self
+                .sandbox
+                .path_arg(instance, input, Self::INPUT_ARG_PATH)
+                .wrap_err("Write file node: invalid path")?;
+
+            let content = match (
+                input.get(Self::INPUT_ARG_TEXT),
+                input.get(Self::INPUT_ARG_BYTES),
+            ) {
+                (Some(text), None) => text.downcast::<String>()?.as_bytes(),
+                (None, Some(bytes)) => bytes.downcast::<Vec<u8>>()?.as_slice(),
+                _ => {
+                    return Err(eyre::eyre!(
+                        "Write file node: exactly one of text or bytes must be connected"
+                    ))
+                }
+            };
+            let append = instance
+                .get_memory_parsed::<bool>(Self::MEMORY_APPEND)?
+                .unwrap_or(false);
+
+            if let Some(parent) = path.parent() {
+                tokio::fs::create_dir_all(parent).await.wrap_err_with(|| {
+                    format!("Write file node: failed to create {}", parent.display())
+                })?;
+            }
+
+            let mut file = tokio::fs::OpenOptions::new()
+                .create(true)
+                .write(true)
+                .append(append)
+                .truncate(!append)
+                .open(&path)
+                .await
+                .wrap_err_with(|| format!("Write file node: failed to open {}", path.display()))?;
+            file.write_all(content)
+                .await
+                .wrap_err_with(|| format!("Write file node: failed to write {}", path.display()))?;
+            file.flush().await?;
+
+            Ok(BTreeMap::from([(
+                Self::OUTPUT_ARG_PATH.to_string(),
+                Value::new(self.sandbox.relative(&path)?),
+            )]))
+        })
+    }
+}
+
+impl NodeMetaTrait for NodeWriteFile {
+    fn get_meta(&self) -> NodeMeta {
+        NodeMeta::new("write_file", "0.1.0")
+            .with_input_arg(
+                Self::INPUT_ARG_PATH,
+                InputArgMeta::new::<String>().with_optional(true),
+            )
+            .with_input_arg(
+                Self::INPUT_ARG_TEXT,
+                InputArgMeta::new::<String>().with_optional(true),
+            )
+            .with_input_arg(
+                Self::INPUT_ARG_BYTES,
+                InputArgMeta::new::<Vec<u8>>().with_optional(true),
+            )
+            .with_output_arg(Self::OUTPUT_ARG_PATH, OutputArgMeta::new::<String>())
+    }
+}
@@ -0,0 +1,70 @@
+use crate::*;
+use node::*;
+use std::sync::Arc;
+
+/// LLM and embedding providers used by the nodes, shared by all tasks registered with them so
+/// that rate limits apply across concurrent runs.
+#[derive(Clone)]
+pub struct NodeProviders {
+    pub llm: Arc<LlmProviders>,
+    /// Set only if the embeddings API is configured.
+    pub embeddings: Option<Arc<dyn EmbeddingProvider>>,
+}
+
+impl NodeProviders {
+    pub fn from_config(config: &Config) -> eyre::Result<Self> {
+        let embeddings = config.embeddings_provider().map(|provider| {
+            Arc::new(OpenAiEmbeddings::new(&provider)) as Arc<dyn EmbeddingProvider>
+        });
+
+        Ok(Self {
+            llm: Arc::new(LlmProviders::from_config(config)?),
+            embeddings,
+        })
+    }
+}
+
+/// Register built-in nodes and all agent nodes configured by `config`.
+///
+/// Embedding nodes are registered only if the embeddings API is configured.
+pub fn register_nodes(task: &mut Task, config: &Config) -> eyre::Result<()> {
+    register_nodes_with(task, config, &NodeProviders::from_config(config)?)
+}
+
+/// Same as [`register_nodes`], but with providers created once and shared between tasks.
+#[tracing::instrument(skip_all)]
+pub fn register_nodes_with(
+    task: &mut Task,
+    config: &Config,
+    providers: &NodeProviders,
+) -> eyre::Result<()> {
+    task.register_built_in_nodes()?;
+
+    task.register_node(NodeLLM::new(providers.llm.clone()))?;
+    task.register_node(NodeStructuredOutput::new(providers.llm.clone()))?;
+
+    let sandbox = Arc::new(Sandbox::new(&config.sandbox_root)?);
+
+    match &providers.embeddings {
+        Some(embeddings) => {
+            task.register_node(NodeEmbedTexts::new(embeddings.clone()))?;
+            task.register_node(NodeQueryStore::new(embeddings.clone(), sandbox.clone()))?;
+        }
+        None => tracing::debug!("Embeddings API is not configured, skipping embedding nodes"),
+    }
+    task.register_node(NodeUpsertStore::new(sandbox.clone()))?;
+
+    task.register_node(NodeReadFile::new(sandbox.clone()))?;
+    task.register_node(NodeWriteFile::new(sandbox.clone()))?;
+    task.register_node(NodeListDir::new(sandbox.clone()))?;
+    task.register_node(NodeGlobFiles::new(sandbox.clone()))?;
+    task.register_node(NodeRunCommand::new(Arc::new(CommandPolicy::from_config(
+        config, sandbox,
+    ))))?;
+
+    task.register_node(NodeHttpRequest::new(Arc::new(HttpPolicy::from_config(
+        config,
+    )))?)?;
+
+    Ok(())
+}
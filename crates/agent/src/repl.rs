@@ -0,0 +1,384 @@
+use crate::*;
+use eyre::{ContextCompat, WrapErr};
+use node::*;
+use rustyline::completion::{Completer, FilenameCompleter, Pair};
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::DefaultHistory;
+use rustyline::validate::Validator;
+use rustyline::{Context, Editor, Helper};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::Path;
+use std::sync::Arc;
+
+const COMMANDS: &[&str] = &[
+    "add",
+    "set",
+    "connect",
+    "disconnect",
+    "show",
+    "nodes",
+    "validate",
+    "run",
+    "save",
+    "load",
+    "help",
+    "exit",
+];
+
+const HELP: &str = "\
+Commands:
+  add NODE                     add an instance of the node
+  set INSTANCE NAME VALUE      set memory of the instance
+  connect FROM.OUTPUT TO.INPUT connect an output to an input
+  disconnect TO.INPUT          remove the connection of an input
+  show                         print instances with their memory and connections
+  nodes                        print registered nodes with their ports
+  validate                     check that all required inputs are connected
+  run                          run the graph and print the status of every instance
+  save PATH                    write the graph file
+  load PATH                    replace the graph with the graph file
+  exit                         leave the REPL
+Values are parsed as JSON, other text is used as a string.
+";
+
+/// Graph built command by command with the [`Task`] API, the state of `agent repl`.
+pub struct Repl {
+    config: Arc<Config>,
+    task: Task,
+    /// Inputs of the loaded graph file, written back on save.
+    inputs: BTreeMap<String, PortRef>,
+}
+
+impl Repl {
+    pub fn new(config: Arc<Config>) -> eyre::Result<Self> {
+        let mut task = Task::new();
+        register_nodes(&mut task, &config)?;
+
+        Ok(Self {
+            config,
+            task,
+            inputs: BTreeMap::new(),
+        })
+    }
+
+    pub fn task(&self) -> &Task {
+        &self.task
+    }
+
+    /// Execute a command line, returns the text to print.
+    pub async fn execute(&mut self, line: &str) -> eyre::Result<String> {
+        let mut words = line.split_whitespace();
+        let command = words.next().unwrap_or_default();
+        let args = words.collect::<Vec<_>>();
+        let mut output = String::new();
+
+        match (command, args.as_slice()) {
+            ("", _) => {}
+            ("add", [node_id]) => {
+                let instance_id = self.task.instantiate(&(*node_id).into())?;
+                writeln!(output, "Added {instance_id} ({node_id})")?;
+            }
+            ("set", [instance_id, name, ..]) => {
+                // the value is the rest of the line, it may contain spaces
+                let value = skip_words(line, 3).context("Usage: set INSTANCE NAME VALUE")?;
+                self.task.set_instance_memory_value(
+                    parse_instance_id(instance_id)?,
+                    name,
+                    parse_value(value),
+                )?;
+            }
+            ("connect", [from, to]) => {
+                let (from, output_arg) = parse_port(from)?;
+                let (to, input_arg) = parse_port(to)?;
+                self.task.connect(from, output_arg, to, input_arg)?;
+            }
+            ("disconnect", [to]) => {
+                let (to, input_arg) = parse_port(to)?;
+                self.task.disconnect(to, input_arg)?;
+            }
+            ("show", []) => self.show(&mut output)?,
+            ("nodes", []) => {
+                for node in self.task.nodes() {
+                    let inputs = node.input_args().keys().cloned().collect::<Vec<_>>();
+                    let outputs = node.output_args().keys().cloned().collect::<Vec<_>>();
+                    writeln!(
+                        output,
+                        "{}  in: {}  out: {}",
+                        node.id(),
+                        inputs.join(", "),
+                        outputs.join(", ")
+                    )?;
+                }
+            }
+            ("validate", []) => {
+                self.task.validate()?;
+                writeln!(output, "Graph is valid")?;
+            }
+            ("run", []) => {
+                let result = self.task.run(&RunBudget::unlimited()).await;
+                if let Some(report) = self.task.last_report() {
+                    for (instance_id, instance) in &report.instances {
+                        writeln!(
+                            output,
+                            "{instance_id} {} {}",
+                            instance.node_id, instance.status
+                        )?;
+                    }
+                }
+                if let Err(err) = result {
+                    writeln!(output, "Run failed: {err:#}")?;
+                }
+            }
+            ("save", [path]) => {
+                let mut graph = self.task.to_graph()?;
+                graph.inputs = self.inputs.clone();
+
+                std::fs::write(path, serde_json::to_string_pretty(&graph)?)
+                    .wrap_err_with(|| format!("Failed to write {path}"))?;
+                writeln!(output, "Saved {path}")?;
+            }
+            ("load", [path]) => {
+                self.load(Path::new(path))?;
+                writeln!(output, "Loaded {path}")?;
+            }
+            ("help", _) => output.push_str(HELP),
+            _ if COMMANDS.contains(&command) => {
+                eyre::bail!("Invalid arguments of {command:?}, try help")
+            }
+            _ => eyre::bail!("Unknown command {command:?}, try help"),
+        }
+
+        Ok(output)
+    }
+
+    /// Completion of the commands for the current graph.
+    pub fn completer(&self) -> ReplCompleter {
+        let instances = self
+            .task
+            .instances()
+            .into_iter()
+            .map(|instance| {
+                let ports = self
+                    .task
+                    .get_instance_ports(instance.instance_id)
+                    .unwrap_or_default();
+                let completion = InstanceCompletion {
+                    memory: instance.memory.keys().cloned().collect(),
+                    inputs: ports.input_args.into_keys().collect(),
+                    outputs: ports.output_args.into_keys().collect(),
+                };
+
+                (instance.instance_id, completion)
+            })
+            .collect();
+
+        let nodes = self
+            .task
+            .nodes()
+            .into_iter()
+            .map(|node| node.id().to_string())
+            .collect();
+
+        ReplCompleter {
+            nodes,
+            instances,
+            files: FilenameCompleter::new(),
+        }
+    }
+
+    fn show(&self, output: &mut String) -> eyre::Result<()> {
+        let instances = self.task.instances();
+        if instances.is_empty() {
+            writeln!(output, "Graph is empty")?;
+        }
+
+        for instance in instances {
+            writeln!(output, "{} {}", instance.instance_id, instance.node_id)?;
+            for (name, value) in &instance.memory {
+                writeln!(output, "  memory {name}{}", format_value(value))?;
+            }
+            for (arg_name, connection) in &instance.input_connections {
+                writeln!(
+                    output,
+                    "  in {arg_name} <- {}.{}",
+                    connection.instance, connection.arg_name
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn load(&mut self, path: &Path) -> eyre::Result<()> {
+        let text = std::fs::read_to_string(path)
+            .wrap_err_with(|| format!("Failed to read {}", path.display()))?;
+        let graph = serde_json::from_str::<TaskGraph>(&text)
+            .wrap_err_with(|| format!("Failed to parse {}", path.display()))?;
+
+        let mut task = Task::new();
+        register_nodes(&mut task, &self.config)?;
+        task.load_graph(&graph)
+            .wrap_err_with(|| format!("Failed to load {}", path.display()))?;
+
+        self.task = task;
+        self.inputs = graph.inputs;
+
+        Ok(())
+    }
+}
+
+fn parse_instance_id(text: &str) -> eyre::Result<NodeInstanceId> {
+    text.parse()
+        .map(NodeInstanceId)
+        .map_err(|_| eyre::eyre!("Invalid instance id {text:?}"))
+}
+
+/// Parse `INSTANCE.PORT`.
+fn parse_port(text: &str) -> eyre::Result<(NodeInstanceId, &str)> {
+    let (instance_id, port) = text
+        .split_once('.')
+        .with_context(|| format!("Expected INSTANCE.PORT, got {text:?}"))?;
+
+    Ok((parse_instance_id(instance_id)?, port))
+}
+
+/// Names completed for an instance.
+#[derive(Clone, Debug, Default)]
+struct InstanceCompletion {
+    memory: Vec<String>,
+    inputs: Vec<String>,
+    outputs: Vec<String>,
+}
+
+/// Tab completion of commands, node ids, instance ids and ports.
+pub struct ReplCompleter {
+    nodes: Vec<String>,
+    instances: BTreeMap<NodeInstanceId, InstanceCompletion>,
+    files: FilenameCompleter,
+}
+
+impl ReplCompleter {
+    /// Start of the completed word and the candidates for the cursor at `pos`.
+    pub fn candidates(&self, line: &str, pos: usize) -> (usize, Vec<String>) {
+        let line = &line[..pos];
+        let start = line.rfind(char::is_whitespace).map_or(0, |space| space + 1);
+        let word = &line[start..];
+        let args = line[..start].split_whitespace().collect::<Vec<_>>();
+
+        let candidates = match args.as_slice() {
+            [] => COMMANDS.iter().map(|command| command.to_string()).collect(),
+            ["add"] => self.nodes.clone(),
+            ["set"] => self.instances.keys().map(ToString::to_string).collect(),
+            ["set", instance_id] => parse_instance_id(instance_id)
+                .ok()
+                .and_then(|instance_id| self.instances.get(&instance_id))
+                .map(|instance| instance.memory.clone())
+                .unwrap_or_default(),
+            ["connect"] => self.ports(word, |instance| &instance.outputs),
+            ["connect", _] | ["disconnect"] => self.ports(word, |instance| &instance.inputs),
+            _ => Vec::new(),
+        };
+
+        let candidates = candidates
+            .into_iter()
+            .filter(|candidate| candidate.starts_with(word))
+            .collect();
+
+        (start, candidates)
+    }
+
+    /// `INSTANCE.` of instances with ports, or `INSTANCE.PORT` once the instance is typed.
+    fn ports(
+        &self,
+        word: &str,
+        ports: impl Fn(&InstanceCompletion) -> &Vec<String>,
+    ) -> Vec<String> {
+        match word.split_once('.') {
+            Some((instance_id, _)) => parse_instance_id(instance_id)
+                .ok()
+                .and_then(|instance_id| self.instances.get(&instance_id))
+                .map(|instance| {
+                    ports(instance)
+                        .iter()
+                        .map(|port| format!("{instance_id}.{port}"))
+                        .collect()
+                })
+                .unwrap_or_default(),
+            None => self
+                .instances
+                .iter()
+                .filter(|(_, instance)| !ports(instance).is_empty())
+                .map(|(instance_id, _)| format!("{instance_id}."))
+                .collect(),
+        }
+    }
+}
+
+impl Completer for ReplCompleter {
+    type Candidate = Pair;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<Pair>)> {
+        let command = line.split_whitespace().next();
+        if matches!(command, Some("save" | "load")) && line[..pos].contains(char::is_whitespace) {
+            return self.files.complete_path(line, pos);
+        }
+
+        let (start, candidates) = self.candidates(line, pos);
+        let candidates = candidates
+            .into_iter()
+            .map(|candidate| Pair {
+                display: candidate.clone(),
+                replacement: candidate,
+            })
+            .collect();
+
+        Ok((start, candidates))
+    }
+}
+
+impl Hinter for ReplCompleter {
+    type Hint = String;
+}
+
+impl Highlighter for ReplCompleter {}
+
+impl Validator for ReplCompleter {}
+
+impl Helper for ReplCompleter {}
+
+/// Read commands from the terminal until `exit` or the end of the input.
+pub async fn run_repl(config: Arc<Config>) -> eyre::Result<()> {
+    let mut repl = Repl::new(config)?;
+    let mut editor = Editor::<ReplCompleter, DefaultHistory>::new()?;
+
+    loop {
+        editor.set_helper(Some(repl.completer()));
+
+        // reading blocks the thread, runs are awaited between the commands
+        let line = match editor.readline("agent> ") {
+            Ok(line) => line,
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => break,
+            Err(err) => return Err(err.into()),
+        };
+        editor.add_history_entry(line.as_str())?;
+
+        if matches!(line.trim(), "exit" | "quit") {
+            break;
+        }
+        match repl.execute(&line).await {
+            Ok(output) => print!("{output}"),
+            Err(err) => eprintln!("Error: {err:#}"),
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,108 @@
+use eyre::{ContextCompat, WrapErr};
+use node::*;
+use std::path::{Component, Path, PathBuf};
+
+/// Directory file system nodes are restricted to.
+///
+/// Paths are always relative to the root, so graphs are portable between machines. Paths
+/// escaping the root with `..` or through symlinks are rejected.
+#[derive(Clone, Debug)]
+pub struct Sandbox {
+    root: PathBuf,
+}
+
+impl Sandbox {
+    /// Create the sandbox, the root directory is created if it does not exist.
+    pub fn new(root: impl AsRef<Path>) -> eyre::Result<Self> {
+        let root = root.as_ref();
+        std::fs::create_dir_all(root)
+            .wrap_err_with(|| format!("Failed to create sandbox root {}", root.display()))?;
+        let root = root
+            .canonicalize()
+            .wrap_err_with(|| format!("Failed to resolve sandbox root {}", root.display()))?;
+
+        Ok(Self { root })
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Absolute path of the sandbox relative `path`.
+    #[tracing::instrument(skip(self))]
+    pub fn resolve(&self, path: &str) -> eyre::Result<PathBuf> {
+        let mut relative = PathBuf::new();
+        for component in Path::new(path).components() {
+            match component {
+                Component::Normal(part) => relative.push(part),
+                Component::CurDir => {}
+                Component::ParentDir => {
+                    if !relative.pop() {
+                        return Err(eyre::eyre!("Path {path:?} escapes the sandbox"));
+                    }
+                }
+                Component::RootDir | Component::Prefix(_) => {
+                    return Err(eyre::eyre!(
+                        "Path {path:?} must be relative to the sandbox root"
+                    ));
+                }
+            }
+        }
+
+        // resolve symlinks of the longest existing prefix, the rest does not exist yet
+        let mut existing = self.root.join(&relative);
+        let mut missing = Vec::new();
+        let resolved = loop {
+            match existing.canonicalize() {
+                Ok(resolved) => break resolved,
+                Err(_) if existing.symlink_metadata().is_ok() => {
+                    return Err(eyre::eyre!("Path {path:?} is a dangling symlink"));
+                }
+                Err(_) => {
+                    missing.push(
+                        existing
+                            .file_name()
+                            .context("Sandbox root does not exist")?
+                            .to_owned(),
+                    );
+                    existing.pop();
+                }
+            }
+        };
+
+        if !resolved.starts_with(&self.root) {
+            return Err(eyre::eyre!("Path {path:?} escapes the sandbox"));
+        }
+
+        Ok(missing
+            .into_iter()
+            .rev()
+            .fold(resolved, |path, part| path.join(part)))
+    }
+
+    /// Path relative to the root, as accepted by [`Self::resolve`].
+    pub fn relative(&self, path: &Path) -> eyre::Result<String> {
+        let relative = path
+            .strip_prefix(&self.root)
+            .wrap_err_with(|| format!("Path {} is outside of the sandbox", path.display()))?;
+
+        Ok(relative.to_string_lossy().into_owned())
+    }
+
+    /// Path from the input argument or, if it is not connected, from the instance memory.
+    pub fn path_arg(
+        &self,
+        instance: &NodeInstance,
+        input: &InstanceRefArgs,
+        arg_name: &str,
+    ) -> eyre::Result<PathBuf> {
+        let path = match input.get(arg_name) {
+            Some(path) => path.downcast::<String>()?.clone(),
+            None => instance
+                .get_memory_parsed::<String>(arg_name)?
+                .with_context(|| format!("Neither input nor memory {arg_name:?} is set"))?,
+        };
+
+        self.resolve(&path)
+    }
+}
@@ -0,0 +1,7 @@
+mod auth;
+mod routes;
+mod runs;
+
+pub use auth::*;
+pub use routes::*;
+pub use runs::*;
@@ -0,0 +1,350 @@
+use crate::*;
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use eyre::WrapErr;
+use metrics_exporter_prometheus::PrometheusHandle;
+use node::*;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio::sync::broadcast;
+
+/// Error response of the API, serialized as `{"error": "..."}`.
+#[derive(Debug)]
+pub struct ApiError {
+    pub status: StatusCode,
+    pub message: String,
+}
+
+impl ApiError {
+    pub fn new(status: StatusCode, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            message: message.into(),
+        }
+    }
+
+    fn not_found(what: impl std::fmt::Display) -> Self {
+        Self::new(StatusCode::NOT_FOUND, format!("{what} not found"))
+    }
+
+    fn invalid(err: eyre::Report) -> Self {
+        Self::new(StatusCode::UNPROCESSABLE_ENTITY, format!("{err:#}"))
+    }
+}
+
+/// Unexpected errors are only logged, their chain may contain paths or provider responses.
+impl From<eyre::Report> for ApiError {
+    fn from(err: eyre::Report) -> Self {
+        tracing::error!("Request failed: {err:#}");
+        Self::new(StatusCode::INTERNAL_SERVER_ERROR, "Internal server error")
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let body = Json(serde_json::json!({ "error": self.message }));
+        (self.status, body).into_response()
+    }
+}
+
+#[derive(Clone)]
+pub struct ServerState {
+    pub(crate) config: Arc<Config>,
+    pub(crate) token: String,
+    /// Shared by all runs, so the rate limits of the providers apply across them.
+    providers: NodeProviders,
+    graphs: Arc<RwLock<BTreeMap<String, TaskGraph>>>,
+    runs: RunStore,
+    worker: RunWorker,
+    metrics: Option<PrometheusHandle>,
+}
+
+/// Request to start a run of the uploaded graph.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct RunRequest {
+    /// Values of the graph inputs, strings are passed as `String`, other values as JSON.
+    pub inputs: BTreeMap<String, serde_json::Value>,
+    pub max_tokens: Option<u64>,
+    pub max_cost: Option<f64>,
+    /// Max duration of the run in seconds.
+    pub timeout: Option<f64>,
+}
+
+impl RunRequest {
+    fn budget(&self) -> eyre::Result<RunBudget> {
+        let mut budget = RunBudget::unlimited();
+        if let Some(max_tokens) = self.max_tokens {
+            budget = budget.with_max_tokens(max_tokens);
+        }
+        if let Some(max_cost) = self.max_cost {
+            budget = budget.with_max_cost(max_cost);
+        }
+        if let Some(timeout) = self.timeout {
+            budget = budget.with_max_duration(
+                Duration::try_from_secs_f64(timeout).wrap_err("Invalid timeout")?,
+            );
+        }
+
+        Ok(budget)
+    }
+}
+
+/// HTTP API to upload graphs, start runs and watch their events.
+///
+/// All routes require the bearer token from [`Config::server_token`]:
+///
+/// - `GET /graphs`, `GET /graphs/{name}`, `PUT /graphs/{name}` - list, get and upload graphs
+/// - `POST /graphs/{name}/runs` - start a run with [`RunRequest`], returns the run id
+/// - `GET /runs`, `GET /runs/{id}` - list runs, get the run with its report
+/// - `GET /runs/{id}/events` - WebSocket with all [`RunEvent`]s of the run as JSON, closed when
+///   the run finishes
+/// - `GET /metrics` - metrics in the Prometheus text format, if [`Config::server_metrics`] is
+///   enabled
+pub struct Server {
+    state: ServerState,
+}
+
+impl Server {
+    pub fn new(config: Arc<Config>) -> eyre::Result<Self> {
+        let token = config
+            .server_token
+            .clone()
+            .filter(|token| !token.is_empty())
+            .ok_or_else(|| eyre::eyre!("AGENT_SERVER_TOKEN must be set to start the server"))?;
+
+        let metrics = config
+            .server_metrics
+            .then(prometheus_recorder)
+            .transpose()?;
+        let providers = NodeProviders::from_config(&config)?;
+        let runs = RunStore::default();
+        let worker = RunWorker::spawn(runs.clone())?;
+
+        Ok(Self {
+            state: ServerState {
+                config,
+                token,
+                providers,
+                graphs: Arc::default(),
+                runs,
+                worker,
+                metrics,
+            },
+        })
+    }
+
+    pub fn router(&self) -> Router {
+        let mut router = Router::new();
+        if self.state.metrics.is_some() {
+            router = router.route("/metrics", get(render_metrics));
+        }
+
+        router
+            .route("/graphs", get(list_graphs))
+            .route("/graphs/{name}", get(get_graph).put(put_graph))
+            .route("/graphs/{name}/runs", axum::routing::post(start_run))
+            .route("/runs", get(list_runs))
+            .route("/runs/{id}", get(get_run))
+            .route("/runs/{id}/events", get(run_events))
+            .layer(axum::middleware::from_fn_with_state(
+                self.state.clone(),
+                require_token,
+            ))
+            .with_state(self.state.clone())
+    }
+
+    pub async fn serve(self, listener: tokio::net::TcpListener) -> eyre::Result<()> {
+        tracing::info!(addr = %listener.local_addr()?, "Server started");
+        axum::serve(listener, self.router())
+            .await
+            .wrap_err("Server failed")
+    }
+}
+
+/// Task with all nodes registered and the graph loaded.
+fn build_task(state: &ServerState, graph: &TaskGraph) -> Result<Task, ApiError> {
+    let mut task = Task::new();
+    register_nodes_with(&mut task, &state.config, &state.providers)?;
+    task.load_graph(graph).map_err(ApiError::invalid)?;
+
+    Ok(task)
+}
+
+fn get_stored_graph(state: &ServerState, name: &str) -> Result<TaskGraph, ApiError> {
+    state
+        .graphs
+        .read()
+        .expect("graphs lock poisoned")
+        .get(name)
+        .cloned()
+        .ok_or_else(|| ApiError::not_found(format!("Graph {name:?}")))
+}
+
+async fn list_graphs(State(state): State<ServerState>) -> Json<serde_json::Value> {
+    let graphs = state.graphs.read().expect("graphs lock poisoned");
+
+    Json(
+        graphs
+            .iter()
+            .map(|(name, graph)| {
+                serde_json::json!({
+                    "name": name,
+                    "instances": graph.instances.len(),
+                    "inputs": graph.inputs.keys().collect::<Vec<_>>(),
+                })
+            })
+            .collect(),
+    )
+}
+
+/// Graph with effective ports of the instances.
+async fn get_graph(
+    State(state): State<ServerState>,
+    Path(name): Path<String>,
+) -> Result<Json<TaskGraph>, ApiError> {
+    let graph = get_stored_graph(&state, &name)?;
+
+    let mut described = build_task(&state, &graph)?.to_graph()?;
+    described.inputs = graph.inputs;
+
+    Ok(Json(described))
+}
+
+async fn put_graph(
+    State(state): State<ServerState>,
+    Path(name): Path<String>,
+    Json(graph): Json<TaskGraph>,
+) -> Result<impl IntoResponse, ApiError> {
+    let is_valid_name = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if !is_valid_name {
+        return Err(ApiError::new(
+            StatusCode::BAD_REQUEST,
+            format!("Invalid graph name {name:?}"),
+        ));
+    }
+
+    // inputs may be required, so only check that the graph can be loaded
+    build_task(&state, &graph)?;
+
+    let is_new = state
+        .graphs
+        .write()
+        .expect("graphs lock poisoned")
+        .insert(name.clone(), graph)
+        .is_none();
+    tracing::info!(name, "Graph uploaded");
+
+    let status = if is_new {
+        StatusCode::CREATED
+    } else {
+        StatusCode::OK
+    };
+    Ok((status, Json(serde_json::json!({ "name": name }))))
+}
+
+async fn start_run(
+    State(state): State<ServerState>,
+    Path(name): Path<String>,
+    Json(request): Json<RunRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    let graph = get_stored_graph(&state, &name)?;
+    let mut task = build_task(&state, &graph)?;
+
+    let inputs = request
+        .inputs
+        .iter()
+        .map(|(name, json)| (name.clone(), Value::from_json(json.clone())))
+        .collect();
+    task.set_graph_inputs(&graph, inputs)
+        .map_err(ApiError::invalid)?;
+    task.validate().map_err(ApiError::invalid)?;
+    let budget = request.budget().map_err(ApiError::invalid)?;
+
+    let id = state.worker.start(&name, task, budget)?;
+    tracing::info!(graph = name, run_id = id, "Run started");
+
+    Ok((StatusCode::ACCEPTED, Json(serde_json::json!({ "id": id }))))
+}
+
+async fn list_runs(State(state): State<ServerState>) -> Json<Vec<RunSummary>> {
+    Json(state.runs.list())
+}
+
+async fn get_run(
+    State(state): State<ServerState>,
+    Path(id): Path<u64>,
+) -> Result<Json<RunSummary>, ApiError> {
+    state
+        .runs
+        .get(id)
+        .map(Json)
+        .ok_or_else(|| ApiError::not_found(format!("Run {id}")))
+}
+
+async fn render_metrics(State(state): State<ServerState>) -> Result<String, ApiError> {
+    let handle = state
+        .metrics
+        .as_ref()
+        .ok_or_else(|| ApiError::not_found("Metrics"))?;
+    handle.run_upkeep();
+
+    Ok(handle.render())
+}
+
+async fn run_events(
+    State(state): State<ServerState>,
+    Path(id): Path<u64>,
+    upgrade: WebSocketUpgrade,
+) -> Result<Response, ApiError> {
+    let (history, live) = state
+        .runs
+        .subscribe(id)
+        .ok_or_else(|| ApiError::not_found(format!("Run {id}")))?;
+
+    Ok(upgrade.on_upgrade(move |socket| stream_events(socket, history, live)))
+}
+
+async fn stream_events(
+    mut socket: WebSocket,
+    history: Vec<RunEvent>,
+    mut live: Option<broadcast::Receiver<RunEvent>>,
+) {
+    for event in &history {
+        if send_event(&mut socket, event).await.is_err() {
+            return;
+        }
+    }
+
+    while let Some(receiver) = &mut live {
+        match receiver.recv().await {
+            Ok(event) => {
+                if send_event(&mut socket, &event).await.is_err() {
+                    return;
+                }
+            }
+            Err(broadcast::error::RecvError::Lagged(missed)) => {
+                tracing::warn!(missed, "WebSocket subscriber missed run events");
+            }
+            Err(broadcast::error::RecvError::Closed) => live = None,
+        }
+    }
+
+    let _ = socket.send(Message::Close(None)).await;
+}
+
+async fn send_event(socket: &mut WebSocket, event: &RunEvent) -> eyre::Result<()> {
+    let json = serde_json::to_string(event)?;
+    socket.send(Message::Text(json.into())).await?;
+
+    Ok(())
+}
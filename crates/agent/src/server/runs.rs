@@ -0,0 +1,225 @@
+use node::*;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::{broadcast, mpsc};
+
+/// Finished runs kept in memory, older ones are dropped.
+const MAX_FINISHED_RUNS: usize = 100;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RunStatus {
+    Running,
+    Succeeded,
+    Failed,
+}
+
+/// Run as returned by the API.
+#[derive(Clone, Debug, Serialize)]
+pub struct RunSummary {
+    pub id: u64,
+    pub graph: String,
+    pub status: RunStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub report: Option<RunReport>,
+}
+
+struct RunRecord {
+    graph: String,
+    status: RunStatus,
+    report: Option<RunReport>,
+    /// All events of the run, replayed to late subscribers.
+    events: Vec<RunEvent>,
+    /// Live events, dropped when the run finishes.
+    live: Option<broadcast::Sender<RunEvent>>,
+}
+
+/// Runs started by the server with their events and reports.
+#[derive(Clone, Default)]
+pub struct RunStore {
+    runs: Arc<Mutex<BTreeMap<u64, RunRecord>>>,
+    next_id: Arc<AtomicU64>,
+}
+
+impl RunStore {
+    fn create(&self, graph: &str) -> u64 {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+
+        self.runs.lock().expect("runs lock poisoned").insert(
+            id,
+            RunRecord {
+                graph: graph.to_string(),
+                status: RunStatus::Running,
+                report: None,
+                events: Vec::new(),
+                live: Some(broadcast::Sender::new(RUN_EVENTS_CAPACITY)),
+            },
+        );
+
+        id
+    }
+
+    fn push_event(&self, id: u64, event: RunEvent) {
+        let mut runs = self.runs.lock().expect("runs lock poisoned");
+        let Some(run) = runs.get_mut(&id) else {
+            return;
+        };
+
+        if let Some(live) = &run.live {
+            let _ = live.send(event.clone());
+        }
+        run.events.push(event);
+    }
+
+    fn finish(&self, id: u64, report: Option<RunReport>, is_success: bool) {
+        let mut runs = self.runs.lock().expect("runs lock poisoned");
+
+        if let Some(run) = runs.get_mut(&id) {
+            run.status = if is_success {
+                RunStatus::Succeeded
+            } else {
+                RunStatus::Failed
+            };
+            run.report = report;
+            // closes subscriptions
+            run.live = None;
+        }
+
+        let finished = runs
+            .iter()
+            .filter(|(_, run)| run.status != RunStatus::Running)
+            .map(|(id, _)| *id)
+            .collect::<Vec<_>>();
+        for id in finished
+            .iter()
+            .take(finished.len().saturating_sub(MAX_FINISHED_RUNS))
+        {
+            runs.remove(id);
+        }
+    }
+
+    pub fn get(&self, id: u64) -> Option<RunSummary> {
+        let runs = self.runs.lock().expect("runs lock poisoned");
+
+        runs.get(&id).map(|run| RunSummary {
+            id,
+            graph: run.graph.clone(),
+            status: run.status,
+            report: run.report.clone(),
+        })
+    }
+
+    /// Summaries without reports, oldest first.
+    pub fn list(&self) -> Vec<RunSummary> {
+        let runs = self.runs.lock().expect("runs lock poisoned");
+
+        runs.iter()
+            .map(|(id, run)| RunSummary {
+                id: *id,
+                graph: run.graph.clone(),
+                status: run.status,
+                report: None,
+            })
+            .collect()
+    }
+
+    /// Events emitted so far and the receiver of the following ones, `None` for the receiver
+    /// if the run has finished.
+    pub fn subscribe(
+        &self,
+        id: u64,
+    ) -> Option<(Vec<RunEvent>, Option<broadcast::Receiver<RunEvent>>)> {
+        let runs = self.runs.lock().expect("runs lock poisoned");
+        let run = runs.get(&id)?;
+
+        Some((
+            run.events.clone(),
+            run.live.as_ref().map(broadcast::Sender::subscribe),
+        ))
+    }
+}
+
+struct RunJob {
+    id: u64,
+    task: Task,
+    budget: RunBudget,
+}
+
+/// Executes runs on a dedicated thread, since futures of nodes are not `Send`.
+#[derive(Clone)]
+pub struct RunWorker {
+    runs: RunStore,
+    jobs: mpsc::UnboundedSender<RunJob>,
+}
+
+impl RunWorker {
+    pub fn spawn(runs: RunStore) -> eyre::Result<Self> {
+        let (jobs, mut receiver) = mpsc::unbounded_channel::<RunJob>();
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?;
+
+        let worker_runs = runs.clone();
+        std::thread::Builder::new()
+            .name("agent-runs".to_string())
+            .spawn(move || {
+                let local = tokio::task::LocalSet::new();
+                local.block_on(&runtime, async move {
+                    while let Some(job) = receiver.recv().await {
+                        tokio::task::spawn_local(Self::execute(worker_runs.clone(), job));
+                    }
+                });
+            })?;
+
+        Ok(Self { runs, jobs })
+    }
+
+    /// Start the run in background, returns its id.
+    pub fn start(&self, graph: &str, task: Task, budget: RunBudget) -> eyre::Result<u64> {
+        let id = self.runs.create(graph);
+
+        if self.jobs.send(RunJob { id, task, budget }).is_err() {
+            self.runs.finish(id, None, false);
+            return Err(eyre::eyre!("Run worker is stopped"));
+        }
+
+        Ok(id)
+    }
+
+    #[tracing::instrument(skip_all, fields(run_id = job.id))]
+    async fn execute(runs: RunStore, job: RunJob) {
+        let RunJob { id, task, budget } = job;
+
+        let mut events = task.subscribe();
+        let forward_runs = runs.clone();
+        let forward = tokio::task::spawn_local(async move {
+            loop {
+                match events.recv().await {
+                    Ok(mut event) => {
+                        // ids of the server runs instead of ids of the task runs
+                        event.run_id = id;
+                        forward_runs.push_event(id, event);
+                    }
+                    Err(broadcast::error::RecvError::Lagged(missed)) => {
+                        tracing::warn!(missed, "Run events dropped");
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        });
+
+        let result = task.run(&budget).await;
+        if let Err(err) = &result {
+            tracing::warn!("Run failed: {err:#}");
+        }
+        let report = task.last_report();
+
+        // closes the event channel once all events are forwarded
+        drop(task);
+        let _ = forward.await;
+
+        runs.finish(id, report, result.is_ok());
+    }
+}
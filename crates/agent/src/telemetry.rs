@@ -0,0 +1,175 @@
+use metrics_exporter_prometheus::{PrometheusBuilder, PrometheusHandle};
+use metrics_util::debugging::{DebugValue, Snapshot};
+use node::*;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::OnceLock;
+
+/// LLM responses replayed from fixtures by `provider`, see [`CachedProvider`](crate::CachedProvider).
+pub const METRIC_LLM_CACHE_HITS: &str = "agent_llm_cache_hits_total";
+/// LLM requests without a fixture by `provider`, recorded or failed depending on the cache mode.
+pub const METRIC_LLM_CACHE_MISSES: &str = "agent_llm_cache_misses_total";
+
+/// Register descriptions of all metrics of the agent, call after installing the recorder.
+pub fn describe_agent_metrics() {
+    describe_metrics();
+    metrics::describe_counter!(
+        METRIC_LLM_CACHE_HITS,
+        "LLM responses replayed from fixtures"
+    );
+    metrics::describe_counter!(METRIC_LLM_CACHE_MISSES, "LLM requests without a fixture");
+}
+
+/// Install the Prometheus recorder as the global metrics recorder on the first call, the
+/// following calls return the same handle.
+pub fn prometheus_recorder() -> eyre::Result<PrometheusHandle> {
+    static HANDLE: OnceLock<PrometheusHandle> = OnceLock::new();
+
+    if let Some(handle) = HANDLE.get() {
+        return Ok(handle.clone());
+    }
+
+    let recorder = PrometheusBuilder::new().build_recorder();
+    let handle = recorder.handle();
+    metrics::set_global_recorder(recorder)
+        .map_err(|err| eyre::eyre!("Failed to install Prometheus recorder: {err}"))?;
+    describe_agent_metrics();
+
+    Ok(HANDLE.get_or_init(|| handle).clone())
+}
+
+/// Metrics of a node, aggregated over all its instances.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct NodeMetrics {
+    pub executions: u64,
+    pub errors: u64,
+    pub retries: u64,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    /// Durations of executions in seconds, sorted.
+    pub durations: Vec<f64>,
+}
+
+impl NodeMetrics {
+    /// Duration below which `percentile` of the executions finished, nearest rank.
+    pub fn duration_percentile(&self, percentile: f64) -> Option<f64> {
+        if self.durations.is_empty() {
+            return None;
+        }
+
+        let rank = (percentile / 100.0 * self.durations.len() as f64).ceil() as usize;
+        Some(self.durations[rank.clamp(1, self.durations.len()) - 1])
+    }
+}
+
+/// Summary of the metrics recorded by runs, printed by the CLI after a run.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MetricsSummary {
+    pub nodes: BTreeMap<String, NodeMetrics>,
+    pub cache_hits: u64,
+    pub cache_misses: u64,
+}
+
+impl MetricsSummary {
+    pub fn from_snapshot(snapshot: Snapshot) -> Self {
+        let mut summary = Self::default();
+
+        for (key, _, _, value) in snapshot.into_vec() {
+            let key = key.key();
+            let label = |name: &str| {
+                key.labels()
+                    .find(|label| label.key() == name)
+                    .map(|label| label.value().to_string())
+            };
+
+            let counter = match &value {
+                DebugValue::Counter(counter) => *counter,
+                _ => 0,
+            };
+            match key.name() {
+                METRIC_LLM_CACHE_HITS => summary.cache_hits += counter,
+                METRIC_LLM_CACHE_MISSES => summary.cache_misses += counter,
+                _ => {}
+            }
+
+            let Some(node_id) = label("node_id") else {
+                continue;
+            };
+            let node = summary.nodes.entry(node_id).or_default();
+            match key.name() {
+                METRIC_NODE_EXECUTIONS => node.executions += counter,
+                METRIC_NODE_ERRORS => node.errors += counter,
+                METRIC_NODE_RETRIES => node.retries += counter,
+                METRIC_NODE_TOKENS => match label("kind").as_deref() {
+                    Some("prompt") => node.prompt_tokens += counter,
+                    Some("completion") => node.completion_tokens += counter,
+                    _ => {}
+                },
+                METRIC_NODE_DURATION => {
+                    if let DebugValue::Histogram(values) = value {
+                        node.durations
+                            .extend(values.into_iter().map(|value| value.into_inner()));
+                        node.durations.sort_by(f64::total_cmp);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        summary
+    }
+}
+
+impl fmt::Display for MetricsSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = self
+            .nodes
+            .keys()
+            .map(String::len)
+            .chain(["node".len()])
+            .max()
+            .unwrap_or_default();
+
+        writeln!(
+            f,
+            "{:width$}  {:>10}  {:>6}  {:>7}  {:>10}  {:>10}  {:>8}",
+            "node", "executions", "errors", "retries", "p50", "p95", "tokens"
+        )?;
+        for (node_id, node) in &self.nodes {
+            let percentile = |percentile| {
+                node.duration_percentile(percentile)
+                    .map_or_else(|| "-".to_string(), format_secs)
+            };
+            writeln!(
+                f,
+                "{node_id:width$}  {:>10}  {:>6}  {:>7}  {:>10}  {:>10}  {:>8}",
+                node.executions,
+                node.errors,
+                node.retries,
+                percentile(50.0),
+                percentile(95.0),
+                node.prompt_tokens + node.completion_tokens,
+            )?;
+        }
+
+        if self.cache_hits + self.cache_misses > 0 {
+            writeln!(
+                f,
+                "LLM cache: {} hits, {} misses",
+                self.cache_hits, self.cache_misses
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+fn format_secs(secs: f64) -> String {
+    if secs < 0.001 {
+        format!("{:.0}µs", secs * 1_000_000.0)
+    } else if secs < 1.0 {
+        format!("{:.1}ms", secs * 1000.0)
+    } else {
+        format!("{secs:.2}s")
+    }
+}
@@ -0,0 +1,137 @@
+mod common;
+
+use agent::*;
+use common::MockServer;
+use node::*;
+use serde_json::json;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+fn fixture_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("agent-llm-cache-{}-{name}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+async fn ollama_server(content: &str) -> (MockServer, Arc<dyn LlmProvider>) {
+    let server = MockServer::start(
+        "/api/chat",
+        json!({ "message": { "role": "assistant", "content": content } }),
+    )
+    .await;
+    let provider = ollama(&server, "test-model");
+
+    (server, provider)
+}
+
+fn ollama(server: &MockServer, model: &str) -> Arc<dyn LlmProvider> {
+    Arc::new(OllamaProvider::new(&ProviderConfig {
+        kind: ProviderKind::Ollama,
+        api_url: Some(server.url.clone()),
+        api_token: None,
+        model: model.to_string(),
+        rate_limit: RateLimitConfig::default(),
+    }))
+}
+
+fn request(text: &str) -> LlmRequest {
+    LlmRequest::new(Conversation::new().with_message(ChatMessage::user(text)))
+}
+
+#[tokio::test]
+async fn record_and_replay() {
+    let dir = fixture_dir("record");
+    let (server, provider) = ollama_server("recorded").await;
+
+    let record = CachedProvider::new("local", provider.clone(), CacheMode::Record, &dir);
+    let response = record.complete(&request("Hi")).await.unwrap();
+    assert_eq!(response.text(), "recorded");
+    assert_eq!(server.take_requests().len(), 1);
+    assert!(record.fixture_path(&request("Hi")).unwrap().exists());
+
+    let replay = CachedProvider::new("local", provider, CacheMode::Replay, &dir);
+    let response = replay.complete(&request("Hi")).await.unwrap();
+    assert_eq!(response.text(), "recorded");
+    assert!(server.take_requests().is_empty());
+
+    // any change of the request is a miss
+    let err = replay.complete(&request("Hello")).await.unwrap_err();
+    assert!(format!("{err:#}").contains("not found"));
+    let err = replay
+        .complete(&request("Hi").with_model("other-model"))
+        .await
+        .unwrap_err();
+    assert!(format!("{err:#}").contains("not found"));
+    assert!(server.take_requests().is_empty());
+}
+
+#[tokio::test]
+async fn fixtures_are_per_configured_model() {
+    let dir = fixture_dir("models");
+    let (server, provider) = ollama_server("recorded").await;
+
+    let record = CachedProvider::new("local", provider, CacheMode::Record, &dir);
+    record.complete(&request("Hi")).await.unwrap();
+    server.take_requests();
+
+    let replay = CachedProvider::new(
+        "local",
+        ollama(&server, "other-model"),
+        CacheMode::Replay,
+        &dir,
+    );
+    let err = replay.complete(&request("Hi")).await.unwrap_err();
+    assert!(format!("{err:#}").contains("not found"));
+    assert!(server.take_requests().is_empty());
+
+    let fixture = std::fs::read_to_string(record.fixture_path(&request("Hi")).unwrap()).unwrap();
+    assert!(fixture.contains("\"model\": \"test-model\""));
+}
+
+#[tokio::test]
+async fn fixtures_are_per_provider() {
+    let dir = fixture_dir("providers");
+    let (_server, provider) = ollama_server("recorded").await;
+
+    let record = CachedProvider::new("first", provider.clone(), CacheMode::Record, &dir);
+    record.complete(&request("Hi")).await.unwrap();
+
+    let replay = CachedProvider::new("second", provider, CacheMode::Replay, &dir);
+    assert!(replay.complete(&request("Hi")).await.is_err());
+}
+
+#[tokio::test]
+async fn replay_graph() {
+    let dir = fixture_dir("graph");
+    let (server, provider) = ollama_server("Hello from fixture").await;
+
+    let build_task = |mode| -> eyre::Result<(Task, NodeInstanceId)> {
+        let provider = Arc::new(CachedProvider::new("local", provider.clone(), mode, &dir));
+        let providers = Arc::new(LlmProviders::new("local").with_provider("local", provider));
+
+        let mut task = Task::new();
+        let text = task.register_node(NodeText)?;
+        let llm = task.register_node(NodeLLM::new(providers))?;
+
+        let text = task.instantiate(&text)?;
+        let llm = task.instantiate(&llm)?;
+        task.set_instance_memory(text, NodeText::MEMORY_TEXT, "Hi".to_string())?;
+        task.connect(
+            text,
+            NodeText::OUT_ARG_TEXT,
+            llm,
+            NodeLLM::INPUT_ARG_CONTEXT,
+        )?;
+
+        Ok((task, llm))
+    };
+
+    let (task, _) = build_task(CacheMode::Record).unwrap();
+    task.run(&RunBudget::unlimited()).await.unwrap();
+    assert_eq!(server.take_requests().len(), 1);
+
+    let (task, llm) = build_task(CacheMode::Replay).unwrap();
+    let report = task.run(&RunBudget::unlimited()).await.unwrap();
+    assert!(server.take_requests().is_empty());
+    assert_eq!(report.instances[&llm].status, InstanceStatus::Succeeded);
+}
@@ -0,0 +1,252 @@
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Output, Stdio};
+
+fn graph_file(name: &str, graph: serde_json::Value) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("agent-cli-{}-{name}.json", std::process::id()));
+    std::fs::write(&path, graph.to_string()).unwrap();
+    path
+}
+
+fn agent(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_agent"))
+        .args(args)
+        .env("AGENT_SANDBOX_ROOT", std::env::temp_dir())
+        .output()
+        .unwrap()
+}
+
+fn hello_graph() -> serde_json::Value {
+    serde_json::json!({
+        "instances": [
+            { "id": 10000, "node": "text" },
+            { "id": 10001, "node": "print" },
+        ],
+        "connections": [{ "from": "10000.text", "to": "10001.text" }],
+        "inputs": { "greeting": "10000.text" },
+    })
+}
+
+#[test]
+fn run_with_inputs() {
+    let path = graph_file("run", hello_graph());
+
+    let output = agent(&[
+        "run",
+        path.to_str().unwrap(),
+        "--input",
+        "greeting=Hello CLI",
+    ]);
+    assert_eq!(output.status.code(), Some(0));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Hello CLI"));
+
+    // the input has no default value in the graph
+    let output = agent(&["run", path.to_str().unwrap()]);
+    assert_eq!(output.status.code(), Some(2));
+
+    let output = agent(&["run", path.to_str().unwrap(), "--input", "unknown=value"]);
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn run_with_breakpoint() {
+    let path = graph_file("debug", hello_graph());
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_agent"))
+        .args([
+            "run",
+            path.to_str().unwrap(),
+            "--input",
+            "greeting=Hello CLI",
+            "--break",
+            "print",
+        ])
+        .env("AGENT_SANDBOX_ROOT", std::env::temp_dir())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(b"memory\nset input text Hello debugger\ncontinue\n")
+        .unwrap();
+
+    let output = child.wait_with_output().unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Paused before 10001 (print)"), "{stderr}");
+    assert!(stderr.contains("text = \"Hello CLI\""), "{stderr}");
+    assert!(stderr.contains("Memory: none"), "{stderr}");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Hello debugger"), "{stdout}");
+    assert!(!stdout.contains("Hello CLI"), "{stdout}");
+}
+
+#[test]
+fn debug_set_value_after_repeated_spaces() {
+    let path = graph_file("debug-spaces", hello_graph());
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_agent"))
+        .args([
+            "run",
+            path.to_str().unwrap(),
+            "--input",
+            "greeting=Hello CLI",
+            "--break",
+            "print",
+        ])
+        .env("AGENT_SANDBOX_ROOT", std::env::temp_dir())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(b"set  input   text   Hello  debugger\ncontinue\n")
+        .unwrap();
+
+    let output = child.wait_with_output().unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Hello  debugger"), "{stdout}");
+}
+
+#[test]
+fn repl_reads_commands() {
+    let path = std::env::temp_dir().join(format!("agent-cli-{}-repl.json", std::process::id()));
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_agent"))
+        .arg("repl")
+        .env("AGENT_SANDBOX_ROOT", std::env::temp_dir())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let commands = format!(
+        "add text\nadd print\nset 10000 text Hello REPL\nconnect 10000.text 10001.text\nrun\nsave {}\nexit\n",
+        path.display()
+    );
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(commands.as_bytes())
+        .unwrap();
+
+    let output = child.wait_with_output().unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Hello REPL"), "{stdout}");
+    assert!(stdout.contains("10001 print succeeded"), "{stdout}");
+
+    let output = agent(&["validate", path.to_str().unwrap()]);
+    assert_eq!(output.status.code(), Some(0));
+}
+
+#[test]
+fn validation_and_runtime_errors() {
+    let invalid = graph_file(
+        "invalid",
+        serde_json::json!({ "instances": [{ "id": 10000, "node": "print" }] }),
+    );
+    let output = agent(&["validate", invalid.to_str().unwrap()]);
+    assert_eq!(output.status.code(), Some(2));
+
+    let unknown_node = graph_file(
+        "unknown",
+        serde_json::json!({ "instances": [{ "id": 10000, "node": "no_such_node" }] }),
+    );
+    let output = agent(&["validate", unknown_node.to_str().unwrap()]);
+    assert_eq!(output.status.code(), Some(2));
+
+    let failing = graph_file(
+        "failing",
+        serde_json::json!({
+            "instances": [
+                { "id": 10000, "node": "read_file", "memory": { "path": "agent-cli-missing.txt" } },
+                { "id": 10001, "node": "print" },
+            ],
+            "connections": [{ "from": "10000.text", "to": "10001.text" }],
+        }),
+    );
+    let output = agent(&["validate", failing.to_str().unwrap()]);
+    assert_eq!(output.status.code(), Some(0));
+    let export = failing.with_extension("dot");
+    let output = agent(&[
+        "run",
+        failing.to_str().unwrap(),
+        "--report",
+        "--export",
+        export.to_str().unwrap(),
+    ]);
+    assert_eq!(output.status.code(), Some(1));
+
+    let dot = std::fs::read_to_string(&export).unwrap();
+    assert!(
+        dot.contains("fillcolor=\"#ffcdd2\", tooltip=\"failed\""),
+        "{dot}"
+    );
+    assert!(dot.contains("tooltip=\"skipped\""), "{dot}");
+
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["instances"]["10000"]["status"], "failed");
+    assert_eq!(report["instances"]["10001"]["status"], "skipped");
+}
+
+#[test]
+fn nodes_list_and_dot() {
+    let output = agent(&["nodes", "list", "--json"]);
+    assert_eq!(output.status.code(), Some(0));
+
+    let nodes: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let text = nodes
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|node| node["id"] == "text")
+        .unwrap();
+    assert_eq!(text["version"], "0.1.0");
+    assert!(text["ports"]["outputs"]["text"].is_object());
+
+    let path = graph_file("dot", hello_graph());
+    let output = agent(&["graph", "dot", path.to_str().unwrap()]);
+    assert_eq!(output.status.code(), Some(0));
+
+    let dot = String::from_utf8(output.stdout).unwrap();
+    assert!(dot.starts_with("digraph task {"), "{dot}");
+    assert!(
+        dot.contains("\"10000\":\"out_text\" -> \"10001\":\"in_text\""),
+        "{dot}"
+    );
+
+    let output = agent(&["graph", "mermaid", path.to_str().unwrap()]);
+    assert_eq!(output.status.code(), Some(0));
+
+    let mermaid = String::from_utf8(output.stdout).unwrap();
+    assert!(mermaid.starts_with("flowchart LR"), "{mermaid}");
+    assert!(mermaid.contains("out text: String"), "{mermaid}");
+    assert!(
+        mermaid.contains("n10000 -->|\"text → text\"| n10001"),
+        "{mermaid}"
+    );
+}
+
+#[test]
+fn invalid_config_fails_as_invalid() {
+    let output = Command::new(env!("CARGO_BIN_EXE_agent"))
+        .args(["nodes", "list"])
+        .env("AGENT_SANDBOX_ROOT", std::env::temp_dir())
+        .env("AGENT_COMMAND_TIMEOUT", "soon")
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("failed to load config"));
+}
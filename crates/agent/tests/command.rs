@@ -0,0 +1,184 @@
+#![cfg(unix)]
+
+use agent::*;
+use node::*;
+use std::sync::Arc;
+use std::time::Duration;
+
+fn policy(name: &str) -> CommandPolicy {
+    let root = std::env::temp_dir().join(format!("agent-command-{}-{name}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&root);
+
+    CommandPolicy {
+        allowlist: ["sh", "cat", "env", "pwd", "sleep"]
+            .into_iter()
+            .map(String::from)
+            .collect(),
+        timeout: Duration::from_secs(5),
+        max_output_bytes: 1024,
+        env: vec!["PATH".to_string()],
+        sandbox: Arc::new(Sandbox::new(&root).unwrap()),
+    }
+}
+
+struct Output {
+    stdout: String,
+    stderr: String,
+    exit_code: i64,
+}
+
+async fn run(
+    policy: CommandPolicy,
+    program: &str,
+    args: &[&str],
+    stdin: Option<&str>,
+    cwd: Option<&str>,
+) -> eyre::Result<Output> {
+    let mut task = Task::new();
+    let node = task.register_node(NodeRunCommand::new(Arc::new(policy)))?;
+    let instance = task.instantiate(&node)?;
+
+    task.set_instance_memory(
+        instance,
+        NodeRunCommand::MEMORY_PROGRAM,
+        program.to_string(),
+    )?;
+    task.set_instance_memory(
+        instance,
+        NodeRunCommand::MEMORY_ARGS,
+        args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>(),
+    )?;
+    if let Some(cwd) = cwd {
+        task.set_instance_memory(instance, NodeRunCommand::MEMORY_CWD, cwd.to_string())?;
+    }
+
+    let stdin = stdin.map(|stdin| Value::new(stdin.to_string()));
+    let mut input = InstanceRefArgs::new();
+    if let Some(stdin) = &stdin {
+        input.insert(NodeRunCommand::INPUT_ARG_STDIN, stdin);
+    }
+
+    let ctx = RunContext::new(&task, RunBudget::unlimited());
+    let output = task
+        .get_node(&node)?
+        .run(task.get_instance(instance)?, &ctx, &input)
+        .await?;
+
+    Ok(Output {
+        stdout: output[NodeRunCommand::OUTPUT_ARG_STDOUT]
+            .downcast::<String>()?
+            .clone(),
+        stderr: output[NodeRunCommand::OUTPUT_ARG_STDERR]
+            .downcast::<String>()?
+            .clone(),
+        exit_code: *output[NodeRunCommand::OUTPUT_ARG_EXIT_CODE].downcast::<i64>()?,
+    })
+}
+
+#[tokio::test]
+async fn output_and_exit_code() {
+    let output = run(
+        policy("output"),
+        "sh",
+        &["-c", "echo out; echo err >&2; exit 3"],
+        None,
+        None,
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(output.stdout, "out\n");
+    assert_eq!(output.stderr, "err\n");
+    assert_eq!(output.exit_code, 3);
+
+    let output = run(policy("stdin"), "cat", &[], Some("hello"), None)
+        .await
+        .unwrap();
+    assert_eq!(output.stdout, "hello");
+    assert_eq!(output.exit_code, 0);
+}
+
+#[tokio::test]
+async fn allowlist() {
+    let err = run(policy("allowlist"), "ls", &[], None, None)
+        .await
+        .err()
+        .unwrap();
+    assert!(format!("{err:#}").contains("not allowed"));
+
+    // paths must be allowlisted explicitly
+    let err = run(
+        policy("allowlist-path"),
+        "/bin/sh",
+        &["-c", "true"],
+        None,
+        None,
+    )
+    .await
+    .err()
+    .unwrap();
+    assert!(format!("{err:#}").contains("not allowed"));
+}
+
+#[tokio::test]
+async fn timeout() {
+    let policy = CommandPolicy {
+        timeout: Duration::from_millis(200),
+        ..policy("timeout")
+    };
+
+    let started_at = std::time::Instant::now();
+    let err = run(policy, "sleep", &["5"], None, None)
+        .await
+        .err()
+        .unwrap();
+    assert!(format!("{err:#}").contains("timed out"));
+    assert!(started_at.elapsed() < Duration::from_secs(2));
+}
+
+#[tokio::test]
+async fn output_is_capped() {
+    let output = run(
+        policy("cap"),
+        "sh",
+        &[
+            "-c",
+            "i=0; while [ $i -lt 1000 ]; do echo 0123456789; i=$((i+1)); done",
+        ],
+        None,
+        None,
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(output.stdout.len(), 1024);
+    assert_eq!(output.exit_code, 0);
+}
+
+#[tokio::test]
+async fn environment_and_cwd() {
+    std::env::set_var("AGENT_TEST_SECRET", "secret");
+
+    let output = run(policy("env"), "env", &[], None, None).await.unwrap();
+    let names = output
+        .stdout
+        .lines()
+        .filter_map(|line| line.split_once('=').map(|(name, _)| name))
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["PATH"]);
+
+    let policy = policy("cwd");
+    let root = policy.sandbox.root().to_path_buf();
+    std::fs::create_dir_all(root.join("work")).unwrap();
+
+    let output = run(policy.clone(), "pwd", &[], None, Some("work"))
+        .await
+        .unwrap();
+    assert_eq!(output.stdout.trim(), root.join("work").to_str().unwrap());
+
+    let err = run(policy, "pwd", &[], None, Some("../"))
+        .await
+        .err()
+        .unwrap();
+    assert!(format!("{err:#}").contains("escapes the sandbox"));
+}
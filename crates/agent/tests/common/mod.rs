@@ -0,0 +1,101 @@
+// Each test crate uses its own subset of the helpers.
+#![allow(dead_code)]
+
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::post;
+use axum::Json;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+pub struct RecordedRequest {
+    pub headers: HeaderMap,
+    pub body: serde_json::Value,
+}
+
+#[derive(Clone)]
+pub struct MockResponse {
+    pub status: StatusCode,
+    pub headers: Vec<(&'static str, String)>,
+    pub body: serde_json::Value,
+}
+
+impl MockResponse {
+    pub fn ok(body: serde_json::Value) -> Self {
+        Self {
+            status: StatusCode::OK,
+            headers: Vec::new(),
+            body,
+        }
+    }
+
+    pub fn status(status: StatusCode) -> Self {
+        Self {
+            status,
+            ..Self::ok(serde_json::json!({ "error": status.to_string() }))
+        }
+    }
+
+    pub fn with_header(mut self, name: &'static str, value: impl Into<String>) -> Self {
+        self.headers.push((name, value.into()));
+        self
+    }
+}
+
+impl IntoResponse for MockResponse {
+    fn into_response(self) -> Response {
+        let mut response = (self.status, Json(self.body)).into_response();
+        for (name, value) in self.headers {
+            response.headers_mut().insert(name, value.parse().unwrap());
+        }
+
+        response
+    }
+}
+
+/// Local HTTP server answering POST requests to `path` with canned JSON responses.
+pub struct MockServer {
+    pub url: String,
+    requests: Arc<Mutex<Vec<RecordedRequest>>>,
+}
+
+impl MockServer {
+    pub async fn start(path: &str, response: serde_json::Value) -> Self {
+        Self::start_with_responses(path, vec![MockResponse::ok(response)]).await
+    }
+
+    /// Answer with `responses` in order, the last one is repeated for all further requests.
+    pub async fn start_with_responses(path: &str, responses: Vec<MockResponse>) -> Self {
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let responses = Arc::new(Mutex::new(VecDeque::from(responses)));
+
+        let handler = {
+            let requests = requests.clone();
+            move |headers: HeaderMap, Json(body): Json<serde_json::Value>| async move {
+                requests
+                    .lock()
+                    .unwrap()
+                    .push(RecordedRequest { headers, body });
+
+                let mut responses = responses.lock().unwrap();
+                match responses.len() {
+                    1 => responses[0].clone(),
+                    _ => responses.pop_front().unwrap(),
+                }
+            }
+        };
+
+        let router = axum::Router::new().route(path, post(handler));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+
+        Self { url, requests }
+    }
+
+    /// Take all requests received so far.
+    pub fn take_requests(&self) -> Vec<RecordedRequest> {
+        std::mem::take(&mut self.requests.lock().unwrap())
+    }
+}
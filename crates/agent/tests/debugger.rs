@@ -0,0 +1,202 @@
+use node::*;
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+/// `10000` text -> `10001` parse_json, `10002` text -> `10003` print.
+fn task() -> eyre::Result<Task> {
+    let mut task = Task::new();
+    task.register_built_in_nodes()?;
+    task.set_event_preview(Some(100));
+
+    let text = task.instantiate(&"text".into())?;
+    let parse = task.instantiate(&"parse_json".into())?;
+    let greeting = task.instantiate(&"text".into())?;
+    let print = task.instantiate(&"print".into())?;
+    task.set_instance_memory(text, NodeText::MEMORY_TEXT, "[1]".to_string())?;
+    task.set_instance_memory(greeting, NodeText::MEMORY_TEXT, "Hello".to_string())?;
+    task.connect(
+        text,
+        NodeText::OUT_ARG_TEXT,
+        parse,
+        NodeParseJson::INPUT_ARG_TEXT,
+    )?;
+    task.connect(
+        greeting,
+        NodeText::OUT_ARG_TEXT,
+        print,
+        NodePrint::INPUT_ARG_TEXT,
+    )?;
+
+    Ok(task)
+}
+
+/// Previews of the outputs by instance id.
+fn outputs(
+    events: &mut tokio::sync::broadcast::Receiver<RunEvent>,
+) -> BTreeMap<u32, BTreeMap<String, String>> {
+    let mut outputs = BTreeMap::new();
+
+    while let Ok(event) = events.try_recv() {
+        if let RunEventKind::NodeOutput {
+            instance_id,
+            outputs: values,
+            ..
+        } = event.kind
+        {
+            let values = values
+                .into_iter()
+                .map(|(name, value)| (name, value.preview.unwrap_or_default()))
+                .collect();
+            outputs.insert(instance_id.0, values);
+        }
+    }
+
+    outputs
+}
+
+#[tokio::test]
+async fn breakpoint_pauses_and_edits_inputs() -> eyre::Result<()> {
+    let task = task()?;
+    let mut events = task.subscribe();
+
+    let debugger = Debugger::new().with_breakpoint("parse_json".parse()?);
+    let (mut session, run) = task.run_debug(&RunBudget::unlimited(), debugger);
+
+    let control = async {
+        let mut paused = Vec::new();
+        while let Some(mut pause) = session.next_pause().await {
+            paused.push(pause.instance_id.0);
+
+            let text = pause.inputs[NodeParseJson::INPUT_ARG_TEXT].downcast::<String>()?;
+            assert_eq!(text, "[1]");
+            pause.inputs.insert(
+                NodeParseJson::INPUT_ARG_TEXT.to_string(),
+                Value::new("[2, 3]".to_string()),
+            );
+            pause.resume(DebugAction::Continue);
+        }
+        eyre::Ok(paused)
+    };
+
+    let (report, paused) = tokio::join!(run, control);
+    report?;
+    assert_eq!(paused?, vec![10001]);
+    assert_eq!(outputs(&mut events)[&10001]["json"], "[2,3]");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn step_runs_instances_in_order() -> eyre::Result<()> {
+    let task = task()?;
+    let mut events = task.subscribe();
+
+    let debugger = Debugger::new().with_step(true);
+    let (mut session, run) = task.run_debug(&RunBudget::unlimited(), debugger);
+
+    let control = async {
+        let mut paused = Vec::new();
+        while let Some(mut pause) = session.next_pause().await {
+            paused.push(pause.instance_id.0);
+
+            if pause.instance_id.0 == 10000 {
+                pause.memory.insert(
+                    NodeText::MEMORY_TEXT.to_string(),
+                    Value::new(r#"{"a": 1}"#.to_string()),
+                );
+            }
+            pause.resume(DebugAction::Step);
+        }
+        paused
+    };
+
+    let (report, paused) = tokio::join!(run, control);
+    report?;
+    assert_eq!(paused, vec![10000, 10001, 10002, 10003]);
+    assert_eq!(outputs(&mut events)[&10001]["json"], r#"{"a":1}"#);
+
+    // changes apply to the paused execution only
+    let text = task
+        .get_instance(NodeInstanceId(10000))?
+        .get_memory::<String>(NodeText::MEMORY_TEXT)?;
+    assert_eq!(text.map(String::as_str), Some("[1]"));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn abort_stops_run() -> eyre::Result<()> {
+    let task = task()?;
+
+    let debugger = Debugger::new().with_breakpoint(Breakpoint::Instance(NodeInstanceId(10001)));
+    let (mut session, run) = task.run_debug(&RunBudget::unlimited(), debugger);
+
+    let control = async {
+        while let Some(pause) = session.next_pause().await {
+            pause.resume(DebugAction::Abort);
+        }
+    };
+
+    let (result, ()) = tokio::join!(run, control);
+    let err = result.unwrap_err();
+    assert!(format!("{err:#}").contains("aborted"));
+
+    let report = task.last_report().unwrap();
+    let status = |id| report.instances[&NodeInstanceId(id)].status;
+    assert_eq!(status(10000), InstanceStatus::Succeeded);
+    assert_eq!(status(10001), InstanceStatus::Failed);
+    assert_eq!(status(10002), InstanceStatus::Skipped);
+    assert_eq!(status(10003), InstanceStatus::Skipped);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn paused_time_is_not_counted_in_max_duration() -> eyre::Result<()> {
+    let task = task()?;
+
+    let budget = RunBudget::unlimited().with_max_duration(Duration::from_millis(100));
+    let (mut session, run) = task.run_debug(&budget, Debugger::new().with_step(true));
+
+    // every pause is longer than the whole budget
+    let control = async {
+        while let Some(pause) = session.next_pause().await {
+            tokio::time::sleep(Duration::from_millis(150)).await;
+            pause.resume(DebugAction::Step);
+        }
+    };
+
+    let (report, ()) = tokio::join!(run, control);
+    report?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn closed_session_aborts_run() -> eyre::Result<()> {
+    let task = task()?;
+
+    let debugger = Debugger::new().with_step(true);
+    let (session, run) = task.run_debug(&RunBudget::unlimited(), debugger);
+    drop(session);
+
+    assert!(run.await.is_err());
+    assert_eq!(
+        task.last_report().unwrap().instances[&NodeInstanceId(10000)].status,
+        InstanceStatus::Failed
+    );
+
+    Ok(())
+}
+
+#[test]
+fn breakpoints_are_parsed() -> eyre::Result<()> {
+    assert_eq!(
+        "10001".parse::<Breakpoint>()?,
+        Breakpoint::Instance(NodeInstanceId(10001))
+    );
+    assert_eq!("llm".parse::<Breakpoint>()?, Breakpoint::Node("llm".into()));
+    assert!("".parse::<Breakpoint>().is_err());
+
+    Ok(())
+}
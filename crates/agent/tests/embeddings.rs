@@ -0,0 +1,454 @@
+mod common;
+
+use agent::*;
+use common::{MockResponse, MockServer};
+use node::*;
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+fn embeddings(server: &MockServer) -> Arc<dyn EmbeddingProvider> {
+    Arc::new(OpenAiEmbeddings::new(&ProviderConfig {
+        kind: ProviderKind::OpenAi,
+        api_url: Some(server.url.clone()),
+        api_token: Some("secret".to_string()),
+        model: "test-embedding".to_string(),
+        rate_limit: RateLimitConfig::default(),
+    }))
+}
+
+fn sandbox(name: &str) -> Arc<Sandbox> {
+    let root = std::env::temp_dir().join(format!("agent-embeddings-{}-{name}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&root);
+
+    Arc::new(Sandbox::new(root).unwrap())
+}
+
+fn embeddings_response(vectors: &[[f32; 2]]) -> MockResponse {
+    let data = vectors
+        .iter()
+        .enumerate()
+        .map(|(index, vector)| json!({ "index": index, "embedding": vector }))
+        .collect::<Vec<_>>();
+
+    MockResponse::ok(json!({ "data": data, "usage": { "prompt_tokens": 7 } }))
+}
+
+/// Emits texts stored in memory.
+struct NodeTexts;
+
+impl NodeTrait for NodeTexts {
+    fn run<'a>(
+        &'a self,
+        instance: &'a NodeInstance,
+        _ctx: &'a RunContext,
+        _input: &'a InstanceRefArgs,
+    ) -> RunResult<'a> {
+        Box::pin(async {
+            let texts = instance
+                .get_memory_parsed::<Vec<String>>("texts")?
+                .unwrap_or_default();
+
+            Ok(BTreeMap::from([("texts".to_string(), Value::new(texts))]))
+        })
+    }
+}
+
+impl NodeMetaTrait for NodeTexts {
+    fn get_meta(&self) -> NodeMeta {
+        NodeMeta::new("texts", "0.1.0")
+            .with_output_arg("texts", OutputArgMeta::new::<Vec<String>>())
+    }
+}
+
+/// Emits embeddings stored in memory.
+struct NodeVectors;
+
+impl NodeTrait for NodeVectors {
+    fn run<'a>(
+        &'a self,
+        instance: &'a NodeInstance,
+        _ctx: &'a RunContext,
+        _input: &'a InstanceRefArgs,
+    ) -> RunResult<'a> {
+        Box::pin(async {
+            let vectors = instance
+                .get_memory::<Vec<Vec<f32>>>("vectors")?
+                .cloned()
+                .unwrap_or_default();
+
+            Ok(BTreeMap::from([(
+                "vectors".to_string(),
+                Value::new(vectors),
+            )]))
+        })
+    }
+}
+
+impl NodeMetaTrait for NodeVectors {
+    fn get_meta(&self) -> NodeMeta {
+        NodeMeta::new("vectors", "0.1.0")
+            .with_output_arg("vectors", OutputArgMeta::new::<Vec<Vec<f32>>>())
+    }
+}
+
+/// Keeps the last received store.
+#[derive(Clone, Default)]
+struct NodeStoreCapture(Arc<Mutex<Option<VectorStore>>>);
+
+impl NodeTrait for NodeStoreCapture {
+    fn run<'a>(
+        &'a self,
+        _instance: &'a NodeInstance,
+        _ctx: &'a RunContext,
+        input: &'a InstanceRefArgs,
+    ) -> RunResult<'a> {
+        Box::pin(async {
+            *self.0.lock().unwrap() = Some(input["store"].downcast::<VectorStore>()?.clone());
+
+            Ok(BTreeMap::new())
+        })
+    }
+}
+
+impl NodeMetaTrait for NodeStoreCapture {
+    fn get_meta(&self) -> NodeMeta {
+        NodeMeta::new("store_capture", "0.1.0")
+            .with_input_arg("store", InputArgMeta::new::<VectorStore>())
+    }
+}
+
+/// Keeps the last received documents.
+#[derive(Clone, Default)]
+struct NodeCapture(Arc<Mutex<Vec<ScoredDocument>>>);
+
+impl NodeTrait for NodeCapture {
+    fn run<'a>(
+        &'a self,
+        _instance: &'a NodeInstance,
+        _ctx: &'a RunContext,
+        input: &'a InstanceRefArgs,
+    ) -> RunResult<'a> {
+        Box::pin(async {
+            *self.0.lock().unwrap() = input["documents"]
+                .downcast::<Vec<ScoredDocument>>()?
+                .clone();
+
+            Ok(BTreeMap::new())
+        })
+    }
+}
+
+impl NodeMetaTrait for NodeCapture {
+    fn get_meta(&self) -> NodeMeta {
+        NodeMeta::new("capture", "0.1.0")
+            .with_input_arg("documents", InputArgMeta::new::<Vec<ScoredDocument>>())
+    }
+}
+
+#[tokio::test]
+async fn openai_embeddings() {
+    let server = MockServer::start(
+        "/embeddings",
+        json!({
+            "model": "test-embedding",
+            "data": [
+                { "index": 1, "embedding": [0.0, 1.0] },
+                { "index": 0, "embedding": [1.0, 0.0] },
+            ],
+            "usage": { "prompt_tokens": 3, "total_tokens": 3 },
+        }),
+    )
+    .await;
+
+    let texts = vec!["first".to_string(), "second".to_string()];
+    let result = embeddings(&server).embed(&texts).await.unwrap();
+
+    assert_eq!(result.vectors, vec![vec![1.0, 0.0], vec![0.0, 1.0]]);
+    assert_eq!(result.usage, Some(TokenUsage::new(3, 0)));
+
+    let request = server.take_requests().remove(0);
+    assert_eq!(request.headers["authorization"], "Bearer secret");
+    assert_eq!(
+        request.body,
+        json!({ "model": "test-embedding", "input": ["first", "second"] })
+    );
+}
+
+#[tokio::test]
+async fn vector_store() {
+    let store = VectorStore::new();
+    store
+        .upsert(Document::new("x", "east"), vec![1.0, 0.0])
+        .unwrap();
+    store
+        .upsert(Document::new("y", "north"), vec![0.0, 1.0])
+        .unwrap();
+    store
+        .upsert(
+            Document::new("xy", "north-east").with_metadata(json!({ "source": "map" })),
+            vec![1.0, 1.0],
+        )
+        .unwrap();
+
+    // clones share documents
+    let shared = store.clone();
+    shared
+        .upsert(Document::new("y", "up"), vec![0.0, 2.0])
+        .unwrap();
+    assert_eq!(store.len(), 3);
+    assert_eq!(store.get("y").unwrap().text, "up");
+
+    let found = store.search(&[0.9, 0.1], 2).unwrap();
+    let ids = found
+        .iter()
+        .map(|scored| scored.document.id.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(ids, ["x", "xy"]);
+    assert!(found[0].score > found[1].score);
+
+    assert!(store.search(&[1.0, 0.0, 0.0], 1).is_err());
+    assert!(store
+        .upsert(Document::new("z", "up"), vec![1.0, 0.0, 0.0])
+        .is_err());
+
+    let path = std::env::temp_dir().join(format!("agent-store-{}.json", std::process::id()));
+    store.save(&path).await.unwrap();
+    let loaded = VectorStore::load(&path).await.unwrap();
+    assert_eq!(loaded.len(), 3);
+    assert_eq!(loaded.get("xy"), store.get("xy"));
+    assert_eq!(loaded.search(&[0.9, 0.1], 2).unwrap(), found);
+
+    // nothing is stored when one of the embeddings is invalid
+    let err = store
+        .upsert_all([
+            (Document::new("z", "down"), vec![0.0, -1.0]),
+            (Document::new("w", "west"), vec![-1.0, 0.0, 0.0]),
+        ])
+        .unwrap_err();
+    assert!(format!("{err:#}").contains("\"w\""), "{err:#}");
+    assert!(store.get("z").is_none());
+
+    // deep clones don't share documents
+    let copy = store.deep_clone();
+    copy.upsert(Document::new("z", "down"), vec![0.0, -1.0])
+        .unwrap();
+    assert_eq!(copy.len(), 4);
+    assert_eq!(store.len(), 3);
+
+    assert!(store.remove("x"));
+    assert!(!store.remove("x"));
+    assert_eq!(store.len(), 2);
+}
+
+#[tokio::test]
+async fn retrieval_graph() -> eyre::Result<()> {
+    let server = MockServer::start_with_responses(
+        "/embeddings",
+        vec![
+            embeddings_response(&[[1.0, 0.0], [0.0, 1.0], [0.7, 0.7]]),
+            embeddings_response(&[[0.1, 0.9]]),
+        ],
+    )
+    .await;
+    let embeddings = embeddings(&server);
+    let capture = NodeCapture::default();
+
+    let mut task = Task::new();
+    let texts = task.register_node(NodeTexts)?;
+    let text = task.register_node(NodeText)?;
+    let embed = task.register_node(NodeEmbedTexts::new(embeddings.clone()))?;
+    let sandbox = sandbox("graph");
+    let upsert = task.register_node(NodeUpsertStore::new(sandbox.clone()))?;
+    let query = task.register_node(NodeQueryStore::new(embeddings, sandbox))?;
+    let captured = task.register_node(capture.clone())?;
+
+    let texts = task.instantiate(&texts)?;
+    let text = task.instantiate(&text)?;
+    let embed = task.instantiate(&embed)?;
+    let upsert = task.instantiate(&upsert)?;
+    let query = task.instantiate(&query)?;
+    let captured = task.instantiate(&captured)?;
+
+    task.set_instance_memory(
+        texts,
+        "texts",
+        vec![
+            "east".to_string(),
+            "north".to_string(),
+            "north-east".to_string(),
+        ],
+    )?;
+    task.set_instance_memory(text, NodeText::MEMORY_TEXT, "up".to_string())?;
+    task.set_instance_memory(query, NodeQueryStore::MEMORY_TOP_K, 2usize)?;
+
+    task.connect(texts, "texts", embed, NodeEmbedTexts::INPUT_ARG_TEXTS)?;
+    task.connect(texts, "texts", upsert, NodeUpsertStore::INPUT_ARG_TEXTS)?;
+    task.connect(
+        embed,
+        NodeEmbedTexts::OUTPUT_ARG_EMBEDDINGS,
+        upsert,
+        NodeUpsertStore::INPUT_ARG_EMBEDDINGS,
+    )?;
+    task.connect(
+        upsert,
+        NodeUpsertStore::OUTPUT_ARG_STORE,
+        query,
+        NodeQueryStore::INPUT_ARG_STORE,
+    )?;
+    task.connect(
+        text,
+        NodeText::OUT_ARG_TEXT,
+        query,
+        NodeQueryStore::INPUT_ARG_QUERY,
+    )?;
+    task.connect(
+        query,
+        NodeQueryStore::OUTPUT_ARG_DOCUMENTS,
+        captured,
+        "documents",
+    )?;
+
+    let report = task.run(&RunBudget::unlimited()).await?;
+    assert_eq!(report.usage.prompt_tokens, 14);
+
+    let documents = capture.0.lock().unwrap().clone();
+    let texts = documents
+        .iter()
+        .map(|scored| scored.document.text.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(texts, ["north", "north-east"]);
+    assert_eq!(documents[0].document.id, NodeUpsertStore::text_id("north"));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn store_path_is_resolved_in_sandbox() -> eyre::Result<()> {
+    let server =
+        MockServer::start_with_responses("/embeddings", vec![embeddings_response(&[[1.0, 0.0]])])
+            .await;
+    let sandbox = sandbox("path");
+    let capture = NodeCapture::default();
+
+    let mut task = Task::new();
+    let texts = task.register_node(NodeTexts)?;
+    let embed = task.register_node(NodeEmbedTexts::new(embeddings(&server)))?;
+    let upsert = task.register_node(NodeUpsertStore::new(sandbox.clone()))?;
+
+    let texts = task.instantiate(&texts)?;
+    let embed = task.instantiate(&embed)?;
+    let upsert = task.instantiate(&upsert)?;
+
+    task.set_instance_memory(texts, "texts", vec!["east".to_string()])?;
+    task.connect(texts, "texts", embed, NodeEmbedTexts::INPUT_ARG_TEXTS)?;
+    task.connect(texts, "texts", upsert, NodeUpsertStore::INPUT_ARG_TEXTS)?;
+    task.connect(
+        embed,
+        NodeEmbedTexts::OUTPUT_ARG_EMBEDDINGS,
+        upsert,
+        NodeUpsertStore::INPUT_ARG_EMBEDDINGS,
+    )?;
+
+    // paths loaded from a graph file are stored as JSON
+    task.set_instance_memory(
+        upsert,
+        NodeUpsertStore::MEMORY_PATH,
+        json!("stores/memory.json"),
+    )?;
+    task.run(&RunBudget::unlimited()).await?;
+    let store = VectorStore::load(&sandbox.root().join("stores/memory.json")).await?;
+    assert_eq!(store.len(), 1);
+
+    let mut query_task = Task::new();
+    let text = query_task.register_node(NodeText)?;
+    let query = query_task.register_node(NodeQueryStore::new(embeddings(&server), sandbox))?;
+    let captured = query_task.register_node(capture.clone())?;
+
+    let text = query_task.instantiate(&text)?;
+    let query = query_task.instantiate(&query)?;
+    let captured = query_task.instantiate(&captured)?;
+
+    query_task.set_instance_memory(text, NodeText::MEMORY_TEXT, "east".to_string())?;
+    query_task.set_instance_memory(
+        query,
+        NodeQueryStore::MEMORY_PATH,
+        json!("stores/memory.json"),
+    )?;
+    query_task.connect(
+        text,
+        NodeText::OUT_ARG_TEXT,
+        query,
+        NodeQueryStore::INPUT_ARG_QUERY,
+    )?;
+    query_task.connect(
+        query,
+        NodeQueryStore::OUTPUT_ARG_DOCUMENTS,
+        captured,
+        "documents",
+    )?;
+    query_task.run(&RunBudget::unlimited()).await?;
+    assert_eq!(capture.0.lock().unwrap()[0].document.text, "east");
+
+    for path in ["../outside.json", "/tmp/outside.json"] {
+        task.set_instance_memory(upsert, NodeUpsertStore::MEMORY_PATH, path.to_string())?;
+        let err = task.run(&RunBudget::unlimited()).await.unwrap_err();
+        assert!(format!("{err:#}").contains("invalid path"), "{err:#}");
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn upsert_does_not_modify_upstream_store() -> eyre::Result<()> {
+    let sandbox = sandbox("upstream");
+    let capture = NodeStoreCapture::default();
+
+    let mut task = Task::new();
+    let texts = task.register_node(NodeTexts)?;
+    let vectors = task.register_node(NodeVectors)?;
+    let upsert = task.register_node(NodeUpsertStore::new(sandbox))?;
+    let captured = task.register_node(capture.clone())?;
+
+    let first_texts = task.instantiate(&texts)?;
+    let first_vectors = task.instantiate(&vectors)?;
+    let first = task.instantiate(&upsert)?;
+    let second_texts = task.instantiate(&texts)?;
+    let second_vectors = task.instantiate(&vectors)?;
+    let second = task.instantiate(&upsert)?;
+    let captured = task.instantiate(&captured)?;
+
+    task.set_instance_memory(first_texts, "texts", vec!["east".to_string()])?;
+    task.set_instance_memory(first_vectors, "vectors", vec![vec![1.0f32, 0.0]])?;
+    task.set_instance_memory(second_texts, "texts", vec!["north".to_string()])?;
+    task.set_instance_memory(second_vectors, "vectors", vec![vec![0.0f32, 1.0]])?;
+
+    for (texts, vectors, upsert) in [
+        (first_texts, first_vectors, first),
+        (second_texts, second_vectors, second),
+    ] {
+        task.connect(texts, "texts", upsert, NodeUpsertStore::INPUT_ARG_TEXTS)?;
+        task.connect(
+            vectors,
+            "vectors",
+            upsert,
+            NodeUpsertStore::INPUT_ARG_EMBEDDINGS,
+        )?;
+    }
+    task.connect(
+        first,
+        NodeUpsertStore::OUTPUT_ARG_STORE,
+        second,
+        NodeUpsertStore::INPUT_ARG_STORE,
+    )?;
+    task.connect(first, NodeUpsertStore::OUTPUT_ARG_STORE, captured, "store")?;
+
+    let report = task.run(&RunBudget::unlimited()).await?;
+    assert_eq!(report.instances[&second].status, InstanceStatus::Succeeded);
+
+    let store = capture.0.lock().unwrap().clone().unwrap();
+    assert_eq!(store.len(), 1);
+    assert!(store.get(&NodeUpsertStore::text_id("north")).is_none());
+
+    Ok(())
+}
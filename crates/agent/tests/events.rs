@@ -0,0 +1,120 @@
+#![cfg(unix)]
+
+use agent::*;
+use node::*;
+use std::sync::Arc;
+use std::time::Duration;
+
+fn sandbox() -> Arc<Sandbox> {
+    let root = std::env::temp_dir().join(format!("agent-events-{}", std::process::id()));
+    Arc::new(Sandbox::new(root).unwrap())
+}
+
+#[tokio::test]
+async fn run_events() -> eyre::Result<()> {
+    let sandbox = sandbox();
+    let policy = CommandPolicy {
+        allowlist: ["sh".to_string()].into(),
+        timeout: Duration::from_secs(5),
+        max_output_bytes: 1024,
+        env: vec!["PATH".to_string()],
+        sandbox: sandbox.clone(),
+    };
+
+    let mut task = Task::new();
+    task.register_built_in_nodes()?;
+    task.register_node(NodeRunCommand::new(Arc::new(policy)))?;
+    task.register_node(NodeReadFile::new(sandbox))?;
+    task.set_event_preview(Some(8));
+
+    let command = task.instantiate(&"run_command".into())?;
+    let print = task.instantiate(&"print".into())?;
+    let read = task.instantiate(&"read_file".into())?;
+    let print_file = task.instantiate(&"print".into())?;
+    task.set_instance_memory(command, NodeRunCommand::MEMORY_PROGRAM, "sh".to_string())?;
+    task.set_instance_memory(
+        command,
+        NodeRunCommand::MEMORY_ARGS,
+        vec![
+            "-c".to_string(),
+            "echo one; sleep 0.1; echo two".to_string(),
+        ],
+    )?;
+    task.set_instance_memory(read, NodeReadFile::MEMORY_PATH, "missing.txt".to_string())?;
+    task.connect(command, NodeRunCommand::OUTPUT_ARG_STDOUT, print, "text")?;
+    task.connect(read, NodeReadFile::OUTPUT_ARG_TEXT, print_file, "text")?;
+
+    let mut events = task.subscribe();
+    assert!(task.run(&RunBudget::unlimited()).await.is_err());
+
+    let mut received = Vec::new();
+    while let Ok(event) = events.try_recv() {
+        received.push(event);
+    }
+    assert!(received.iter().all(|event| event.run_id == 1));
+
+    assert!(matches!(
+        received.first().map(|event| &event.kind),
+        Some(RunEventKind::RunStarted { instances: 4 })
+    ));
+    assert!(matches!(
+        received.last().map(|event| &event.kind),
+        Some(RunEventKind::RunFinished { error: Some(_), .. })
+    ));
+
+    let chunks = received
+        .iter()
+        .filter_map(|event| match &event.kind {
+            RunEventKind::StreamChunk {
+                instance_id,
+                arg,
+                text,
+            } if *instance_id == command && arg == NodeRunCommand::OUTPUT_ARG_STDOUT => {
+                Some(text.as_str())
+            }
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(chunks, ["one\n", "two\n"]);
+
+    let position = |predicate: &dyn Fn(&RunEventKind) -> bool| {
+        received
+            .iter()
+            .position(|event| predicate(&event.kind))
+            .unwrap()
+    };
+    let command_started = position(
+        &|kind| matches!(kind, RunEventKind::NodeStarted { instance_id, .. } if *instance_id == command),
+    );
+    let command_output = position(
+        &|kind| matches!(kind, RunEventKind::NodeOutput { instance_id, .. } if *instance_id == command),
+    );
+    let print_output = position(
+        &|kind| matches!(kind, RunEventKind::NodeOutput { instance_id, .. } if *instance_id == print),
+    );
+    assert!(command_started < command_output && command_output < print_output);
+    position(
+        &|kind| matches!(kind, RunEventKind::NodeFailed { instance_id, .. } if *instance_id == read),
+    );
+    position(
+        &|kind| matches!(kind, RunEventKind::NodeSkipped { instance_id, .. } if *instance_id == print_file),
+    );
+
+    let RunEventKind::NodeOutput { outputs, .. } = &received[command_output].kind else {
+        unreachable!();
+    };
+    let stdout = &outputs[NodeRunCommand::OUTPUT_ARG_STDOUT];
+    assert_eq!(stdout.preview.as_deref(), Some("\"one\\ntw…"));
+    assert_eq!(
+        outputs[NodeRunCommand::OUTPUT_ARG_EXIT_CODE]
+            .preview
+            .as_deref(),
+        Some("0")
+    );
+
+    let json = serde_json::to_value(&received[command_output])?;
+    assert_eq!(json["type"], "node_output");
+    assert_eq!(json["instance_id"], command.0);
+
+    Ok(())
+}
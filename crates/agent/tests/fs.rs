@@ -0,0 +1,274 @@
+use agent::*;
+use node::*;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+fn sandbox(name: &str) -> (PathBuf, Arc<Sandbox>) {
+    let dir = std::env::temp_dir().join(format!("agent-fs-{}-{name}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let root = dir.join("root");
+
+    (dir, Arc::new(Sandbox::new(&root).unwrap()))
+}
+
+/// Keeps the last received value.
+#[derive(Clone, Default)]
+struct NodeCapture(Arc<Mutex<Option<Value>>>);
+
+impl NodeTrait for NodeCapture {
+    fn run<'a>(
+        &'a self,
+        _instance: &'a NodeInstance,
+        _ctx: &'a RunContext,
+        input: &'a InstanceRefArgs,
+    ) -> RunResult<'a> {
+        Box::pin(async {
+            *self.0.lock().unwrap() = input.get("value").map(|value| (*value).clone());
+            Ok(BTreeMap::new())
+        })
+    }
+}
+
+impl NodeMetaTrait for NodeCapture {
+    fn get_meta(&self) -> NodeMeta {
+        NodeMeta::new("capture", "0.1.0")
+            .with_input_arg("value", InputArgMeta::new::<String>().with_optional(true))
+    }
+}
+
+#[test]
+fn resolve() {
+    let (dir, sandbox) = sandbox("resolve");
+    let root = sandbox.root().to_path_buf();
+    std::fs::write(dir.join("secret.txt"), "secret").unwrap();
+
+    assert_eq!(sandbox.resolve("a/b.txt").unwrap(), root.join("a/b.txt"));
+    assert_eq!(sandbox.resolve("a/../b.txt").unwrap(), root.join("b.txt"));
+    assert_eq!(sandbox.resolve("./").unwrap(), root);
+    assert!(sandbox.resolve("../secret.txt").is_err());
+    assert!(sandbox.resolve("a/../../secret.txt").is_err());
+    assert!(sandbox
+        .resolve(dir.join("secret.txt").to_str().unwrap())
+        .is_err());
+
+    #[cfg(unix)]
+    {
+        std::os::unix::fs::symlink(&dir, root.join("out")).unwrap();
+        std::os::unix::fs::symlink(dir.join("missing"), root.join("dangling")).unwrap();
+        std::fs::create_dir(root.join("docs")).unwrap();
+        std::os::unix::fs::symlink(root.join("docs"), root.join("inside")).unwrap();
+
+        assert!(sandbox.resolve("out/secret.txt").is_err());
+        assert!(sandbox.resolve("out/new.txt").is_err());
+        assert!(sandbox.resolve("dangling").is_err());
+        assert_eq!(
+            sandbox.resolve("inside/a.md").unwrap(),
+            root.join("docs/a.md")
+        );
+    }
+}
+
+#[tokio::test]
+async fn write_read_list_glob() -> eyre::Result<()> {
+    let (_dir, sandbox) = sandbox("nodes");
+    std::fs::create_dir_all(sandbox.root().join("docs/nested"))?;
+    std::fs::write(sandbox.root().join("docs/a.md"), "# A")?;
+    std::fs::write(sandbox.root().join("docs/nested/b.md"), "# B")?;
+    std::fs::write(sandbox.root().join("docs/c.txt"), "C")?;
+
+    let capture = NodeCapture::default();
+
+    let mut task = Task::new();
+    let text = task.register_node(NodeText)?;
+    let write = task.register_node(NodeWriteFile::new(sandbox.clone()))?;
+    let read = task.register_node(NodeReadFile::new(sandbox.clone()))?;
+    let captured = task.register_node(capture.clone())?;
+
+    let text = task.instantiate(&text)?;
+    let write = task.instantiate(&write)?;
+    let read = task.instantiate(&read)?;
+    let captured = task.instantiate(&captured)?;
+
+    task.set_instance_memory(text, NodeText::MEMORY_TEXT, "report".to_string())?;
+    task.set_instance_memory(
+        write,
+        NodeWriteFile::MEMORY_PATH,
+        "out/report.md".to_string(),
+    )?;
+    task.connect(
+        text,
+        NodeText::OUT_ARG_TEXT,
+        write,
+        NodeWriteFile::INPUT_ARG_TEXT,
+    )?;
+    task.connect(
+        write,
+        NodeWriteFile::OUTPUT_ARG_PATH,
+        read,
+        NodeReadFile::INPUT_ARG_PATH,
+    )?;
+    task.connect(read, NodeReadFile::OUTPUT_ARG_TEXT, captured, "value")?;
+
+    task.run(&RunBudget::unlimited()).await?;
+    let value = capture.0.lock().unwrap().take().unwrap();
+    assert_eq!(value.downcast::<String>()?, "report");
+
+    // binary mode replaces the text output
+    task.set_instance_memory(read, NodeReadFile::MEMORY_BINARY, true)?;
+    let ports = task.get_instance_ports(read)?;
+    assert!(ports
+        .output_args
+        .contains_key(NodeReadFile::OUTPUT_ARG_BYTES));
+    assert!(!ports
+        .output_args
+        .contains_key(NodeReadFile::OUTPUT_ARG_TEXT));
+
+    let list = NodeListDir::new(sandbox.clone());
+    let glob = NodeGlobFiles::new(sandbox.clone());
+    let mut task = Task::new();
+    let list = task.register_node(list)?;
+    let glob = task.register_node(glob)?;
+    let list = task.instantiate(&list)?;
+    let glob = task.instantiate(&glob)?;
+    task.set_instance_memory(list, NodeListDir::MEMORY_PATH, "docs".to_string())?;
+    task.set_instance_memory(
+        glob,
+        NodeGlobFiles::MEMORY_PATTERN,
+        "docs/**/*.md".to_string(),
+    )?;
+
+    let ctx = RunContext::new(&task, RunBudget::unlimited());
+    let input = InstanceRefArgs::new();
+
+    let output = task
+        .get_node(&"list_dir".into())?
+        .run(task.get_instance(list)?, &ctx, &input)
+        .await?;
+    assert_eq!(
+        output[NodeListDir::OUTPUT_ARG_ENTRIES].downcast::<Vec<String>>()?,
+        &["docs/a.md", "docs/c.txt", "docs/nested/"]
+    );
+
+    let output = task
+        .get_node(&"glob_files".into())?
+        .run(task.get_instance(glob)?, &ctx, &input)
+        .await?;
+    assert_eq!(
+        output[NodeGlobFiles::OUTPUT_ARG_PATHS].downcast::<Vec<String>>()?,
+        &["docs/a.md", "docs/nested/b.md"]
+    );
+
+    drop(ctx);
+    task.set_instance_memory(glob, NodeGlobFiles::MEMORY_PATTERN, "../*".to_string())?;
+    let ctx = RunContext::new(&task, RunBudget::unlimited());
+    assert!(task
+        .get_node(&"glob_files".into())?
+        .run(task.get_instance(glob)?, &ctx, &input)
+        .await
+        .is_err());
+
+    Ok(())
+}
+
+async fn glob(sandbox: &Arc<Sandbox>, pattern: &str) -> eyre::Result<Vec<String>> {
+    let mut task = Task::new();
+    let glob = task.register_node(NodeGlobFiles::new(sandbox.clone()))?;
+    let glob = task.instantiate(&glob)?;
+    task.set_instance_memory(glob, NodeGlobFiles::MEMORY_PATTERN, pattern.to_string())?;
+
+    let ctx = RunContext::new(&task, RunBudget::unlimited());
+    let output = task
+        .get_node(&"glob_files".into())?
+        .run(task.get_instance(glob)?, &ctx, &InstanceRefArgs::new())
+        .await?;
+
+    Ok(output[NodeGlobFiles::OUTPUT_ARG_PATHS]
+        .downcast::<Vec<String>>()?
+        .clone())
+}
+
+#[tokio::test]
+async fn glob_starts_at_literal_prefix() -> eyre::Result<()> {
+    let (dir, sandbox) = sandbox("glob-prefix");
+    let root = sandbox.root();
+    std::fs::create_dir_all(root.join("docs/api/v1"))?;
+    std::fs::write(root.join("docs/api/v1/a.md"), "")?;
+    std::fs::write(root.join("docs/b.md"), "")?;
+    std::fs::write(root.join("c.md"), "")?;
+
+    assert_eq!(
+        glob(&sandbox, "docs/api/**/*.md").await?,
+        ["docs/api/v1/a.md"]
+    );
+    assert_eq!(glob(&sandbox, "docs/b.md").await?, ["docs/b.md"]);
+    assert_eq!(glob(&sandbox, "*.md").await?, ["c.md"]);
+    assert!(glob(&sandbox, "missing/**/*.md").await?.is_empty());
+
+    // the prefix is resolved in the sandbox, a symlink leading out of it matches nothing
+    #[cfg(unix)]
+    {
+        std::fs::create_dir_all(dir.join("outside"))?;
+        std::fs::write(dir.join("outside/secret.md"), "")?;
+        std::os::unix::fs::symlink(dir.join("outside"), root.join("out"))?;
+        assert!(glob(&sandbox, "out/*.md").await?.is_empty());
+    }
+
+    Ok(())
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn glob_skips_unreadable_directories() -> eyre::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let (_dir, sandbox) = sandbox("glob-unreadable");
+    let root = sandbox.root();
+    std::fs::create_dir_all(root.join("locked"))?;
+    std::fs::write(root.join("locked/a.md"), "")?;
+    std::fs::write(root.join("b.md"), "")?;
+    std::fs::set_permissions(root.join("locked"), std::fs::Permissions::from_mode(0o000))?;
+
+    // permissions do not apply to root
+    let is_locked = std::fs::read_dir(root.join("locked")).is_err();
+    let paths = glob(&sandbox, "**/*.md").await;
+    std::fs::set_permissions(root.join("locked"), std::fs::Permissions::from_mode(0o755))?;
+
+    if is_locked {
+        assert_eq!(paths?, ["b.md"]);
+    } else {
+        assert_eq!(paths?, ["b.md", "locked/a.md"]);
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn write_outside_is_rejected() -> eyre::Result<()> {
+    let (dir, sandbox) = sandbox("escape");
+
+    let mut task = Task::new();
+    let text = task.register_node(NodeText)?;
+    let write = task.register_node(NodeWriteFile::new(sandbox))?;
+    let text = task.instantiate(&text)?;
+    let write = task.instantiate(&write)?;
+
+    task.set_instance_memory(text, NodeText::MEMORY_TEXT, "pwned".to_string())?;
+    task.set_instance_memory(
+        write,
+        NodeWriteFile::MEMORY_PATH,
+        "../escape.txt".to_string(),
+    )?;
+    task.connect(
+        text,
+        NodeText::OUT_ARG_TEXT,
+        write,
+        NodeWriteFile::INPUT_ARG_TEXT,
+    )?;
+
+    let err = task.run(&RunBudget::unlimited()).await.unwrap_err();
+    assert!(format!("{err:#}").contains("escapes the sandbox"));
+    assert!(!dir.join("escape.txt").exists());
+
+    Ok(())
+}
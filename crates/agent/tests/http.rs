@@ -0,0 +1,229 @@
+use agent::*;
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Redirect};
+use axum::routing::{get, post};
+use axum::Json;
+use node::*;
+use std::sync::Arc;
+use std::time::Duration;
+
+async fn start_server() -> String {
+    let router = axum::Router::new()
+        .route(
+            "/json",
+            get(|| async { Json(serde_json::json!({ "name": "agent", "items": [1, 2] })) }),
+        )
+        .route(
+            "/echo",
+            post(|headers: HeaderMap, body: String| async move {
+                let header = headers
+                    .get("x-test")
+                    .map(|value| value.to_str().unwrap().to_string());
+                (
+                    StatusCode::CREATED,
+                    [("x-echo", "yes")],
+                    Json(serde_json::json!({ "header": header, "body": body })),
+                )
+            }),
+        )
+        .route(
+            "/missing",
+            get(|| async { (StatusCode::NOT_FOUND, "not found") }),
+        )
+        .route("/big", get(|| async { "x".repeat(4096) }))
+        .route(
+            "/slow",
+            get(|| async {
+                tokio::time::sleep(Duration::from_secs(5)).await;
+                "late"
+            }),
+        )
+        .route(
+            "/redirect",
+            get(|| async { Redirect::temporary("http://localhost:1/json").into_response() }),
+        );
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+
+    url
+}
+
+fn policy() -> HttpPolicy {
+    HttpPolicy {
+        allowed_hosts: ["127.0.0.1".to_string()].into(),
+        timeout: Duration::from_secs(1),
+        max_response_bytes: 1024,
+    }
+}
+
+async fn request(
+    policy: HttpPolicy,
+    method: &str,
+    url: &str,
+    headers: Option<serde_json::Value>,
+    body: Option<&str>,
+) -> eyre::Result<std::collections::BTreeMap<String, Value>> {
+    let mut task = Task::new();
+    let node = task.register_node(NodeHttpRequest::new(Arc::new(policy))?)?;
+    let instance = task.instantiate(&node)?;
+
+    task.set_instance_memory(instance, NodeHttpRequest::MEMORY_METHOD, method.to_string())?;
+    task.set_instance_memory(instance, NodeHttpRequest::MEMORY_URL, url.to_string())?;
+
+    let headers = headers.map(Value::new);
+    let body = body.map(|body| Value::new(body.to_string()));
+    let mut input = InstanceRefArgs::new();
+    if let Some(headers) = &headers {
+        input.insert(NodeHttpRequest::INPUT_ARG_HEADERS, headers);
+    }
+    if let Some(body) = &body {
+        input.insert(NodeHttpRequest::INPUT_ARG_BODY, body);
+    }
+
+    let ctx = RunContext::new(&task, RunBudget::unlimited());
+    task.get_node(&node)?
+        .run(task.get_instance(instance)?, &ctx, &input)
+        .await
+}
+
+#[tokio::test]
+async fn get_json() {
+    let url = start_server().await;
+    let output = request(policy(), "GET", &format!("{url}/json"), None, None)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        *output[NodeHttpRequest::OUTPUT_ARG_STATUS]
+            .downcast::<i64>()
+            .unwrap(),
+        200
+    );
+    assert_eq!(
+        output[NodeHttpRequest::OUTPUT_ARG_JSON]
+            .downcast::<serde_json::Value>()
+            .unwrap(),
+        &serde_json::json!({ "name": "agent", "items": [1, 2] })
+    );
+    assert_eq!(
+        output[NodeHttpRequest::OUTPUT_ARG_HEADERS]
+            .downcast::<serde_json::Value>()
+            .unwrap()["content-type"],
+        "application/json"
+    );
+}
+
+#[tokio::test]
+async fn post_with_headers_and_body() {
+    let url = start_server().await;
+    let output = request(
+        policy(),
+        "post",
+        &format!("{url}/echo"),
+        Some(serde_json::json!({ "X-Test": "value" })),
+        Some("hello"),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(
+        *output[NodeHttpRequest::OUTPUT_ARG_STATUS]
+            .downcast::<i64>()
+            .unwrap(),
+        201
+    );
+    assert_eq!(
+        output[NodeHttpRequest::OUTPUT_ARG_JSON]
+            .downcast::<serde_json::Value>()
+            .unwrap(),
+        &serde_json::json!({ "header": "value", "body": "hello" })
+    );
+    assert_eq!(
+        output[NodeHttpRequest::OUTPUT_ARG_HEADERS]
+            .downcast::<serde_json::Value>()
+            .unwrap()["x-echo"],
+        "yes"
+    );
+}
+
+#[tokio::test]
+async fn error_status_is_output() {
+    let url = start_server().await;
+    let output = request(policy(), "GET", &format!("{url}/missing"), None, None)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        *output[NodeHttpRequest::OUTPUT_ARG_STATUS]
+            .downcast::<i64>()
+            .unwrap(),
+        404
+    );
+    assert_eq!(
+        output[NodeHttpRequest::OUTPUT_ARG_BODY]
+            .downcast::<String>()
+            .unwrap(),
+        "not found"
+    );
+    assert!(output[NodeHttpRequest::OUTPUT_ARG_JSON]
+        .downcast::<serde_json::Value>()
+        .unwrap()
+        .is_null());
+}
+
+#[tokio::test]
+async fn host_not_allowed() {
+    let url = start_server().await;
+    let url = url.replace("127.0.0.1", "localhost");
+
+    let result = request(policy(), "GET", &format!("{url}/json"), None, None).await;
+    if let Err(err) = result {
+        assert!(err.to_string().contains("not allowed"), "{err}");
+    } else {
+        panic!("request to a host outside of the allowlist succeeded");
+    }
+}
+
+#[tokio::test]
+async fn redirect_to_other_host_is_rejected() {
+    let url = start_server().await;
+
+    let result = request(policy(), "GET", &format!("{url}/redirect"), None, None).await;
+    if let Err(err) = result {
+        assert!(format!("{err:?}").contains("not allowed"), "{err:?}");
+    } else {
+        panic!("redirect to a host outside of the allowlist was followed");
+    }
+}
+
+#[tokio::test]
+async fn response_size_and_timeout_limits() {
+    let url = start_server().await;
+
+    let result = request(policy(), "GET", &format!("{url}/big"), None, None).await;
+    if let Err(err) = result {
+        assert!(format!("{err:#}").contains("exceeds 1024 bytes"), "{err:#}");
+    } else {
+        panic!("oversized response was accepted");
+    }
+
+    let result = request(policy(), "GET", &format!("{url}/slow"), None, None).await;
+    assert!(result.is_err());
+}
+
+#[test]
+fn wildcard_hosts() {
+    let policy = HttpPolicy {
+        allowed_hosts: ["*.example.com".to_string()].into(),
+        ..policy()
+    };
+
+    let allowed = |url: &str| policy.is_allowed(&url.parse().unwrap());
+    assert!(allowed("https://api.example.com/v1"));
+    assert!(allowed("https://a.b.EXAMPLE.com"));
+    assert!(!allowed("https://example.com"));
+    assert!(!allowed("https://badexample.com"));
+    assert!(!allowed("https://example.com.evil.net"));
+}
@@ -0,0 +1,66 @@
+use agent::*;
+use metrics_util::debugging::DebuggingRecorder;
+use node::*;
+
+fn run_task(task: &Task) -> eyre::Result<RunReport> {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?
+        .block_on(task.run(&RunBudget::unlimited()))
+}
+
+#[test]
+fn node_metrics_are_recorded() -> eyre::Result<()> {
+    let mut task = Task::new();
+    task.register_built_in_nodes()?;
+
+    let text = task.instantiate(&"text".into())?;
+    let parse = task.instantiate(&"parse_json".into())?;
+    let print = task.instantiate(&"print".into())?;
+    task.set_instance_memory(text, "text", "not json".to_string())?;
+    task.connect(text, "text", parse, "text")?;
+    task.connect(text, "text", print, "text")?;
+
+    // the recorder is local to the thread, so the run must not leave it
+    let recorder = DebuggingRecorder::new();
+    let snapshotter = recorder.snapshotter();
+    metrics::with_local_recorder(&recorder, || {
+        assert!(run_task(&task).is_err());
+        assert!(run_task(&task).is_err());
+    });
+
+    let summary = MetricsSummary::from_snapshot(snapshotter.snapshot());
+    assert_eq!(
+        summary.nodes.keys().collect::<Vec<_>>(),
+        ["parse_json", "print", "text"]
+    );
+
+    let parse = &summary.nodes["parse_json"];
+    assert_eq!(parse.executions, 2);
+    assert_eq!(parse.errors, 2);
+    assert_eq!(parse.durations.len(), 2);
+    assert!(parse.duration_percentile(95.0) >= parse.duration_percentile(50.0));
+
+    let print = &summary.nodes["print"];
+    assert_eq!(print.executions, 2);
+    assert_eq!(print.errors, 0);
+
+    let table = summary.to_string();
+    assert!(table.starts_with("node"));
+    assert!(table.lines().any(|line| line.starts_with("parse_json")));
+
+    Ok(())
+}
+
+#[test]
+fn duration_percentiles() {
+    let node = NodeMetrics {
+        durations: (1..=20).map(f64::from).collect(),
+        ..Default::default()
+    };
+
+    assert_eq!(node.duration_percentile(50.0), Some(10.0));
+    assert_eq!(node.duration_percentile(95.0), Some(19.0));
+    assert_eq!(node.duration_percentile(100.0), Some(20.0));
+    assert_eq!(NodeMetrics::default().duration_percentile(95.0), None);
+}
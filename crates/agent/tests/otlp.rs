@@ -0,0 +1,129 @@
+#![cfg(feature = "otlp")]
+
+mod common;
+
+use agent::*;
+use axum::http::StatusCode;
+use common::{MockResponse, MockServer};
+use init_log::*;
+use node::*;
+use serde_json::json;
+use std::sync::Arc;
+
+fn llm_providers(server: &MockServer) -> Arc<LlmProviders> {
+    let ollama = Arc::new(OllamaProvider::new(&ProviderConfig {
+        kind: ProviderKind::Ollama,
+        api_url: Some(server.url.clone()),
+        api_token: None,
+        model: "test-model".to_string(),
+        rate_limit: RateLimitConfig::default(),
+    }));
+    let provider = RateLimitedProvider::new("local", ollama, &RateLimitConfig::default());
+
+    Arc::new(LlmProviders::new("local").with_provider("local", Arc::new(provider)))
+}
+
+/// Spans sent to the collector by name.
+fn exported_spans(collector: &MockServer) -> Vec<serde_json::Value> {
+    collector
+        .take_requests()
+        .into_iter()
+        .flat_map(|request| {
+            let mut spans = Vec::new();
+            for resource in request.body["resourceSpans"].as_array().unwrap() {
+                for scope in resource["scopeSpans"].as_array().unwrap() {
+                    spans.extend(scope["spans"].as_array().unwrap().iter().cloned());
+                }
+            }
+            spans
+        })
+        .collect()
+}
+
+fn attribute(span: &serde_json::Value, key: &str) -> Option<String> {
+    let value = span["attributes"]
+        .as_array()?
+        .iter()
+        .find(|attribute| attribute["key"] == key)?["value"]
+        .clone();
+
+    match value.as_object()?.values().next()? {
+        serde_json::Value::String(value) => Some(value.clone()),
+        value => Some(value.to_string()),
+    }
+}
+
+// the collector stub must keep answering while the guard blocks on the final export
+#[tokio::test(flavor = "multi_thread")]
+async fn run_is_exported_as_trace() -> eyre::Result<()> {
+    let collector = MockServer::start("/v1/traces", json!({})).await;
+    let guard = LogInit::for_tests()
+        .with_config(LogConfig {
+            otlp: Some(OtlpConfig {
+                endpoint: collector.url.clone(),
+                protocol: OtlpProtocol::HttpJson,
+                service_name: "agent-test".to_string(),
+            }),
+            ..Default::default()
+        })
+        .init()?;
+
+    let llm = MockServer::start_with_responses(
+        "/api/chat",
+        vec![
+            MockResponse::status(StatusCode::TOO_MANY_REQUESTS).with_header("retry-after", "0"),
+            MockResponse::ok(json!({
+                "message": { "role": "assistant", "content": "Hi!" },
+                "prompt_eval_count": 12,
+                "eval_count": 3,
+            })),
+        ],
+    )
+    .await;
+
+    let mut task = Task::new();
+    task.register_built_in_nodes()?;
+    task.register_node(NodeLLM::new(llm_providers(&llm)))?;
+
+    let text = task.instantiate(&"text".into())?;
+    let model = task.instantiate(&"llm".into())?;
+    task.set_instance_memory(text, "text", "Hello".to_string())?;
+    task.connect(text, "text", model, NodeLLM::INPUT_ARG_CONTEXT)?;
+    task.run(&RunBudget::unlimited()).await?;
+
+    drop(guard);
+
+    let spans = exported_spans(&collector);
+    let run = spans
+        .iter()
+        .find(|span| {
+            span["name"] == "run"
+                && attribute(span, "code.module.name").as_deref() == Some("node::state")
+        })
+        .expect("run span is exported");
+    assert_eq!(attribute(run, "run_id").as_deref(), Some("1"));
+    assert_eq!(attribute(run, "prompt_tokens").as_deref(), Some("12"));
+
+    let nodes = spans
+        .iter()
+        .filter(|span| span["name"] == "run_instance")
+        .collect::<Vec<_>>();
+    assert_eq!(nodes.len(), 2);
+    for node in &nodes {
+        assert_eq!(node["traceId"], run["traceId"]);
+        assert_eq!(node["parentSpanId"], run["spanId"]);
+    }
+
+    let llm_span = nodes
+        .iter()
+        .find(|span| attribute(span, "node_id").as_deref() == Some("llm"))
+        .expect("LLM node span is exported");
+    assert_eq!(attribute(llm_span, "instance_id"), Some(model.to_string()));
+    assert_eq!(
+        attribute(llm_span, "completion_tokens").as_deref(),
+        Some("3")
+    );
+    assert_eq!(attribute(llm_span, "retries").as_deref(), Some("1"));
+
+    Ok(())
+}
@@ -0,0 +1,289 @@
+mod common;
+
+use agent::*;
+use common::MockServer;
+use node::*;
+use serde_json::json;
+use std::sync::Arc;
+
+fn provider_config(kind: ProviderKind, api_url: &str) -> ProviderConfig {
+    ProviderConfig {
+        kind,
+        api_url: Some(api_url.to_string()),
+        api_token: Some("secret".to_string()),
+        model: "test-model".to_string(),
+        rate_limit: RateLimitConfig::default(),
+    }
+}
+
+fn schema() -> serde_json::Value {
+    json!({
+        "type": "object",
+        "properties": { "answer": { "type": "string" } },
+        "required": ["answer"],
+    })
+}
+
+#[tokio::test]
+async fn openai_provider() {
+    let server = MockServer::start(
+        "/v1/chat/completions",
+        json!({
+            "choices": [{
+                "message": {
+                    "role": "assistant",
+                    "content": "Hello!",
+                    "tool_calls": [{
+                        "id": "call_1",
+                        "type": "function",
+                        "function": { "name": "search", "arguments": "{\"query\":\"rust\"}" },
+                    }],
+                },
+            }],
+        }),
+    )
+    .await;
+
+    let provider = OpenAiProvider::new(&provider_config(
+        ProviderKind::OpenAi,
+        &format!("{}/v1", server.url),
+    ));
+
+    let conversation = Conversation::new()
+        .with_message(ChatMessage::system("Be brief"))
+        .with_message(ChatMessage::user("Hi").with_name("bob"));
+    let request = LlmRequest::new(conversation).with_response_format(ResponseFormat::JsonSchema {
+        name: "response".to_string(),
+        schema: schema(),
+    });
+
+    let response = provider.complete(&request).await.unwrap();
+    assert_eq!(response.text(), "Hello!");
+    assert_eq!(response.message.tool_calls[0].name, "search");
+    assert_eq!(
+        response.message.tool_calls[0].arguments,
+        json!({ "query": "rust" })
+    );
+
+    let requests = server.take_requests();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].headers["authorization"], "Bearer secret");
+
+    let body = &requests[0].body;
+    assert_eq!(body["model"], "test-model");
+    assert_eq!(
+        body["messages"],
+        json!([
+            { "role": "system", "content": "Be brief" },
+            { "role": "user", "content": "Hi", "name": "bob" },
+        ])
+    );
+    assert_eq!(body["response_format"]["json_schema"]["schema"], schema());
+}
+
+#[tokio::test]
+async fn anthropic_provider() {
+    let server = MockServer::start(
+        "/v1/messages",
+        json!({
+            "content": [{
+                "type": "tool_use",
+                "id": "toolu_1",
+                "name": "response",
+                "input": { "answer": "42" },
+            }],
+        }),
+    )
+    .await;
+
+    let provider = AnthropicProvider::new(&provider_config(ProviderKind::Anthropic, &server.url));
+
+    let conversation = Conversation::new()
+        .with_message(ChatMessage::system("Be brief"))
+        .with_message(ChatMessage::user("Question?"));
+    let request = LlmRequest::new(conversation)
+        .with_model("other-model")
+        .with_response_format(ResponseFormat::JsonSchema {
+            name: "response".to_string(),
+            schema: schema(),
+        });
+
+    let response = provider.complete(&request).await.unwrap();
+    assert_eq!(
+        serde_json::from_str::<serde_json::Value>(&response.text()).unwrap(),
+        json!({ "answer": "42" })
+    );
+    assert!(response.message.tool_calls.is_empty());
+
+    let requests = server.take_requests();
+    assert_eq!(requests[0].headers["x-api-key"], "secret");
+    assert_eq!(
+        requests[0].headers["anthropic-version"],
+        AnthropicProvider::API_VERSION
+    );
+
+    let body = &requests[0].body;
+    assert_eq!(body["model"], "other-model");
+    assert_eq!(body["system"], "Be brief");
+    assert_eq!(
+        body["messages"],
+        json!([{ "role": "user", "content": [{ "type": "text", "text": "Question?" }] }])
+    );
+    assert_eq!(
+        body["tool_choice"],
+        json!({ "type": "tool", "name": "response" })
+    );
+    assert_eq!(body["tools"][0]["input_schema"], schema());
+}
+
+#[tokio::test]
+async fn ollama_provider() {
+    let server = MockServer::start(
+        "/api/chat",
+        json!({ "message": { "role": "assistant", "content": "Local hello" }, "done": true }),
+    )
+    .await;
+
+    let provider = OllamaProvider::new(&provider_config(ProviderKind::Ollama, &server.url));
+
+    let request = LlmRequest {
+        max_tokens: Some(10),
+        ..LlmRequest::new(Conversation::new().with_message(ChatMessage::user("Hi")))
+    };
+
+    let response = provider.complete(&request).await.unwrap();
+    assert_eq!(response.text(), "Local hello");
+
+    let body = &server.take_requests()[0].body;
+    assert_eq!(body["stream"], false);
+    assert_eq!(body["options"]["num_predict"], 10);
+    assert_eq!(
+        body["messages"],
+        json!([{ "role": "user", "content": "Hi" }])
+    );
+}
+
+#[tokio::test]
+async fn ollama_provider_images() {
+    let server = MockServer::start(
+        "/api/chat",
+        json!({ "message": { "role": "assistant", "content": "A cat" } }),
+    )
+    .await;
+    let provider = OllamaProvider::new(&provider_config(ProviderKind::Ollama, &server.url));
+
+    let message = ChatMessage::user("What is it?")
+        .with_part(ContentPart::image_url("data:image/png;base64,iVBORw0KGgo="));
+    let request = LlmRequest::new(Conversation::new().with_message(message));
+    provider.complete(&request).await.unwrap();
+
+    let body = &server.take_requests()[0].body;
+    assert_eq!(body["messages"][0]["images"], json!(["iVBORw0KGgo="]));
+
+    // remote images are not fetched
+    let message = ChatMessage::user("What is it?")
+        .with_part(ContentPart::image_url("https://example.com/cat.png"));
+    let request = LlmRequest::new(Conversation::new().with_message(message));
+    let err = provider.complete(&request).await.unwrap_err();
+    assert!(
+        format!("{err:#}").contains("only base64 data URL images"),
+        "{err:#}"
+    );
+    assert!(server.take_requests().is_empty());
+}
+
+#[tokio::test]
+async fn provider_selection() {
+    let server = MockServer::start(
+        "/api/chat",
+        json!({ "message": { "role": "assistant", "content": "from local" } }),
+    )
+    .await;
+
+    let local = Arc::new(OllamaProvider::new(&provider_config(
+        ProviderKind::Ollama,
+        &server.url,
+    )));
+    let providers = LlmProviders::new("local").with_provider("local", local);
+
+    let request = LlmRequest::new(Conversation::new().with_message(ChatMessage::user("Hi")));
+
+    let default = providers.get(None).unwrap();
+    assert_eq!(
+        default.complete(&request).await.unwrap().text(),
+        "from local"
+    );
+
+    assert!(providers.get(Some("missing")).is_err());
+}
+
+#[tokio::test]
+async fn usage_and_cost() {
+    let openai = MockServer::start(
+        "/chat/completions",
+        json!({
+            "model": "priced-model",
+            "choices": [{ "message": { "role": "assistant", "content": "ok" } }],
+            "usage": { "prompt_tokens": 1000, "completion_tokens": 500 },
+        }),
+    )
+    .await;
+    let ollama = MockServer::start(
+        "/api/chat",
+        json!({ "message": { "role": "assistant", "content": "12345678" } }),
+    )
+    .await;
+
+    let providers = LlmProviders::new("openai")
+        .with_provider(
+            "openai",
+            Arc::new(OpenAiProvider::new(&provider_config(
+                ProviderKind::OpenAi,
+                &openai.url,
+            ))),
+        )
+        .with_provider(
+            "ollama",
+            Arc::new(OllamaProvider::new(&provider_config(
+                ProviderKind::Ollama,
+                &ollama.url,
+            ))),
+        )
+        .with_prices(PriceTable::from([(
+            "priced-model".to_string(),
+            ModelPrice {
+                prompt: 1.0,
+                completion: 2.0,
+            },
+        )]));
+
+    let request = LlmRequest::new(Conversation::new().with_message(ChatMessage::user("1234")));
+
+    let usage = providers
+        .complete(None, &request)
+        .await
+        .unwrap()
+        .usage
+        .unwrap();
+    assert_eq!(usage.prompt_tokens, 1000);
+    assert_eq!(usage.completion_tokens, 500);
+    assert!(!usage.estimated);
+    assert!((usage.cost - 0.002).abs() < 1e-9);
+
+    let usage = providers
+        .complete(Some("ollama"), &request)
+        .await
+        .unwrap()
+        .usage
+        .unwrap();
+    assert!(usage.estimated);
+    assert_eq!(
+        usage.prompt_tokens,
+        request.conversation.estimate_tokens() as u64
+    );
+    assert_eq!(
+        usage.completion_tokens,
+        ChatMessage::assistant("12345678").estimate_tokens() as u64
+    );
+    assert_eq!(usage.cost, 0.0);
+}
@@ -0,0 +1,236 @@
+mod common;
+
+use agent::*;
+use axum::http::StatusCode;
+use common::{MockResponse, MockServer};
+use node::*;
+use serde_json::json;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Provider answering after a delay and tracking the amount of concurrent calls.
+#[derive(Default)]
+struct SlowProvider {
+    in_flight: AtomicUsize,
+    max_in_flight: AtomicUsize,
+    calls: AtomicUsize,
+}
+
+impl LlmProvider for SlowProvider {
+    fn model(&self) -> &str {
+        "slow"
+    }
+
+    fn complete<'a>(&'a self, _request: &'a LlmRequest) -> LlmResult<'a> {
+        Box::pin(async move {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            let in_flight = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_in_flight.fetch_max(in_flight, Ordering::SeqCst);
+
+            tokio::time::sleep(Duration::from_secs(1)).await;
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+
+            Ok(LlmResponse {
+                message: ChatMessage::assistant("ok"),
+                model: "slow".to_string(),
+                usage: None,
+                retries: 0,
+            })
+        })
+    }
+}
+
+/// Provider rejecting the first `rejections` calls with `429 Too Many Requests`.
+struct RejectingProvider {
+    rejections: usize,
+    retry_after: Option<Duration>,
+    calls: AtomicUsize,
+}
+
+impl LlmProvider for RejectingProvider {
+    fn model(&self) -> &str {
+        "rejecting"
+    }
+
+    fn complete<'a>(&'a self, _request: &'a LlmRequest) -> LlmResult<'a> {
+        Box::pin(async move {
+            if self.calls.fetch_add(1, Ordering::SeqCst) < self.rejections {
+                return Err(LlmHttpError {
+                    status: StatusCode::TOO_MANY_REQUESTS,
+                    retry_after: self.retry_after,
+                    body: String::new(),
+                }
+                .into());
+            }
+
+            Ok(LlmResponse {
+                message: ChatMessage::assistant("ok"),
+                model: "rejecting".to_string(),
+                usage: None,
+                retries: 0,
+            })
+        })
+    }
+}
+
+fn ollama(server: &MockServer) -> Arc<dyn LlmProvider> {
+    Arc::new(OllamaProvider::new(&ProviderConfig {
+        kind: ProviderKind::Ollama,
+        api_url: Some(server.url.clone()),
+        api_token: None,
+        model: "test-model".to_string(),
+        rate_limit: RateLimitConfig::default(),
+    }))
+}
+
+fn rate_limited_server() -> Vec<MockResponse> {
+    vec![
+        MockResponse::status(StatusCode::TOO_MANY_REQUESTS).with_header("retry-after", "1"),
+        MockResponse::ok(json!({ "message": { "role": "assistant", "content": "ok" } })),
+    ]
+}
+
+fn request(text: &str) -> LlmRequest {
+    LlmRequest::new(Conversation::new().with_message(ChatMessage::user(text)))
+}
+
+async fn complete_concurrently(provider: Arc<dyn LlmProvider>, calls: usize) {
+    let handles = (0..calls)
+        .map(|_| {
+            let provider = provider.clone();
+            tokio::spawn(async move { provider.complete(&request("Hi")).await.unwrap() })
+        })
+        .collect::<Vec<_>>();
+
+    for handle in handles {
+        handle.await.unwrap();
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn max_in_flight() {
+    let slow = Arc::new(SlowProvider::default());
+    let config = RateLimitConfig {
+        max_in_flight: Some(2),
+        ..Default::default()
+    };
+    let provider = Arc::new(RateLimitedProvider::new("slow", slow.clone(), &config));
+
+    let started_at = Instant::now();
+    complete_concurrently(provider, 5).await;
+
+    assert_eq!(slow.calls.load(Ordering::SeqCst), 5);
+    assert_eq!(slow.max_in_flight.load(Ordering::SeqCst), 2);
+    assert!(started_at.elapsed() >= Duration::from_secs(3));
+}
+
+#[tokio::test(start_paused = true)]
+async fn requests_per_minute() {
+    let slow = Arc::new(SlowProvider::default());
+    let config = RateLimitConfig {
+        requests_per_minute: Some(2),
+        ..Default::default()
+    };
+    let provider = Arc::new(RateLimitedProvider::new("slow", slow.clone(), &config));
+
+    let started_at = Instant::now();
+    complete_concurrently(provider, 3).await;
+
+    // burst of two calls, the third one waits for half a minute to refill
+    assert_eq!(slow.calls.load(Ordering::SeqCst), 3);
+    assert!(started_at.elapsed() >= Duration::from_secs(30));
+    assert!(started_at.elapsed() < Duration::from_secs(40));
+}
+
+#[tokio::test(start_paused = true)]
+async fn tokens_per_minute() {
+    let slow = Arc::new(SlowProvider::default());
+    let config = RateLimitConfig {
+        tokens_per_minute: Some(100),
+        ..Default::default()
+    };
+    let provider = RateLimitedProvider::new("slow", slow.clone(), &config);
+
+    // ~54 tokens each, the second call waits for the bucket to refill
+    let text = "a".repeat(200);
+
+    let started_at = Instant::now();
+    provider.complete(&request(&text)).await.unwrap();
+    assert!(started_at.elapsed() < Duration::from_secs(2));
+
+    provider.complete(&request(&text)).await.unwrap();
+    assert!(started_at.elapsed() >= Duration::from_secs(5));
+}
+
+#[tokio::test]
+async fn retry_after() {
+    // without the wrapper the typed error is returned
+    let server = MockServer::start_with_responses("/api/chat", rate_limited_server()).await;
+    let err = ollama(&server).complete(&request("Hi")).await.unwrap_err();
+    let http_err = err.downcast_ref::<LlmHttpError>().unwrap();
+    assert!(http_err.is_rate_limited());
+    assert_eq!(http_err.retry_after, Some(Duration::from_secs(1)));
+
+    let server = MockServer::start_with_responses("/api/chat", rate_limited_server()).await;
+    let provider = RateLimitedProvider::new("local", ollama(&server), &RateLimitConfig::default());
+
+    let started_at = std::time::Instant::now();
+    let response = provider.complete(&request("Hi")).await.unwrap();
+    assert_eq!(response.text(), "ok");
+    assert_eq!(server.take_requests().len(), 2);
+    assert!(started_at.elapsed() >= Duration::from_secs(1));
+}
+
+#[tokio::test]
+async fn retries_are_limited() {
+    let server = MockServer::start_with_responses(
+        "/api/chat",
+        vec![MockResponse::status(StatusCode::TOO_MANY_REQUESTS).with_header("retry-after", "0")],
+    )
+    .await;
+
+    let config = RateLimitConfig {
+        max_retries: 2,
+        ..Default::default()
+    };
+    let provider = RateLimitedProvider::new("local", ollama(&server), &config);
+
+    let err = provider.complete(&request("Hi")).await.unwrap_err();
+    assert!(err.downcast_ref::<LlmHttpError>().is_some());
+    assert_eq!(server.take_requests().len(), 3);
+}
+
+#[tokio::test(start_paused = true)]
+async fn retry_delay_is_capped() {
+    // the exponential backoff would overflow long before the last attempt
+    let rejecting = Arc::new(RejectingProvider {
+        rejections: 40,
+        retry_after: None,
+        calls: AtomicUsize::new(0),
+    });
+    let config = RateLimitConfig {
+        max_retries: 100,
+        ..Default::default()
+    };
+    let provider = RateLimitedProvider::new("rejecting", rejecting.clone(), &config);
+
+    let started_at = Instant::now();
+    let response = provider.complete(&request("Hi")).await.unwrap();
+    assert_eq!(response.retries, 40);
+    assert_eq!(rejecting.calls.load(Ordering::SeqCst), 41);
+    assert!(started_at.elapsed() <= Duration::from_secs(40 * 60));
+
+    // a long Retry-After is capped as well
+    let rejecting = Arc::new(RejectingProvider {
+        rejections: 1,
+        retry_after: Some(Duration::from_secs(3600)),
+        calls: AtomicUsize::new(0),
+    });
+    let provider = RateLimitedProvider::new("rejecting", rejecting, &config);
+
+    let started_at = Instant::now();
+    provider.complete(&request("Hi")).await.unwrap();
+    assert!(started_at.elapsed() <= Duration::from_secs(61));
+}
@@ -0,0 +1,152 @@
+use agent::*;
+use envstruct::prelude::*;
+use node::*;
+use std::sync::Arc;
+
+fn repl() -> Repl {
+    std::env::set_var("AGENT_REPL_TEST_SANDBOX_ROOT", std::env::temp_dir());
+    let config = Config::with_prefix("AGENT_REPL_TEST").unwrap();
+
+    Repl::new(Arc::new(config)).unwrap()
+}
+
+async fn execute(repl: &mut Repl, lines: &[&str]) -> eyre::Result<String> {
+    let mut output = String::new();
+    for line in lines {
+        output.push_str(&repl.execute(line).await?);
+    }
+
+    Ok(output)
+}
+
+#[tokio::test]
+async fn repl_builds_runs_and_saves_graph() -> eyre::Result<()> {
+    let mut repl = repl();
+
+    let output = execute(
+        &mut repl,
+        &[
+            "add text",
+            "add print",
+            r#"set 10000 text "hello world""#,
+            "connect 10000.text 10001.text",
+            "validate",
+        ],
+    )
+    .await?;
+    assert!(output.contains("Added 10000 (text)"), "{output}");
+    assert!(output.contains("Graph is valid"), "{output}");
+
+    let output = repl.execute("show").await?;
+    assert!(
+        output.contains(r#"memory text = "hello world""#),
+        "{output}"
+    );
+    assert!(output.contains("in text <- 10000.text"), "{output}");
+
+    let output = repl.execute("run").await?;
+    assert!(output.contains("10001 print succeeded"), "{output}");
+
+    let path = std::env::temp_dir().join(format!("agent-repl-{}.json", std::process::id()));
+    repl.execute(&format!("save {}", path.display())).await?;
+
+    let mut loaded = self::repl();
+    loaded.execute(&format!("load {}", path.display())).await?;
+    let text = loaded
+        .task()
+        .get_instance(NodeInstanceId(10000))?
+        .get_memory::<String>("text")?;
+    assert_eq!(text.map(String::as_str), Some("hello world"));
+    assert_eq!(
+        loaded
+            .task()
+            .get_instance(NodeInstanceId(10001))?
+            .input_connections["text"]
+            .instance,
+        NodeInstanceId(10000)
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn repl_set_value_after_repeated_spaces() -> eyre::Result<()> {
+    let mut repl = repl();
+    execute(
+        &mut repl,
+        &[
+            "add text",
+            "set  10000 text hello",
+            "add text",
+            "  set 10001\ttext   two  words  ",
+        ],
+    )
+    .await?;
+
+    let text = |id| -> eyre::Result<Option<String>> {
+        Ok(repl
+            .task()
+            .get_instance(NodeInstanceId(id))?
+            .get_memory::<String>("text")?
+            .cloned())
+    };
+    assert_eq!(text(10000)?.as_deref(), Some("hello"));
+    assert_eq!(text(10001)?.as_deref(), Some("two  words"));
+
+    assert!(repl.execute("set 10000 text   ").await.is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn repl_reports_errors() -> eyre::Result<()> {
+    let mut repl = repl();
+
+    assert!(repl.execute("add missing").await.is_err());
+    assert!(repl.execute("frobnicate").await.is_err());
+    assert!(repl.execute("connect 10000").await.is_err());
+
+    repl.execute("add print").await?;
+    let err = repl.execute("validate").await.unwrap_err();
+    assert!(format!("{err:#}").contains("not connected"), "{err:#}");
+
+    // the graph is kept after a failed command
+    assert!(repl.execute("show").await?.contains("10000 print"));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn repl_completes_nodes_instances_and_ports() -> eyre::Result<()> {
+    let mut repl = repl();
+    execute(&mut repl, &["add text", "add print", "set 10000 text hi"]).await?;
+    let completer = repl.completer();
+
+    assert_eq!(
+        completer.candidates("con", 3),
+        (0, vec!["connect".to_string()])
+    );
+    assert_eq!(
+        completer.candidates("add pri", 7),
+        (4, vec!["print".to_string()])
+    );
+    // only instances with outputs can be connected from
+    assert_eq!(
+        completer.candidates("connect ", 8),
+        (8, vec!["10000.".to_string()])
+    );
+    assert_eq!(
+        completer.candidates("connect 10000.", 14),
+        (8, vec!["10000.text".to_string()])
+    );
+    assert_eq!(
+        completer.candidates("connect 10000.text 10001.t", 26),
+        (19, vec!["10001.text".to_string()])
+    );
+    assert_eq!(
+        completer.candidates("set 10000 t", 11),
+        (10, vec!["text".to_string()])
+    );
+
+    Ok(())
+}
@@ -0,0 +1,336 @@
+use agent::*;
+use envstruct::prelude::*;
+use futures::StreamExt;
+use reqwest::StatusCode;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio_tungstenite::tungstenite::Message;
+
+const TOKEN: &str = "test-token";
+
+fn config() -> Config {
+    std::env::set_var("AGENT_SERVER_TEST_SERVER_TOKEN", TOKEN);
+    std::env::set_var("AGENT_SERVER_TEST_SANDBOX_ROOT", std::env::temp_dir());
+    Config::with_prefix("AGENT_SERVER_TEST").unwrap()
+}
+
+async fn start_server() -> String {
+    start_server_with(config()).await
+}
+
+async fn start_server_with(config: Config) -> String {
+    let server = Server::new(Arc::new(config)).unwrap();
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap().to_string();
+    tokio::spawn(server.serve(listener));
+
+    addr
+}
+
+fn client() -> reqwest::Client {
+    let mut headers = reqwest::header::HeaderMap::new();
+    headers.insert(
+        reqwest::header::AUTHORIZATION,
+        format!("Bearer {TOKEN}").parse().unwrap(),
+    );
+    reqwest::Client::builder()
+        .default_headers(headers)
+        .build()
+        .unwrap()
+}
+
+fn hello_graph() -> serde_json::Value {
+    serde_json::json!({
+        "instances": [
+            { "id": 10000, "node": "text" },
+            { "id": 10001, "node": "print" },
+        ],
+        "connections": [{ "from": "10000.text", "to": "10001.text" }],
+        "inputs": { "greeting": "10000.text" },
+    })
+}
+
+async fn upload_graph(addr: &str, name: &str) {
+    let response = client()
+        .put(format!("http://{addr}/graphs/{name}"))
+        .json(&hello_graph())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+}
+
+async fn start_run(addr: &str, name: &str, body: serde_json::Value) -> reqwest::Response {
+    client()
+        .post(format!("http://{addr}/graphs/{name}/runs"))
+        .json(&body)
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn wait_for_run(addr: &str, id: u64) -> serde_json::Value {
+    for _ in 0..100 {
+        let run: serde_json::Value = client()
+            .get(format!("http://{addr}/runs/{id}"))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        if run["status"] != "running" {
+            return run;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+
+    panic!("Run {id} did not finish");
+}
+
+#[tokio::test]
+async fn server_requires_token() {
+    let addr = start_server().await;
+
+    let response = reqwest::get(format!("http://{addr}/graphs")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    let response = reqwest::Client::new()
+        .get(format!("http://{addr}/graphs"))
+        .bearer_auth("wrong")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    let response = reqwest::get(format!("http://{addr}/graphs?access_token={TOKEN}"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn server_decodes_access_token_query() {
+    let token = "test+token/=";
+    std::env::set_var("AGENT_SERVER_QUERY_TEST_SERVER_TOKEN", token);
+    std::env::set_var("AGENT_SERVER_QUERY_TEST_SANDBOX_ROOT", std::env::temp_dir());
+    let addr = start_server_with(Config::with_prefix("AGENT_SERVER_QUERY_TEST").unwrap()).await;
+
+    let response = reqwest::get(format!(
+        "http://{addr}/graphs?access_token=test%2Btoken%2F%3D"
+    ))
+    .await
+    .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // a literal `+` is a space in query strings
+    let response = reqwest::get(format!("http://{addr}/graphs?access_token={token}"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[test]
+fn internal_errors_are_not_exposed() {
+    let err = eyre::eyre!("permission denied").wrap_err("Failed to read /etc/agent/secret");
+    let err = ApiError::from(err);
+
+    assert_eq!(err.status, StatusCode::INTERNAL_SERVER_ERROR);
+    assert_eq!(err.message, "Internal server error");
+}
+
+#[tokio::test]
+async fn server_uploads_and_lists_graphs() {
+    let addr = start_server().await;
+    upload_graph(&addr, "hello").await;
+
+    let graphs: serde_json::Value = client()
+        .get(format!("http://{addr}/graphs"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(
+        graphs,
+        serde_json::json!([{ "name": "hello", "instances": 2, "inputs": ["greeting"] }])
+    );
+
+    let graph: serde_json::Value = client()
+        .get(format!("http://{addr}/graphs/hello"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(graph["instances"][0]["node"], "text");
+    assert!(graph["instances"][0]["ports"]["outputs"]["text"].is_object());
+    assert_eq!(graph["inputs"]["greeting"], "10000.text");
+
+    let response = client()
+        .get(format!("http://{addr}/graphs/missing"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn server_rejects_invalid_graphs() {
+    let addr = start_server().await;
+
+    let response = client()
+        .put(format!("http://{addr}/graphs/broken"))
+        .json(&serde_json::json!({ "instances": [{ "id": 10000, "node": "missing" }] }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert!(body["error"].as_str().unwrap().contains("missing"));
+
+    let response = client()
+        .put(format!("http://{addr}/graphs/bad%20name"))
+        .json(&hello_graph())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn server_runs_graph_with_inputs() {
+    let addr = start_server().await;
+    upload_graph(&addr, "hello").await;
+
+    let response = start_run(
+        &addr,
+        "hello",
+        serde_json::json!({ "inputs": { "greeting": "Hello server" } }),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::ACCEPTED);
+    let id = response.json::<serde_json::Value>().await.unwrap()["id"]
+        .as_u64()
+        .unwrap();
+
+    let run = wait_for_run(&addr, id).await;
+    assert_eq!(run["graph"], "hello");
+    assert_eq!(run["status"], "succeeded");
+    assert_eq!(run["report"]["instances"]["10001"]["status"], "succeeded");
+
+    let runs: serde_json::Value = client()
+        .get(format!("http://{addr}/runs"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(
+        runs,
+        serde_json::json!([{ "id": id, "graph": "hello", "status": "succeeded" }])
+    );
+}
+
+#[tokio::test]
+async fn server_rejects_missing_inputs() {
+    let addr = start_server().await;
+    upload_graph(&addr, "hello").await;
+
+    let response = start_run(&addr, "hello", serde_json::json!({})).await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert!(body["error"].as_str().unwrap().contains("greeting"));
+
+    let response = start_run(&addr, "missing", serde_json::json!({})).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn server_streams_run_events() {
+    let addr = start_server().await;
+    upload_graph(&addr, "hello").await;
+
+    let response = start_run(
+        &addr,
+        "hello",
+        serde_json::json!({ "inputs": { "greeting": "Hello events" } }),
+    )
+    .await;
+    let id = response.json::<serde_json::Value>().await.unwrap()["id"]
+        .as_u64()
+        .unwrap();
+
+    // history is replayed to subscribers connecting at any time
+    let (mut socket, _) = tokio_tungstenite::connect_async(format!(
+        "ws://{addr}/runs/{id}/events?access_token={TOKEN}"
+    ))
+    .await
+    .unwrap();
+
+    let mut events = Vec::new();
+    while let Some(message) = socket.next().await {
+        match message.unwrap() {
+            Message::Text(text) => {
+                events.push(serde_json::from_str::<serde_json::Value>(&text).unwrap())
+            }
+            Message::Close(_) => break,
+            _ => {}
+        }
+    }
+
+    let types = events
+        .iter()
+        .map(|event| event["type"].as_str().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(types.first(), Some(&"run_started"));
+    assert_eq!(types.last(), Some(&"run_finished"));
+    assert_eq!(types.iter().filter(|t| **t == "node_output").count(), 2);
+    assert!(events.iter().all(|event| event["run_id"] == id));
+}
+
+#[tokio::test]
+async fn server_exposes_metrics() {
+    std::env::set_var("AGENT_SERVER_METRICS_TEST_SERVER_TOKEN", TOKEN);
+    std::env::set_var("AGENT_SERVER_METRICS_TEST_SERVER_METRICS", "true");
+    let config = Config::with_prefix("AGENT_SERVER_METRICS_TEST").unwrap();
+
+    let addr = start_server_with(config).await;
+    upload_graph(&addr, "hello").await;
+    let response = start_run(
+        &addr,
+        "hello",
+        serde_json::json!({ "inputs": { "greeting": "Hello metrics" } }),
+    )
+    .await;
+    let id = response.json::<serde_json::Value>().await.unwrap()["id"]
+        .as_u64()
+        .unwrap();
+    wait_for_run(&addr, id).await;
+
+    // the recorder is global, so runs of other tests may be counted too
+    let metrics = client()
+        .get(format!("http://{addr}/metrics"))
+        .send()
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+    assert!(metrics.contains(r#"agent_node_executions_total{node_id="print"}"#));
+    assert!(metrics.contains(r#"agent_runs_total{status="succeeded"}"#));
+    assert!(metrics.contains("agent_node_duration_seconds{node_id=\"text\",quantile=\"0.95\"}"));
+
+    // metrics are disabled by default
+    let addr = start_server().await;
+    let response = client()
+        .get(format!("http://{addr}/metrics"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
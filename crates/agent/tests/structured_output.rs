@@ -0,0 +1,160 @@
+mod common;
+
+use agent::*;
+use common::{MockResponse, MockServer};
+use node::*;
+use serde_json::json;
+use std::sync::Arc;
+
+fn schema() -> serde_json::Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "name": { "type": "string" },
+            "count": { "type": "integer" },
+            "score": { "type": "number" },
+        },
+        "required": ["name", "count", "score"],
+    })
+}
+
+fn reply(content: &str) -> MockResponse {
+    MockResponse::ok(json!({
+        "message": { "role": "assistant", "content": content },
+        "prompt_eval_count": 10,
+        "eval_count": 5,
+    }))
+}
+
+fn task(server: &MockServer, max_retries: usize) -> eyre::Result<(Task, NodeInstanceId)> {
+    let ollama = Arc::new(OllamaProvider::new(&ProviderConfig {
+        kind: ProviderKind::Ollama,
+        api_url: Some(server.url.clone()),
+        api_token: None,
+        model: "test-model".to_string(),
+        rate_limit: RateLimitConfig::default(),
+    }));
+    let providers = Arc::new(LlmProviders::new("local").with_provider("local", ollama));
+
+    let mut task = Task::new();
+    task.register_node(NodeStructuredOutput::new(providers))?;
+    let id = task.instantiate(&"structured_output".into())?;
+    task.set_instance_memory_value(
+        id,
+        NodeStructuredOutput::MEMORY_SCHEMA,
+        Value::new(schema()),
+    )?;
+    task.set_instance_memory_value(
+        id,
+        NodeStructuredOutput::MEMORY_MAX_RETRIES,
+        Value::new(json!(max_retries)),
+    )?;
+
+    Ok((task, id))
+}
+
+/// Run the node alone with a context prompt.
+async fn run_node(task: &Task, id: NodeInstanceId) -> eyre::Result<InstanceArgs> {
+    let ctx = RunContext::new(task, RunBudget::unlimited());
+    let context = Value::new("Describe the item".to_string());
+    let args = InstanceRefArgs::from([(NodeStructuredOutput::INPUT_ARG_CONTEXT, &context)]);
+
+    let instance = task.get_instance(id)?;
+    task.get_node(&instance.node_id)?
+        .run(instance, &ctx, &args)
+        .await
+}
+
+#[tokio::test]
+async fn valid_reply_fills_typed_ports() -> eyre::Result<()> {
+    let server = MockServer::start_with_responses(
+        "/api/chat",
+        vec![reply(r#"{"name": "apple", "count": 3, "score": 0.5}"#)],
+    )
+    .await;
+    let (task, id) = task(&server, 0)?;
+
+    let output = run_node(&task, id).await?;
+    assert_eq!(output["name"].downcast::<String>()?, "apple");
+    assert_eq!(*output["count"].downcast::<i64>()?, 3);
+    assert_eq!(*output["score"].downcast::<f64>()?, 0.5);
+    assert_eq!(
+        output["json"].downcast::<serde_json::Value>()?["name"],
+        "apple"
+    );
+
+    let requests = server.take_requests();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].body["format"], schema());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn number_and_integer_follow_schema_type() -> eyre::Result<()> {
+    // `42` for a number and `3.0` for an integer
+    let server = MockServer::start_with_responses(
+        "/api/chat",
+        vec![reply(r#"{"name": "pear", "count": 3.0, "score": 42}"#)],
+    )
+    .await;
+    let (task, id) = task(&server, 0)?;
+
+    let ports = task.get_instance_ports(id)?;
+    assert_eq!(
+        ports.output_args["count"].value_type,
+        ValueType::new::<i64>()
+    );
+    assert_eq!(
+        ports.output_args["score"].value_type,
+        ValueType::new::<f64>()
+    );
+
+    let output = run_node(&task, id).await?;
+    assert_eq!(*output["count"].downcast::<i64>()?, 3);
+    assert_eq!(*output["score"].downcast::<f64>()?, 42.0);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn invalid_reply_is_sent_back_and_corrected() -> eyre::Result<()> {
+    let server = MockServer::start_with_responses(
+        "/api/chat",
+        vec![
+            reply(r#"{"name": "apple"}"#),
+            reply(r#"{"name": "apple", "count": 1, "score": 1.5}"#),
+        ],
+    )
+    .await;
+    let (task, id) = task(&server, 1)?;
+
+    let output = run_node(&task, id).await?;
+    assert_eq!(*output["count"].downcast::<i64>()?, 1);
+
+    let requests = server.take_requests();
+    assert_eq!(requests.len(), 2);
+    let messages = requests[1].body["messages"].as_array().unwrap();
+    assert_eq!(messages[messages.len() - 2]["role"], "assistant");
+    let correction = messages.last().unwrap()["content"].as_str().unwrap();
+    assert!(
+        correction.contains("\"count\" is a required property"),
+        "{correction}"
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn invalid_replies_exhaust_retries() -> eyre::Result<()> {
+    let server = MockServer::start_with_responses("/api/chat", vec![reply("not json")]).await;
+    let (task, id) = task(&server, 1)?;
+
+    let Err(err) = run_node(&task, id).await else {
+        panic!("invalid replies are rejected");
+    };
+    assert!(format!("{err:#}").contains("after 2 attempts"), "{err:#}");
+    assert_eq!(server.take_requests().len(), 2);
+
+    Ok(())
+}
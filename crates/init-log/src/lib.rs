@@ -1,47 +1,419 @@
-use std::sync::Arc;
+#[cfg(feature = "otlp")]
+mod otlp;
+
+#[cfg(feature = "otlp")]
+pub use otlp::*;
+
+use color_eyre::eyre::{self, WrapErr};
+use rolling_file::{BasicRollingFileAppender, RollingConditionBasic, RollingFrequency};
+use std::path::PathBuf;
+use std::str::FromStr;
+use tracing_appender::non_blocking::WorkerGuard;
 use tracing_log::LogTracer;
 use tracing_subscriber::layer::SubscriberExt;
-use tracing_subscriber::{EnvFilter, Registry};
+use tracing_subscriber::{EnvFilter, Layer, Registry};
+
+pub(crate) type BoxedLayer = Box<dyn Layer<Registry> + Send + Sync>;
+
+/// Format of log lines.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LogFormat {
+    /// Multi-line human readable output.
+    #[default]
+    Pretty,
+    /// Single line per event.
+    Compact,
+    /// Single JSON object per line with the fields and the current spans.
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = eyre::Report;
 
-#[track_caller]
-#[inline(always)]
-pub fn init_logging(enable_colors: bool) {
-    if enable_colors {
-        color_eyre::install().expect("failed to install color_eyre");
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "pretty" => Ok(Self::Pretty),
+            "compact" => Ok(Self::Compact),
+            "json" => Ok(Self::Json),
+            _ => eyre::bail!("unknown log format {value:?}, expected pretty, compact or json"),
+        }
     }
+}
 
-    LogTracer::builder()
-        .ignore_crate("rustls")
-        .with_max_level(log::LevelFilter::Debug)
-        .init()
-        .expect("failed to initialize LogTracer");
+/// When the log file is rotated, independently of [`LogFileConfig::max_size`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LogRotation {
+    Never,
+    Hourly,
+    #[default]
+    Daily,
+}
+
+impl FromStr for LogRotation {
+    type Err = eyre::Report;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "never" => Ok(Self::Never),
+            "hourly" => Ok(Self::Hourly),
+            "daily" => Ok(Self::Daily),
+            _ => eyre::bail!("unknown log rotation {value:?}, expected never, hourly or daily"),
+        }
+    }
+}
+
+/// Log file, rotated files are kept next to it as `<path>.1`, `<path>.2` and so on.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LogFileConfig {
+    pub path: PathBuf,
+    pub format: LogFormat,
+    pub rotation: LogRotation,
+    /// Size in bytes after which the file is rotated.
+    pub max_size: Option<u64>,
+    /// Rotated files kept, older ones are removed.
+    pub max_files: usize,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LogConfig {
+    /// Format of the stderr output.
+    pub format: LogFormat,
+    pub file: Option<LogFileConfig>,
+    /// Export of spans, each [`tracing`] span becomes an OpenTelemetry span.
+    #[cfg(feature = "otlp")]
+    pub otlp: Option<OtlpConfig>,
+}
+
+impl LogConfig {
+    /// Read the config from environment variables:
+    ///
+    /// - `LOG_FORMAT` - `pretty` (default), `compact` or `json`
+    /// - `LOG_FILE` - path of the log file, logs are written only to stderr if not set
+    /// - `LOG_FILE_FORMAT` - format of the log file, `json` by default
+    /// - `LOG_FILE_ROTATION` - `never`, `hourly` or `daily` (default)
+    /// - `LOG_FILE_MAX_SIZE` - size in bytes after which the file is rotated
+    /// - `LOG_FILE_MAX_FILES` - rotated files kept, `7` by default
+    ///
+    /// The level is set by `LOG_LEVEL` in the [`EnvFilter`] syntax. With the `otlp` feature spans
+    /// are exported as configured by [`OtlpConfig::from_env`].
+    pub fn from_env() -> eyre::Result<Self> {
+        let format = env_parsed("LOG_FORMAT")?.unwrap_or_default();
+
+        let file = match env("LOG_FILE") {
+            Some(path) => Some(LogFileConfig {
+                path: path.into(),
+                format: env_parsed("LOG_FILE_FORMAT")?.unwrap_or(LogFormat::Json),
+                rotation: env_parsed("LOG_FILE_ROTATION")?.unwrap_or_default(),
+                max_size: env_parsed("LOG_FILE_MAX_SIZE")?,
+                max_files: env_parsed("LOG_FILE_MAX_FILES")?.unwrap_or(7),
+            }),
+            None => None,
+        };
+
+        Ok(Self {
+            format,
+            file,
+            #[cfg(feature = "otlp")]
+            otlp: OtlpConfig::from_env()?,
+        })
+    }
+}
+
+pub(crate) fn env(name: &str) -> Option<String> {
+    std::env::var(name).ok().filter(|value| !value.is_empty())
+}
+
+pub(crate) fn env_parsed<T>(name: &str) -> eyre::Result<Option<T>>
+where
+    T: FromStr,
+    T::Err: std::fmt::Display,
+{
+    env(name)
+        .map(|value| {
+            value
+                .parse()
+                .map_err(|err| eyre::eyre!("invalid {name}: {err}"))
+        })
+        .transpose()
+}
+
+/// Flushes buffered logs of the log file and exported spans when dropped, keep it alive until
+/// the program exits. For [`LogInit::init_scoped`] the subscriber is also removed.
+#[must_use = "buffered logs are lost when the guard is dropped"]
+pub struct LogGuard {
+    _scope: Option<tracing::dispatcher::DefaultGuard>,
+    _file: Option<WorkerGuard>,
+    #[cfg(feature = "otlp")]
+    tracer_provider: Option<opentelemetry_sdk::trace::SdkTracerProvider>,
+}
+
+#[cfg(feature = "otlp")]
+impl Drop for LogGuard {
+    fn drop(&mut self) {
+        if let Some(provider) = self.tracer_provider.take() {
+            if let Err(err) = provider.shutdown() {
+                eprintln!("Failed to export spans: {err}");
+            }
+        }
+    }
+}
+
+/// Builder of the logging setup, every component can be switched off, e.g. when embedded in a
+/// host which already installed its own.
+///
+/// ```no_run
+/// let _guard = init_log::LogInit::new()
+///     .with_color_eyre(false)
+///     .with_panic_hook(false)
+///     .init()?;
+/// # Ok::<(), color_eyre::Report>(())
+/// ```
+#[derive(Clone, Debug)]
+pub struct LogInit {
+    config: LogConfig,
+    colors: bool,
+    color_eyre: bool,
+    log_tracer: bool,
+    panic_hook: bool,
+    error_layer: bool,
+    test_writer: bool,
+}
+
+impl Default for LogInit {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LogInit {
+    /// All components enabled, logs written to stderr in the [`LogFormat::Pretty`] format.
+    pub fn new() -> Self {
+        Self {
+            config: LogConfig::default(),
+            colors: true,
+            color_eyre: true,
+            log_tracer: true,
+            panic_hook: true,
+            error_layer: true,
+            test_writer: false,
+        }
+    }
+
+    /// All components enabled, configured by environment variables, see
+    /// [`LogConfig::from_env`].
+    pub fn from_env() -> eyre::Result<Self> {
+        Ok(Self::new().with_config(LogConfig::from_env()?))
+    }
+
+    /// Logs written to the output captured by the test harness, shown only for failed tests.
+    /// Global components are disabled, so it can be initialized by every test with
+    /// [`Self::init_scoped`].
+    pub fn for_tests() -> Self {
+        Self {
+            config: LogConfig {
+                format: LogFormat::Compact,
+                ..Default::default()
+            },
+            colors: false,
+            color_eyre: false,
+            log_tracer: false,
+            panic_hook: false,
+            error_layer: true,
+            test_writer: true,
+        }
+    }
+
+    pub fn with_config(mut self, config: LogConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// ANSI colors in the stderr output, disabled for the log file and in tests.
+    pub fn with_colors(mut self, colors: bool) -> Self {
+        self.colors = colors;
+        self
+    }
+
+    /// Install [`color_eyre`] reports with span traces.
+    pub fn with_color_eyre(mut self, color_eyre: bool) -> Self {
+        self.color_eyre = color_eyre;
+        self
+    }
+
+    /// Forward records of the `log` crate to `tracing`.
+    pub fn with_log_tracer(mut self, log_tracer: bool) -> Self {
+        self.log_tracer = log_tracer;
+        self
+    }
 
-    let env_filter =
-        EnvFilter::try_from_env("LOG_LEVEL").unwrap_or_else(|_| EnvFilter::new("info"));
+    /// Log panics as errors instead of printing them to stderr.
+    pub fn with_panic_hook(mut self, panic_hook: bool) -> Self {
+        self.panic_hook = panic_hook;
+        self
+    }
 
-    let subscriber = Registry::default()
-        .with(tracing_subscriber::fmt::layer().pretty())
-        .with(tracing_error::ErrorLayer::default())
-        .with(env_filter);
-    let subscriber = Arc::new(subscriber);
+    /// Capture span traces for [`tracing_error::SpanTrace`].
+    pub fn with_error_layer(mut self, error_layer: bool) -> Self {
+        self.error_layer = error_layer;
+        self
+    }
 
-    tracing::subscriber::set_global_default(subscriber).expect("failed to set tracing subscriber");
+    /// Set the global subscriber, fails if it is already set, e.g. by the host.
+    ///
+    /// The fallible global components are installed first, so the subscriber is left unset
+    /// when any of them fails.
+    pub fn init(self) -> eyre::Result<LogGuard> {
+        let (dispatch, guard) = self.build()?;
+        self.install_globals()?;
+        tracing::dispatcher::set_global_default(dispatch)
+            .wrap_err("failed to set tracing subscriber")?;
+        self.install_panic_hook();
 
-    std::panic::set_hook(Box::new(|err| {
-        let payload = err.payload();
+        Ok(guard)
+    }
 
-        let location = err
-            .location()
-            .copied()
-            .map(|loc| format!("{}", loc))
-            .unwrap_or_default();
+    /// Set the subscriber for the current thread until the guard is dropped, events of other
+    /// threads are not logged.
+    pub fn init_scoped(self) -> eyre::Result<LogGuard> {
+        let (dispatch, mut guard) = self.build()?;
+        self.install_globals()?;
+        guard._scope = Some(tracing::dispatcher::set_default(&dispatch));
+        self.install_panic_hook();
 
-        if let Some(payload) = payload.downcast_ref::<String>() {
-            tracing::error!(location, "panic:\n{payload}");
-        } else if let Some(&payload) = payload.downcast_ref::<&'static str>() {
-            tracing::error!(location, "panic:\n{payload}");
+        Ok(guard)
+    }
+
+    fn build(&self) -> eyre::Result<(tracing::Dispatch, LogGuard)> {
+        let env_filter =
+            EnvFilter::try_from_env("LOG_LEVEL").unwrap_or_else(|_| EnvFilter::new("info"));
+
+        let mut layers = Vec::new();
+        if self.test_writer {
+            layers.push(fmt_layer(
+                self.config.format,
+                false,
+                tracing_subscriber::fmt::TestWriter::new(),
+            ));
         } else {
-            tracing::error!(location, "panic with unknown payload");
+            layers.push(fmt_layer(self.config.format, self.colors, std::io::stderr));
+        }
+
+        let mut file_guard = None;
+        if let Some(file) = &self.config.file {
+            let (writer, guard) = tracing_appender::non_blocking(open_log_file(file)?);
+            layers.push(fmt_layer(file.format, false, writer));
+            file_guard = Some(guard);
+        }
+        #[cfg(feature = "otlp")]
+        let mut tracer_provider = None;
+        #[cfg(feature = "otlp")]
+        if let Some(otlp) = &self.config.otlp {
+            let (layer, provider) = otlp_layer(otlp)?;
+            layers.push(layer);
+            tracer_provider = Some(provider);
+        }
+
+        let subscriber = Registry::default()
+            .with(layers)
+            .with(self.error_layer.then(tracing_error::ErrorLayer::default))
+            .with(env_filter);
+
+        let guard = LogGuard {
+            _scope: None,
+            _file: file_guard,
+            #[cfg(feature = "otlp")]
+            tracer_provider,
+        };
+
+        Ok((tracing::Dispatch::new(subscriber), guard))
+    }
+
+    /// Fallible process wide components, they look up the subscriber on every event so they
+    /// can be installed before it.
+    fn install_globals(&self) -> eyre::Result<()> {
+        if self.log_tracer {
+            LogTracer::builder()
+                .ignore_crate("rustls")
+                .with_max_level(log::LevelFilter::Debug)
+                .init()
+                .wrap_err("failed to initialize LogTracer")?;
+        }
+
+        if self.color_eyre {
+            color_eyre::install().wrap_err("failed to install color_eyre")?;
+        }
+
+        Ok(())
+    }
+
+    /// Log panics to the subscriber, installed last as it can't fail.
+    fn install_panic_hook(&self) {
+        if self.panic_hook {
+            std::panic::set_hook(Box::new(|err| {
+                let payload = err.payload();
+
+                let location = err
+                    .location()
+                    .copied()
+                    .map(|loc| format!("{}", loc))
+                    .unwrap_or_default();
+
+                if let Some(payload) = payload.downcast_ref::<String>() {
+                    tracing::error!(location, "panic:\n{payload}");
+                } else if let Some(&payload) = payload.downcast_ref::<&'static str>() {
+                    tracing::error!(location, "panic:\n{payload}");
+                } else {
+                    tracing::error!(location, "panic with unknown payload");
+                }
+            }));
         }
-    }));
+    }
+}
+
+/// Initialize logging configured by environment variables with all components, see
+/// [`LogInit`] to customize it.
+pub fn init_logging(enable_colors: bool) -> eyre::Result<LogGuard> {
+    LogInit::from_env()?
+        .with_colors(enable_colors)
+        .with_color_eyre(enable_colors)
+        .init()
+}
+
+fn fmt_layer<W>(format: LogFormat, ansi: bool, writer: W) -> BoxedLayer
+where
+    W: for<'writer> tracing_subscriber::fmt::MakeWriter<'writer> + Send + Sync + 'static,
+{
+    let layer = tracing_subscriber::fmt::layer()
+        .with_ansi(ansi)
+        .with_writer(writer);
+
+    match format {
+        LogFormat::Pretty => layer.pretty().boxed(),
+        LogFormat::Compact => layer.compact().boxed(),
+        LogFormat::Json => layer.json().with_current_span(true).boxed(),
+    }
+}
+
+fn open_log_file(config: &LogFileConfig) -> eyre::Result<BasicRollingFileAppender> {
+    if let Some(dir) = config
+        .path
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+    {
+        std::fs::create_dir_all(dir)
+            .wrap_err_with(|| format!("failed to create {}", dir.display()))?;
+    }
+
+    let mut condition = RollingConditionBasic::new();
+    condition = match config.rotation {
+        LogRotation::Never => condition,
+        LogRotation::Hourly => condition.frequency(RollingFrequency::EveryHour),
+        LogRotation::Daily => condition.frequency(RollingFrequency::EveryDay),
+    };
+    if let Some(max_size) = config.max_size {
+        condition = condition.max_size(max_size);
+    }
+
+    BasicRollingFileAppender::new(&config.path, condition, config.max_files)
+        .wrap_err_with(|| format!("failed to open {}", config.path.display()))
 }
@@ -0,0 +1,86 @@
+use crate::*;
+use color_eyre::eyre::{self, WrapErr};
+use opentelemetry::trace::TracerProvider;
+use opentelemetry_otlp::{Protocol, WithExportConfig};
+use opentelemetry_sdk::trace::SdkTracerProvider;
+use opentelemetry_sdk::Resource;
+use std::str::FromStr;
+
+/// Encoding of spans sent to the collector.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OtlpProtocol {
+    #[default]
+    HttpProtobuf,
+    HttpJson,
+}
+
+impl FromStr for OtlpProtocol {
+    type Err = eyre::Report;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "http/protobuf" => Ok(Self::HttpProtobuf),
+            "http/json" => Ok(Self::HttpJson),
+            _ => eyre::bail!(
+                "unsupported OTLP protocol {value:?}, expected http/protobuf or http/json"
+            ),
+        }
+    }
+}
+
+/// Export of spans to an OpenTelemetry collector over OTLP/HTTP.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OtlpConfig {
+    /// Base url of the collector, spans are sent to `<endpoint>/v1/traces`.
+    pub endpoint: String,
+    pub protocol: OtlpProtocol,
+    pub service_name: String,
+}
+
+impl OtlpConfig {
+    /// Read the config from the standard variables `OTEL_EXPORTER_OTLP_ENDPOINT`,
+    /// `OTEL_EXPORTER_OTLP_PROTOCOL` and `OTEL_SERVICE_NAME`, `None` if the endpoint is not set.
+    pub fn from_env() -> eyre::Result<Option<Self>> {
+        let Some(endpoint) = env("OTEL_EXPORTER_OTLP_ENDPOINT") else {
+            return Ok(None);
+        };
+
+        Ok(Some(Self {
+            endpoint,
+            protocol: env_parsed("OTEL_EXPORTER_OTLP_PROTOCOL")?.unwrap_or_default(),
+            service_name: env("OTEL_SERVICE_NAME").unwrap_or_else(|| "agent".to_string()),
+        }))
+    }
+}
+
+/// Layer turning spans into OpenTelemetry spans, the provider must be shut down to flush them.
+pub(crate) fn otlp_layer(config: &OtlpConfig) -> eyre::Result<(BoxedLayer, SdkTracerProvider)> {
+    let protocol = match config.protocol {
+        OtlpProtocol::HttpProtobuf => Protocol::HttpBinary,
+        OtlpProtocol::HttpJson => Protocol::HttpJson,
+    };
+    let exporter = opentelemetry_otlp::SpanExporter::builder()
+        .with_http()
+        .with_protocol(protocol)
+        .with_endpoint(format!(
+            "{}/v1/traces",
+            config.endpoint.trim_end_matches('/')
+        ))
+        .build()
+        .wrap_err("failed to build OTLP exporter")?;
+
+    let provider = SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(
+            Resource::builder()
+                .with_service_name(config.service_name.clone())
+                .build(),
+        )
+        .build();
+
+    let layer = tracing_opentelemetry::layer()
+        .with_tracer(provider.tracer(env!("CARGO_PKG_NAME")))
+        .boxed();
+
+    Ok((layer, provider))
+}
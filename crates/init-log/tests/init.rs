@@ -0,0 +1,161 @@
+use init_log::*;
+
+#[test]
+fn second_global_init_fails() {
+    let _guard = LogInit::for_tests().init().unwrap();
+    tracing::info!("logged by the global subscriber");
+
+    let err = LogInit::for_tests().init().err().unwrap();
+    assert!(err.to_string().contains("failed to set tracing subscriber"));
+}
+
+#[test]
+fn scoped_init_per_test() {
+    let threads = (0..4)
+        .map(|index| {
+            std::thread::spawn(move || {
+                let _guard = LogInit::for_tests().init_scoped().unwrap();
+                tracing::info!(index, "logged by the thread subscriber");
+
+                // nested scopes replace the outer one until dropped
+                let _nested = LogInit::for_tests().init_scoped().unwrap();
+            })
+        })
+        .collect::<Vec<_>>();
+
+    for thread in threads {
+        thread.join().unwrap();
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn invalid_log_file_fails() {
+    let config = LogConfig {
+        file: Some(LogFileConfig {
+            path: "/dev/null/agent.log".into(),
+            format: LogFormat::Json,
+            rotation: LogRotation::Daily,
+            max_size: None,
+            max_files: 1,
+        }),
+        ..Default::default()
+    };
+
+    let result = LogInit::for_tests().with_config(config).init_scoped();
+    assert!(result.is_err());
+}
+
+#[test]
+fn format_and_rotation_are_parsed() {
+    assert_eq!("pretty".parse::<LogFormat>().unwrap(), LogFormat::Pretty);
+    assert_eq!("Compact".parse::<LogFormat>().unwrap(), LogFormat::Compact);
+    assert_eq!("JSON".parse::<LogFormat>().unwrap(), LogFormat::Json);
+    let err = "xml".parse::<LogFormat>().unwrap_err();
+    assert!(err.to_string().contains("unknown log format"), "{err}");
+
+    assert_eq!("never".parse::<LogRotation>().unwrap(), LogRotation::Never);
+    assert_eq!(
+        "Hourly".parse::<LogRotation>().unwrap(),
+        LogRotation::Hourly
+    );
+    assert_eq!("DAILY".parse::<LogRotation>().unwrap(), LogRotation::Daily);
+    let err = "weekly".parse::<LogRotation>().unwrap_err();
+    assert!(err.to_string().contains("unknown log rotation"), "{err}");
+}
+
+/// The only test changing the environment, so the variables are not read concurrently.
+#[test]
+fn config_from_env() {
+    const VARS: &[&str] = &[
+        "LOG_FORMAT",
+        "LOG_FILE",
+        "LOG_FILE_FORMAT",
+        "LOG_FILE_ROTATION",
+        "LOG_FILE_MAX_SIZE",
+        "LOG_FILE_MAX_FILES",
+    ];
+    for name in VARS {
+        std::env::remove_var(name);
+    }
+
+    // defaults, empty values are ignored
+    std::env::set_var("LOG_FORMAT", "");
+    let config = LogConfig::from_env().unwrap();
+    assert_eq!(config.format, LogFormat::Pretty);
+    assert_eq!(config.file, None);
+
+    std::env::set_var("LOG_FORMAT", "compact");
+    std::env::set_var("LOG_FILE", "logs/agent.log");
+    let config = LogConfig::from_env().unwrap();
+    assert_eq!(config.format, LogFormat::Compact);
+    assert_eq!(
+        config.file,
+        Some(LogFileConfig {
+            path: "logs/agent.log".into(),
+            format: LogFormat::Json,
+            rotation: LogRotation::Daily,
+            max_size: None,
+            max_files: 7,
+        })
+    );
+
+    std::env::set_var("LOG_FILE_FORMAT", "pretty");
+    std::env::set_var("LOG_FILE_ROTATION", "hourly");
+    std::env::set_var("LOG_FILE_MAX_SIZE", "1048576");
+    std::env::set_var("LOG_FILE_MAX_FILES", "3");
+    let config = LogConfig::from_env().unwrap();
+    assert_eq!(
+        config.file,
+        Some(LogFileConfig {
+            path: "logs/agent.log".into(),
+            format: LogFormat::Pretty,
+            rotation: LogRotation::Hourly,
+            max_size: Some(1048576),
+            max_files: 3,
+        })
+    );
+
+    std::env::set_var("LOG_FILE_MAX_SIZE", "1MB");
+    let err = LogConfig::from_env().unwrap_err();
+    assert!(
+        err.to_string().contains("invalid LOG_FILE_MAX_SIZE"),
+        "{err}"
+    );
+
+    for name in VARS {
+        std::env::remove_var(name);
+    }
+}
+
+#[test]
+fn log_file_is_rotated_by_size() {
+    let dir = std::env::temp_dir().join(format!("init-log-rotation-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let path = dir.join("agent.log");
+
+    let config = LogConfig {
+        file: Some(LogFileConfig {
+            path: path.clone(),
+            format: LogFormat::Json,
+            rotation: LogRotation::Never,
+            max_size: Some(256),
+            max_files: 2,
+        }),
+        ..Default::default()
+    };
+
+    let guard = LogInit::for_tests()
+        .with_config(config)
+        .init_scoped()
+        .unwrap();
+    for index in 0..20 {
+        tracing::info!(index, "line long enough to fill the log file quickly");
+    }
+    // flushes the file
+    drop(guard);
+
+    assert!(path.exists());
+    assert!(dir.join("agent.log.1").exists());
+    let _ = std::fs::remove_dir_all(&dir);
+}
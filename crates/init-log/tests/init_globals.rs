@@ -0,0 +1,19 @@
+use init_log::*;
+
+/// Runs in its own binary, as both the `log` logger and the subscriber are process wide.
+#[test]
+fn failed_global_component_leaves_subscriber_unset() {
+    // the host already installed a `log` logger
+    tracing_log::LogTracer::init().unwrap();
+
+    let err = LogInit::for_tests()
+        .with_log_tracer(true)
+        .init()
+        .err()
+        .unwrap();
+    assert!(err.to_string().contains("failed to initialize LogTracer"));
+    assert!(!tracing::dispatcher::has_been_set());
+
+    let _guard = LogInit::for_tests().init().unwrap();
+    assert!(tracing::dispatcher::has_been_set());
+}
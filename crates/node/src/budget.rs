@@ -0,0 +1,100 @@
+use crate::*;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Limits enforced by [`Task::run`], exceeding any of them fails the run with [`BudgetExceeded`].
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct RunBudget {
+    /// Total amount of prompt and completion tokens.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u64>,
+    /// Total cost in USD.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_cost: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_node_executions: Option<usize>,
+    /// Wall time of the run, time a debug run spends paused is not counted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_duration: Option<Duration>,
+}
+
+impl RunBudget {
+    pub fn unlimited() -> Self {
+        Self::default()
+    }
+
+    pub fn with_max_tokens(mut self, max_tokens: u64) -> Self {
+        self.max_tokens = Some(max_tokens);
+        self
+    }
+
+    pub fn with_max_cost(mut self, max_cost: f64) -> Self {
+        self.max_cost = Some(max_cost);
+        self
+    }
+
+    pub fn with_max_node_executions(mut self, max_node_executions: usize) -> Self {
+        self.max_node_executions = Some(max_node_executions);
+        self
+    }
+
+    pub fn with_max_duration(mut self, max_duration: Duration) -> Self {
+        self.max_duration = Some(max_duration);
+        self
+    }
+
+    /// Check total usage of the run against token and cost limits.
+    pub fn check_usage(&self, usage: &TokenUsage) -> Option<BudgetLimit> {
+        if let Some(limit) = self.max_tokens {
+            if usage.total_tokens() > limit {
+                return Some(BudgetLimit::Tokens {
+                    limit,
+                    used: usage.total_tokens(),
+                });
+            }
+        }
+
+        if let Some(limit) = self.max_cost {
+            if usage.cost > limit {
+                return Some(BudgetLimit::Cost {
+                    limit,
+                    used: usage.cost,
+                });
+            }
+        }
+
+        None
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, derive_more::Display)]
+pub enum BudgetLimit {
+    #[display("token limit {limit} exceeded ({used} used)")]
+    Tokens { limit: u64, used: u64 },
+    #[display("cost limit ${limit} exceeded (${used:.6} used)")]
+    Cost { limit: f64, used: f64 },
+    #[display("node execution limit {limit} exceeded")]
+    NodeExecutions { limit: usize },
+    #[display("duration limit {limit:?} exceeded")]
+    Duration { limit: Duration },
+}
+
+/// Error of a run stopped by [`RunBudget`], can be extracted from the run error with
+/// [`eyre::Report::downcast_ref`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct BudgetExceeded {
+    pub limit: BudgetLimit,
+    /// Instance which crossed the limit, for duration limit it is the oldest running instance.
+    pub instance_id: Option<NodeInstanceId>,
+}
+
+impl std::fmt::Display for BudgetExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.instance_id {
+            Some(instance_id) => write!(f, "Run budget exceeded by {instance_id}: {}", self.limit),
+            None => write!(f, "Run budget exceeded: {}", self.limit),
+        }
+    }
+}
+
+impl std::error::Error for BudgetExceeded {}
@@ -0,0 +1,335 @@
+use serde::{Deserialize, Serialize};
+
+/// Rough amount of characters per token used for local token estimates.
+pub(crate) const CHARS_PER_TOKEN: usize = 4;
+
+/// Extra tokens spent by providers on every message (role, separators).
+const TOKENS_PER_MESSAGE: usize = 4;
+
+/// Estimate amount of tokens in the text without a real tokenizer.
+pub fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(CHARS_PER_TOKEN)
+}
+
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Serialize,
+    Deserialize,
+    derive_more::Display,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum ChatRole {
+    #[display("system")]
+    System,
+    #[display("user")]
+    User,
+    #[display("assistant")]
+    Assistant,
+    #[display("tool")]
+    Tool,
+}
+
+impl std::str::FromStr for ChatRole {
+    type Err = eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "system" => Ok(Self::System),
+            "user" => Ok(Self::User),
+            "assistant" => Ok(Self::Assistant),
+            "tool" => Ok(Self::Tool),
+            _ => Err(eyre::eyre!("Unknown chat role: {s:?}")),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ContentPart {
+    Text { text: String },
+    ImageUrl { url: String },
+}
+
+impl ContentPart {
+    pub fn text(text: impl Into<String>) -> Self {
+        Self::Text { text: text.into() }
+    }
+
+    pub fn image_url(url: impl Into<String>) -> Self {
+        Self::ImageUrl { url: url.into() }
+    }
+}
+
+/// Function call requested by the assistant.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ToolCall {
+    pub id: String,
+    pub name: String,
+    pub arguments: serde_json::Value,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ChatMessage {
+    pub role: ChatRole,
+    pub content: Vec<ContentPart>,
+    /// Optional name of the participant, used to distinguish participants with the same role.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tool_calls: Vec<ToolCall>,
+    /// Id of the tool call this message is a result of, only set for [`ChatRole::Tool`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_call_id: Option<String>,
+}
+
+impl ChatMessage {
+    pub fn new(role: ChatRole, text: impl Into<String>) -> Self {
+        Self {
+            role,
+            content: vec![ContentPart::text(text)],
+            name: None,
+            tool_calls: Vec::new(),
+            tool_call_id: None,
+        }
+    }
+
+    pub fn system(text: impl Into<String>) -> Self {
+        Self::new(ChatRole::System, text)
+    }
+
+    pub fn user(text: impl Into<String>) -> Self {
+        Self::new(ChatRole::User, text)
+    }
+
+    pub fn assistant(text: impl Into<String>) -> Self {
+        Self::new(ChatRole::Assistant, text)
+    }
+
+    pub fn tool(tool_call_id: impl Into<String>, text: impl Into<String>) -> Self {
+        Self {
+            tool_call_id: Some(tool_call_id.into()),
+            ..Self::new(ChatRole::Tool, text)
+        }
+    }
+
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    pub fn with_part(mut self, part: ContentPart) -> Self {
+        self.content.push(part);
+        self
+    }
+
+    pub fn with_tool_call(mut self, tool_call: ToolCall) -> Self {
+        self.tool_calls.push(tool_call);
+        self
+    }
+
+    /// Concatenated text of all text parts of the message.
+    pub fn text(&self) -> String {
+        self.content
+            .iter()
+            .filter_map(|part| match part {
+                ContentPart::Text { text } => Some(text.as_str()),
+                ContentPart::ImageUrl { .. } => None,
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    pub fn estimate_tokens(&self) -> usize {
+        let tool_calls = self
+            .tool_calls
+            .iter()
+            .map(|call| estimate_tokens(&call.name) + estimate_tokens(&call.arguments.to_string()))
+            .sum::<usize>();
+
+        TOKENS_PER_MESSAGE + estimate_tokens(&self.text()) + tool_calls
+    }
+}
+
+/// Ordered list of chat messages passed between LLM related nodes.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Conversation {
+    pub messages: Vec<ChatMessage>,
+}
+
+impl Conversation {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_message(mut self, message: ChatMessage) -> Self {
+        self.messages.push(message);
+        self
+    }
+
+    pub fn push(&mut self, message: ChatMessage) {
+        self.messages.push(message);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+
+    pub fn estimate_tokens(&self) -> usize {
+        self.messages.iter().map(ChatMessage::estimate_tokens).sum()
+    }
+
+    /// Drop the oldest messages until the conversation fits into `max_tokens`.
+    ///
+    /// System messages are always kept. Tool results left without the assistant message that
+    /// requested them are dropped as well, since providers reject such conversations.
+    pub fn trim_to_budget(&self, max_tokens: usize) -> Self {
+        let is_system = |message: &ChatMessage| message.role == ChatRole::System;
+
+        let system_tokens = self
+            .messages
+            .iter()
+            .filter(|message| is_system(message))
+            .map(ChatMessage::estimate_tokens)
+            .sum::<usize>();
+        let mut budget = max_tokens.saturating_sub(system_tokens);
+
+        // index of the oldest non-system message that still fits
+        let mut first_kept = self.messages.len();
+        for (index, message) in self.messages.iter().enumerate().rev() {
+            if is_system(message) {
+                continue;
+            }
+
+            let tokens = message.estimate_tokens();
+            if tokens > budget {
+                break;
+            }
+
+            budget -= tokens;
+            first_kept = index;
+        }
+
+        while self
+            .messages
+            .get(first_kept)
+            .is_some_and(|message| message.role == ChatRole::Tool)
+        {
+            first_kept += 1;
+        }
+
+        Self {
+            messages: self
+                .messages
+                .iter()
+                .enumerate()
+                .filter(|(index, message)| is_system(message) || *index >= first_kept)
+                .map(|(_, message)| message.clone())
+                .collect(),
+        }
+    }
+
+    /// Render conversation as plain text, one `role: text` block per message.
+    pub fn render(&self) -> String {
+        self.messages
+            .iter()
+            .map(|message| match &message.name {
+                Some(name) => format!("{} ({}): {}", message.role, name, message.text()),
+                None => format!("{}: {}", message.role, message.text()),
+            })
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every message of 8 characters costs 6 tokens.
+    fn conversation() -> Conversation {
+        Conversation::new()
+            .with_message(ChatMessage::system("system01"))
+            .with_message(ChatMessage::user("user-001"))
+            .with_message(ChatMessage::assistant("asst-001"))
+            .with_message(ChatMessage::user("user-002"))
+    }
+
+    fn texts(conversation: &Conversation) -> Vec<String> {
+        conversation
+            .messages
+            .iter()
+            .map(ChatMessage::text)
+            .collect()
+    }
+
+    #[test]
+    fn trim_keeps_system_and_drops_oldest() {
+        let conversation = conversation();
+        assert_eq!(conversation.estimate_tokens(), 24);
+
+        assert_eq!(conversation.trim_to_budget(24), conversation);
+        assert_eq!(
+            texts(&conversation.trim_to_budget(18)),
+            ["system01", "asst-001", "user-002"]
+        );
+        assert_eq!(
+            texts(&conversation.trim_to_budget(17)),
+            ["system01", "user-002"]
+        );
+    }
+
+    #[test]
+    fn trim_budget_smaller_than_message() {
+        let conversation = conversation();
+
+        // the system message is kept even if it alone exceeds the budget
+        assert_eq!(texts(&conversation.trim_to_budget(11)), ["system01"]);
+        assert_eq!(texts(&conversation.trim_to_budget(0)), ["system01"]);
+
+        // a message is never cut, the newest one is dropped if it does not fit
+        let conversation = Conversation::new().with_message(ChatMessage::user("a".repeat(100)));
+        assert!(conversation.trim_to_budget(10).is_empty());
+    }
+
+    #[test]
+    fn trim_drops_orphaned_tool_results() {
+        let conversation = Conversation::new()
+            .with_message(ChatMessage::user("user-001"))
+            .with_message(ChatMessage::assistant("asst-001").with_tool_call(ToolCall {
+                id: "call".to_string(),
+                name: "lookup".to_string(),
+                arguments: serde_json::json!({}),
+            }))
+            .with_message(ChatMessage::tool("call", "result01"))
+            .with_message(ChatMessage::user("user-002"));
+
+        // the tool result fits, but the assistant message requesting it does not
+        assert_eq!(texts(&conversation.trim_to_budget(12)), ["user-002"]);
+    }
+
+    #[test]
+    fn render_conversation() {
+        let conversation = Conversation::new()
+            .with_message(ChatMessage::system("Be brief"))
+            .with_message(
+                ChatMessage::user("Look at this")
+                    .with_name("ann")
+                    .with_part(ContentPart::image_url("https://example.com/cat.png"))
+                    .with_part(ContentPart::text("What is it?")),
+            )
+            .with_message(ChatMessage::assistant("A cat"));
+
+        assert_eq!(
+            conversation.render(),
+            "system: Be brief\n\nuser (ann): Look at this\nWhat is it?\n\nassistant: A cat"
+        );
+        assert_eq!(Conversation::new().render(), "");
+    }
+}
@@ -0,0 +1,334 @@
+use crate::*;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// State of a single [`Task::run`], shared with the running nodes.
+pub struct RunContext<'t> {
+    task: &'t Task,
+    run_id: u64,
+    budget: RunBudget,
+    started_at: Instant,
+    instances: Mutex<BTreeMap<NodeInstanceId, InstanceReport>>,
+    running: Mutex<BTreeMap<NodeInstanceId, Instant>>,
+    usage: Mutex<BTreeMap<NodeInstanceId, TokenUsage>>,
+    retries: Mutex<BTreeMap<NodeInstanceId, u32>>,
+    budget_exceeded: Mutex<Option<BudgetExceeded>>,
+    budget_notify: tokio::sync::Notify,
+    debugger: Option<DebugState>,
+}
+
+impl<'t> RunContext<'t> {
+    pub fn new(task: &'t Task, budget: RunBudget) -> Self {
+        Self {
+            task,
+            run_id: task.next_run_id(),
+            budget,
+            started_at: Instant::now(),
+            instances: Mutex::new(BTreeMap::new()),
+            running: Mutex::new(BTreeMap::new()),
+            usage: Mutex::new(BTreeMap::new()),
+            retries: Mutex::new(BTreeMap::new()),
+            budget_exceeded: Mutex::new(None),
+            budget_notify: tokio::sync::Notify::new(),
+            debugger: None,
+        }
+    }
+
+    pub(crate) fn with_debugger(mut self, debugger: DebugState) -> Self {
+        self.debugger = Some(debugger);
+        self
+    }
+
+    /// Debugger of runs started with [`Task::run_debug`].
+    pub(crate) fn debugger(&self) -> Option<&DebugState> {
+        self.debugger.as_ref()
+    }
+
+    pub fn task(&self) -> &'t Task {
+        self.task
+    }
+
+    /// Id of the run in [`RunEvent::run_id`].
+    pub fn run_id(&self) -> u64 {
+        self.run_id
+    }
+
+    pub fn budget(&self) -> &RunBudget {
+        &self.budget
+    }
+
+    /// Time passed since the run started.
+    pub fn elapsed(&self) -> Duration {
+        self.started_at.elapsed()
+    }
+
+    /// Send event to subscribers of the task, dropped if there are none.
+    pub(crate) fn emit(&self, kind: RunEventKind) {
+        let _ = self.task.events().send(RunEvent {
+            run_id: self.run_id,
+            elapsed: self.elapsed(),
+            kind,
+        });
+    }
+
+    /// Publish a part of the output while the instance is still running, e.g. text generated
+    /// so far or lines printed by a command.
+    pub fn stream_chunk(
+        &self,
+        instance_id: NodeInstanceId,
+        arg: impl Into<String>,
+        text: impl Into<String>,
+    ) {
+        self.emit(RunEventKind::StreamChunk {
+            instance_id,
+            arg: arg.into(),
+            text: text.into(),
+        });
+    }
+
+    /// Add tokens spent by the instance, called by nodes using LLMs.
+    ///
+    /// If the run budget is exceeded, the run is stopped and all running instances are
+    /// cancelled.
+    #[tracing::instrument(skip(self))]
+    pub fn record_usage(&self, instance_id: NodeInstanceId, usage: TokenUsage) {
+        *self
+            .usage
+            .lock()
+            .expect("usage lock poisoned")
+            .entry(instance_id)
+            .or_default() += usage;
+
+        if let Some(limit) = self.budget.check_usage(&self.total_usage()) {
+            self.exceed_budget(BudgetExceeded {
+                limit,
+                instance_id: Some(instance_id),
+            });
+        }
+    }
+
+    /// Usage recorded so far for the instance.
+    pub fn instance_usage(&self, instance_id: NodeInstanceId) -> TokenUsage {
+        self.usage
+            .lock()
+            .expect("usage lock poisoned")
+            .get(&instance_id)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Usage recorded so far for the whole run.
+    pub fn total_usage(&self) -> TokenUsage {
+        self.usage
+            .lock()
+            .expect("usage lock poisoned")
+            .values()
+            .copied()
+            .sum()
+    }
+
+    /// Add retried calls of the instance, e.g. LLM calls rejected by rate limits or replies
+    /// not matching the schema.
+    pub fn record_retries(&self, instance_id: NodeInstanceId, retries: u32) {
+        if retries > 0 {
+            *self
+                .retries
+                .lock()
+                .expect("retries lock poisoned")
+                .entry(instance_id)
+                .or_default() += retries;
+        }
+    }
+
+    /// Retries recorded so far for the instance.
+    pub fn instance_retries(&self, instance_id: NodeInstanceId) -> u32 {
+        self.retries
+            .lock()
+            .expect("retries lock poisoned")
+            .get(&instance_id)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Running instances, oldest first.
+    pub fn running_instances(&self) -> Vec<NodeInstanceId> {
+        let running = self.running.lock().expect("running lock poisoned");
+
+        let mut instances = running.iter().collect::<Vec<_>>();
+        instances.sort_by_key(|(_, started_at)| **started_at);
+        instances.into_iter().map(|(id, _)| *id).collect()
+    }
+
+    fn exceed_budget(&self, exceeded: BudgetExceeded) {
+        let mut current = self.budget_exceeded.lock().expect("budget lock poisoned");
+
+        // keep the first instance that crossed the limit
+        if current.is_none() {
+            tracing::warn!("{exceeded}");
+            *current = Some(exceeded);
+            self.budget_notify.notify_one();
+        }
+    }
+
+    /// Token or cost limit crossed so far, if any.
+    pub(crate) fn exceeded_budget(&self) -> Option<BudgetExceeded> {
+        self.budget_exceeded
+            .lock()
+            .expect("budget lock poisoned")
+            .clone()
+    }
+
+    /// Wait until token or cost budget is exceeded.
+    pub(crate) async fn budget_exceeded(&self) -> BudgetExceeded {
+        loop {
+            if let Some(exceeded) = self.exceeded_budget() {
+                return exceeded;
+            }
+
+            self.budget_notify.notified().await;
+        }
+    }
+
+    pub(crate) fn start_instance(&self, instance: &NodeInstance) {
+        self.running
+            .lock()
+            .expect("running lock poisoned")
+            .insert(instance.instance_id, Instant::now());
+
+        self.emit(RunEventKind::NodeStarted {
+            instance_id: instance.instance_id,
+            node_id: instance.node_id.clone(),
+        });
+    }
+
+    /// Mark the instance as skipped since one of its dependencies failed.
+    pub(crate) fn skip_instance(&self, instance: &NodeInstance) {
+        self.instances
+            .lock()
+            .expect("instances lock poisoned")
+            .insert(
+                instance.instance_id,
+                InstanceReport::skipped(instance.node_id.clone()),
+            );
+
+        self.emit(RunEventKind::NodeSkipped {
+            instance_id: instance.instance_id,
+            node_id: instance.node_id.clone(),
+        });
+    }
+
+    pub(crate) fn finish_instance(
+        &self,
+        instance: &NodeInstance,
+        result: &eyre::Result<InstanceArgs>,
+    ) {
+        let started_at = self
+            .running
+            .lock()
+            .expect("running lock poisoned")
+            .remove(&instance.instance_id);
+
+        let (status, error) = match result {
+            Ok(_) => (InstanceStatus::Succeeded, None),
+            Err(err) => (InstanceStatus::Failed, Some(format!("{err:#}"))),
+        };
+
+        let report = InstanceReport {
+            node_id: instance.node_id.clone(),
+            status,
+            duration: started_at.map(|t| t.elapsed()).unwrap_or_default(),
+            usage: self.instance_usage(instance.instance_id),
+            error,
+        };
+
+        let instance_id = instance.instance_id;
+        let node_id = instance.node_id.clone();
+        self.emit(match (result, &report.error) {
+            (Ok(outputs), _) => RunEventKind::NodeOutput {
+                instance_id,
+                node_id,
+                duration: report.duration,
+                usage: report.usage,
+                outputs: outputs
+                    .iter()
+                    .map(|(name, value)| {
+                        let preview = ValuePreview::new(value, self.task.event_preview());
+                        (name.clone(), preview)
+                    })
+                    .collect(),
+            },
+            (Err(_), error) => RunEventKind::NodeFailed {
+                instance_id,
+                node_id,
+                duration: report.duration,
+                error: error.clone().unwrap_or_default(),
+            },
+        });
+
+        self.instances
+            .lock()
+            .expect("instances lock poisoned")
+            .insert(instance.instance_id, report);
+    }
+
+    pub(crate) fn into_report(self, error: Option<&eyre::Report>) -> RunReport {
+        let mut instances =
+            std::mem::take(&mut *self.instances.lock().expect("instances lock poisoned"));
+        let running = std::mem::take(&mut *self.running.lock().expect("running lock poisoned"));
+        let usage = std::mem::take(&mut *self.usage.lock().expect("usage lock poisoned"));
+
+        for instance in self.task.instances() {
+            let id = instance.instance_id;
+            let node_id = instance.node_id.clone();
+
+            if instances.contains_key(&id) {
+                continue;
+            }
+
+            let (report, event) = match running.get(&id) {
+                Some(started_at) => {
+                    let report = InstanceReport {
+                        status: InstanceStatus::Cancelled,
+                        duration: started_at.elapsed(),
+                        usage: usage.get(&id).copied().unwrap_or_default(),
+                        ..InstanceReport::skipped(node_id.clone())
+                    };
+                    let event = RunEventKind::NodeCancelled {
+                        instance_id: id,
+                        node_id,
+                        duration: report.duration,
+                    };
+
+                    (report, event)
+                }
+                None => (
+                    InstanceReport::skipped(node_id.clone()),
+                    RunEventKind::NodeSkipped {
+                        instance_id: id,
+                        node_id,
+                    },
+                ),
+            };
+
+            instances.insert(id, report);
+            self.emit(event);
+        }
+
+        let usage = instances.values().map(|report| report.usage).sum();
+        let report = RunReport {
+            instances,
+            duration: self.started_at.elapsed(),
+            usage,
+            error: error.map(|err| format!("{err:#}")),
+        };
+
+        self.emit(RunEventKind::RunFinished {
+            duration: report.duration,
+            usage: report.usage,
+            error: report.error.clone(),
+        });
+
+        report
+    }
+}
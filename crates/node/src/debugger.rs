@@ -0,0 +1,239 @@
+use crate::*;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot, watch};
+use tokio::time::Instant;
+
+/// Instance or node a debug run pauses before.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Breakpoint {
+    Instance(NodeInstanceId),
+    /// Every instance of the node.
+    Node(NodeId),
+}
+
+impl Breakpoint {
+    pub fn matches(&self, instance: &NodeInstance) -> bool {
+        match self {
+            Self::Instance(instance_id) => *instance_id == instance.instance_id,
+            Self::Node(node_id) => *node_id == instance.node_id,
+        }
+    }
+}
+
+/// Numbers are parsed as instance ids, everything else as node ids.
+impl FromStr for Breakpoint {
+    type Err = eyre::Report;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        if value.is_empty() {
+            eyre::bail!("breakpoint must be an instance id or a node id");
+        }
+
+        Ok(match value.parse::<u32>() {
+            Ok(id) => Self::Instance(NodeInstanceId(id)),
+            Err(_) => Self::Node(NodeId(value.to_string())),
+        })
+    }
+}
+
+impl fmt::Display for Breakpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Instance(instance_id) => write!(f, "{instance_id}"),
+            Self::Node(node_id) => write!(f, "{node_id}"),
+        }
+    }
+}
+
+/// How a paused debug run goes on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DebugAction {
+    /// Run until the next breakpoint.
+    Continue,
+    /// Pause again before the next instance.
+    Step,
+    /// Stop the run, the paused instance fails and nothing else is started.
+    Abort,
+}
+
+/// Breakpoints of a debug run, see [`Task::run_debug`].
+#[derive(Clone, Debug, Default)]
+pub struct Debugger {
+    breakpoints: BTreeSet<Breakpoint>,
+    step: bool,
+}
+
+impl Debugger {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_breakpoint(mut self, breakpoint: Breakpoint) -> Self {
+        self.breakpoints.insert(breakpoint);
+        self
+    }
+
+    pub fn with_breakpoints(mut self, breakpoints: impl IntoIterator<Item = Breakpoint>) -> Self {
+        self.breakpoints.extend(breakpoints);
+        self
+    }
+
+    /// Pause before the first instance, as if stepping from the start of the run.
+    pub fn with_step(mut self, step: bool) -> Self {
+        self.step = step;
+        self
+    }
+}
+
+/// Instance of a debug run paused before it runs.
+///
+/// The inputs and memory can be changed before resuming, changes apply to this execution
+/// only and are not saved to the task. Dropping the pause without resuming aborts the run.
+pub struct DebugPause {
+    pub instance_id: NodeInstanceId,
+    pub node_id: NodeId,
+    /// Input values received from the dependencies.
+    pub inputs: InstanceArgs,
+    pub memory: BTreeMap<String, Value>,
+    resume: oneshot::Sender<Resume>,
+}
+
+impl DebugPause {
+    pub fn resume(self, action: DebugAction) {
+        let _ = self.resume.send(Resume {
+            action,
+            inputs: self.inputs,
+            memory: self.memory,
+        });
+    }
+}
+
+impl fmt::Debug for DebugPause {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DebugPause")
+            .field("instance_id", &self.instance_id)
+            .field("node_id", &self.node_id)
+            .field("inputs", &self.inputs.keys().collect::<Vec<_>>())
+            .field("memory", &self.memory.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+struct Resume {
+    action: DebugAction,
+    inputs: InstanceArgs,
+    memory: BTreeMap<String, Value>,
+}
+
+/// Receiver of the pauses of a debug run, see [`Task::run_debug`].
+pub struct DebugSession {
+    pauses: mpsc::UnboundedReceiver<DebugPause>,
+}
+
+impl DebugSession {
+    /// Wait until the run pauses, `None` once the run finished.
+    pub async fn next_pause(&mut self) -> Option<DebugPause> {
+        self.pauses.recv().await
+    }
+}
+
+/// Debugger attached to a [`RunContext`].
+pub(crate) struct DebugState {
+    breakpoints: BTreeSet<Breakpoint>,
+    stepping: AtomicBool,
+    aborted: AtomicBool,
+    pauses: mpsc::UnboundedSender<DebugPause>,
+    paused_time: watch::Sender<PausedTime>,
+}
+
+/// Time the run spent paused, excluded from [`RunBudget::max_duration`].
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct PausedTime {
+    /// Start of the current pause.
+    pub(crate) since: Option<Instant>,
+    /// Duration of the finished pauses.
+    pub(crate) total: Duration,
+}
+
+impl DebugState {
+    pub(crate) fn new(debugger: Debugger) -> (Self, DebugSession) {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let state = Self {
+            breakpoints: debugger.breakpoints,
+            stepping: AtomicBool::new(debugger.step),
+            aborted: AtomicBool::new(false),
+            pauses: sender,
+            paused_time: watch::Sender::new(PausedTime::default()),
+        };
+
+        (state, DebugSession { pauses: receiver })
+    }
+
+    pub(crate) fn is_aborted(&self) -> bool {
+        self.aborted.load(Ordering::Relaxed)
+    }
+
+    /// Changes of the paused time, on every pause and resume.
+    pub(crate) fn paused_time(&self) -> watch::Receiver<PausedTime> {
+        self.paused_time.subscribe()
+    }
+
+    /// Pause before the instance if it hits a breakpoint or the run is stepping, returns the
+    /// possibly changed inputs and the memory if the instance was paused.
+    #[tracing::instrument(skip_all, fields(instance_id = %instance.instance_id))]
+    pub(crate) async fn pause(
+        &self,
+        instance: &NodeInstance,
+        inputs: InstanceArgs,
+    ) -> eyre::Result<(InstanceArgs, Option<BTreeMap<String, Value>>)> {
+        let is_breakpoint = self
+            .breakpoints
+            .iter()
+            .any(|breakpoint| breakpoint.matches(instance));
+        if !is_breakpoint && !self.stepping.load(Ordering::Relaxed) {
+            return Ok((inputs, None));
+        }
+
+        let (sender, receiver) = oneshot::channel();
+        let pause = DebugPause {
+            instance_id: instance.instance_id,
+            node_id: instance.node_id.clone(),
+            inputs,
+            memory: instance.memory.clone(),
+            resume: sender,
+        };
+
+        tracing::info!("Paused before instance");
+        self.paused_time
+            .send_modify(|paused| paused.since = Some(Instant::now()));
+        // the session or the pause was dropped without resuming
+        let resume = match self.pauses.send(pause) {
+            Ok(()) => receiver.await.ok(),
+            Err(_) => None,
+        };
+        self.paused_time.send_modify(|paused| {
+            if let Some(since) = paused.since.take() {
+                paused.total += since.elapsed();
+            }
+        });
+        let Some(resume) = resume else {
+            self.aborted.store(true, Ordering::Relaxed);
+            eyre::bail!("Debug session closed");
+        };
+
+        match resume.action {
+            DebugAction::Continue => self.stepping.store(false, Ordering::Relaxed),
+            DebugAction::Step => self.stepping.store(true, Ordering::Relaxed),
+            DebugAction::Abort => {
+                self.aborted.store(true, Ordering::Relaxed);
+                eyre::bail!("Run aborted by the debugger");
+            }
+        }
+
+        Ok((resume.inputs, Some(resume.memory)))
+    }
+}
@@ -0,0 +1,104 @@
+use crate::*;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+/// Amount of events buffered for every subscriber, subscribers falling further behind miss the
+/// oldest events and get [`tokio::sync::broadcast::error::RecvError::Lagged`].
+pub const RUN_EVENTS_CAPACITY: usize = 1024;
+
+/// Event of a [`Task::run`], subscribe with [`Task::subscribe`].
+#[derive(Clone, Debug, Serialize)]
+pub struct RunEvent {
+    /// Sequential id of the run, unique within the task.
+    pub run_id: u64,
+    /// Time passed since the run started.
+    #[serde(serialize_with = "serialize_secs")]
+    pub elapsed: Duration,
+    #[serde(flatten)]
+    pub kind: RunEventKind,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RunEventKind {
+    RunStarted {
+        instances: usize,
+    },
+    NodeStarted {
+        instance_id: NodeInstanceId,
+        node_id: NodeId,
+    },
+    /// Instance succeeded with the outputs.
+    NodeOutput {
+        instance_id: NodeInstanceId,
+        node_id: NodeId,
+        #[serde(serialize_with = "serialize_secs")]
+        duration: Duration,
+        usage: TokenUsage,
+        outputs: BTreeMap<String, ValuePreview>,
+    },
+    NodeFailed {
+        instance_id: NodeInstanceId,
+        node_id: NodeId,
+        #[serde(serialize_with = "serialize_secs")]
+        duration: Duration,
+        error: String,
+    },
+    /// Instance was not executed, because one of its dependencies failed or the run stopped.
+    NodeSkipped {
+        instance_id: NodeInstanceId,
+        node_id: NodeId,
+    },
+    /// Running instance was stopped, e.g. because the run budget was exceeded.
+    NodeCancelled {
+        instance_id: NodeInstanceId,
+        node_id: NodeId,
+        #[serde(serialize_with = "serialize_secs")]
+        duration: Duration,
+    },
+    /// Part of the output produced while the instance is running, see
+    /// [`RunContext::stream_chunk`].
+    StreamChunk {
+        instance_id: NodeInstanceId,
+        /// Output argument the chunk belongs to.
+        arg: String,
+        text: String,
+    },
+    RunFinished {
+        #[serde(serialize_with = "serialize_secs")]
+        duration: Duration,
+        usage: TokenUsage,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+    },
+}
+
+/// Output value in [`RunEventKind::NodeOutput`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct ValuePreview {
+    #[serde(rename = "type")]
+    pub type_name: &'static str,
+    /// JSON of the value cut to the preview length, set only if previews are enabled with
+    /// [`Task::set_event_preview`] and the value is serializable.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub preview: Option<String>,
+}
+
+impl ValuePreview {
+    pub fn new(value: &Value, max_chars: Option<usize>) -> Self {
+        let preview = max_chars.and_then(|max_chars| {
+            let json = value.to_json().ok()?.to_string();
+
+            Some(match json.char_indices().nth(max_chars) {
+                Some((end, _)) => format!("{}…", &json[..end]),
+                None => json,
+            })
+        });
+
+        Self {
+            type_name: value.type_name(),
+            preview,
+        }
+    }
+}
@@ -0,0 +1,241 @@
+use crate::*;
+use eyre::ContextCompat;
+use futures::stream::{FuturesUnordered, StreamExt};
+use std::collections::{HashMap, HashSet};
+
+/// Run all instances of the task, starting every instance as soon as its dependencies finish.
+///
+/// Failed instances skip their dependents while independent branches keep running. Exceeding
+/// the run budget stops the run and cancels running instances by dropping their futures.
+pub(crate) async fn execute(task: &Task, ctx: &RunContext<'_>) -> eyre::Result<()> {
+    let budget = ctx.budget();
+    let deadline = budget
+        .max_duration
+        .map(|max_duration| tokio::time::Instant::now() + max_duration);
+
+    let mut pending = task.instances();
+    let mut results = HashMap::<NodeInstanceId, InstanceArgs>::new();
+    let mut failed = HashSet::<NodeInstanceId>::new();
+    let mut first_error = None;
+    let mut executions = 0;
+    let mut running = FuturesUnordered::new();
+
+    loop {
+        // checked after every finished instance, also when it was the last one running
+        if let Some(exceeded) = ctx.exceeded_budget() {
+            return Err(exceeded.into());
+        }
+
+        let mut waiting = Vec::new();
+
+        for instance in pending {
+            let mut deps = instance
+                .input_connections
+                .values()
+                .map(|connection| connection.instance);
+
+            if deps.clone().any(|dep| failed.contains(&dep)) {
+                tracing::debug!(instance_id = %instance.instance_id, "Skipping instance");
+                ctx.skip_instance(instance);
+                failed.insert(instance.instance_id);
+                continue;
+            }
+
+            if !deps.all(|dep| results.contains_key(&dep)) {
+                waiting.push(instance);
+                continue;
+            }
+
+            // debug runs start one instance at a time, so steps follow the instance order
+            if ctx.debugger().is_some() && !running.is_empty() {
+                waiting.push(instance);
+                continue;
+            }
+
+            if let Some(limit) = budget.max_node_executions {
+                if executions >= limit {
+                    return Err(BudgetExceeded {
+                        limit: BudgetLimit::NodeExecutions { limit },
+                        instance_id: Some(instance.instance_id),
+                    }
+                    .into());
+                }
+            }
+            executions += 1;
+
+            match collect_args(instance, &results) {
+                Ok(args) => running.push(run_instance(task, instance, ctx, args)),
+                Err(err) => {
+                    let result = Err::<InstanceArgs, _>(err);
+                    ctx.finish_instance(instance, &result);
+                    failed.insert(instance.instance_id);
+                    if let Err(err) = result {
+                        first_error.get_or_insert(err);
+                    }
+                }
+            }
+        }
+
+        pending = waiting;
+
+        if running.is_empty() {
+            break;
+        }
+
+        // limits win over finished instances, so nothing new starts after a limit was crossed
+        tokio::select! {
+            biased;
+            exceeded = ctx.budget_exceeded() => return Err(exceeded.into()),
+            _ = wait_deadline(deadline, ctx) => {
+                return Err(BudgetExceeded {
+                    limit: BudgetLimit::Duration {
+                        limit: budget.max_duration.unwrap_or_default(),
+                    },
+                    instance_id: ctx.running_instances().first().copied(),
+                }
+                .into());
+            }
+            Some((instance_id, result)) = running.next() => match result {
+                Ok(output) => {
+                    results.insert(instance_id, output);
+                }
+                Err(err) => {
+                    if ctx.debugger().is_some_and(DebugState::is_aborted) {
+                        return Err(err);
+                    }
+                    failed.insert(instance_id);
+                    first_error.get_or_insert(err);
+                }
+            },
+        }
+    }
+
+    first_error.map_or(Ok(()), Err)
+}
+
+/// Wait until the deadline, moved by the time the debugger kept the run paused.
+async fn wait_deadline(deadline: Option<tokio::time::Instant>, ctx: &RunContext<'_>) {
+    let Some(deadline) = deadline else {
+        return std::future::pending().await;
+    };
+    let Some(debugger) = ctx.debugger() else {
+        return tokio::time::sleep_until(deadline).await;
+    };
+
+    let mut paused_time = debugger.paused_time();
+    loop {
+        let paused = *paused_time.borrow_and_update();
+        if paused.since.is_some() {
+            // the clock stops until the run is resumed
+            if paused_time.changed().await.is_err() {
+                return std::future::pending().await;
+            }
+            continue;
+        }
+
+        tokio::select! {
+            _ = tokio::time::sleep_until(deadline + paused.total) => return,
+            changed = paused_time.changed() => {
+                if changed.is_err() {
+                    return tokio::time::sleep_until(deadline + paused.total).await;
+                }
+            }
+        }
+    }
+}
+
+/// Clone outputs of the dependencies connected to the instance inputs.
+fn collect_args(
+    instance: &NodeInstance,
+    results: &HashMap<NodeInstanceId, InstanceArgs>,
+) -> eyre::Result<InstanceArgs> {
+    let mut args = InstanceArgs::new();
+
+    for (arg_name, connection) in &instance.input_connections {
+        let arg_value = results
+            .get(&connection.instance)
+            .context("Result not found")?
+            .get(&connection.arg_name)
+            .with_context(|| {
+                format!(
+                    "Argument {:?} not found in the instance {}",
+                    connection.arg_name, connection.instance
+                )
+            })?;
+
+        args.insert(arg_name.clone(), arg_value.clone());
+    }
+
+    Ok(args)
+}
+
+#[tracing::instrument(
+    skip_all,
+    fields(
+        instance_id = %instance.instance_id,
+        node_id = %instance.node_id,
+        prompt_tokens,
+        completion_tokens,
+        cost,
+        retries,
+        otel.status_code,
+    )
+)]
+async fn run_instance<'a>(
+    task: &'a Task,
+    instance: &'a NodeInstance,
+    ctx: &'a RunContext<'a>,
+    args: InstanceArgs,
+) -> (NodeInstanceId, eyre::Result<InstanceArgs>) {
+    let paused = match ctx.debugger() {
+        Some(debugger) => debugger.pause(instance, args).await,
+        None => Ok((args, None)),
+    };
+
+    ctx.start_instance(instance);
+    let started_at = std::time::Instant::now();
+
+    let result = match paused {
+        Ok((args, memory)) => {
+            // memory changed in the debugger applies to this execution only
+            let edited = memory.map(|memory| NodeInstance {
+                memory,
+                ..instance.clone()
+            });
+            let args = args
+                .iter()
+                .map(|(arg_name, value)| (arg_name.as_str(), value))
+                .collect::<InstanceRefArgs>();
+
+            match task.get_node(&instance.node_id) {
+                Ok(node) => {
+                    node.run(edited.as_ref().unwrap_or(instance), ctx, &args)
+                        .await
+                }
+                Err(err) => Err(err),
+            }
+        }
+        Err(err) => Err(err),
+    };
+    ctx.finish_instance(instance, &result);
+
+    let usage = ctx.instance_usage(instance.instance_id);
+    let span = tracing::Span::current();
+    span.record("prompt_tokens", usage.prompt_tokens);
+    span.record("completion_tokens", usage.completion_tokens);
+    span.record("cost", usage.cost);
+    let retries = ctx.instance_retries(instance.instance_id);
+    span.record("retries", retries);
+    if result.is_err() {
+        span.record("otel.status_code", "ERROR");
+    }
+    record_instance_metrics(
+        instance,
+        started_at.elapsed(),
+        usage,
+        retries,
+        result.is_ok(),
+    );
+
+    (instance.instance_id, result)
+}
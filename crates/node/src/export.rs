@@ -0,0 +1,262 @@
+use crate::*;
+use std::fmt::Write;
+
+/// Render [`TaskGraph`] as Graphviz DOT or Mermaid flowchart.
+///
+/// Instances show their ports with types, every connection is an edge between ports. With a
+/// [`RunReport`] instances are colored by their status.
+pub struct GraphExport<'a> {
+    graph: &'a TaskGraph,
+    report: Option<&'a RunReport>,
+}
+
+impl<'a> GraphExport<'a> {
+    pub fn new(graph: &'a TaskGraph) -> Self {
+        Self {
+            graph,
+            report: None,
+        }
+    }
+
+    pub fn with_report(mut self, report: &'a RunReport) -> Self {
+        self.report = Some(report);
+        self
+    }
+
+    fn status(&self, id: NodeInstanceId) -> Option<InstanceStatus> {
+        self.report?
+            .instances
+            .get(&id)
+            .map(|instance| instance.status)
+    }
+
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph task {\n    rankdir=LR;\n    node [shape=record];\n");
+
+        for instance in &self.graph.instances {
+            // with LR direction a record in braces is laid out horizontally: inputs, title,
+            // outputs
+            let mut columns = Vec::new();
+            if !instance.ports.inputs.is_empty() {
+                columns.push(record_ports("in", &instance.ports.inputs));
+            }
+            columns.push(format!(
+                "{}\\n{}",
+                record_escape(&instance.id.to_string()),
+                record_escape(&instance.node)
+            ));
+            if !instance.ports.outputs.is_empty() {
+                columns.push(record_ports("out", &instance.ports.outputs));
+            }
+
+            let style = match self.status(instance.id) {
+                Some(status) => format!(
+                    ", style=filled, fillcolor=\"{}\", tooltip=\"{status}\"",
+                    status_color(status)
+                ),
+                None => String::new(),
+            };
+            let _ = writeln!(
+                dot,
+                "    \"{}\" [label=\"{{{}}}\"{style}];",
+                instance.id,
+                columns.join("|")
+            );
+        }
+
+        for (name, port) in &self.graph.inputs {
+            let input = format!("\"input:{}\"", name.replace('"', "\\\""));
+            let _ = writeln!(dot, "    {input} [shape=plaintext];");
+            let _ = writeln!(
+                dot,
+                "    {input} -> \"{}\" [label=\"{}\", style=dashed];",
+                port.instance,
+                port.arg.replace('"', "\\\"")
+            );
+        }
+
+        for connection in &self.graph.connections {
+            let _ = writeln!(
+                dot,
+                "    \"{}\":\"{}\" -> \"{}\":\"{}\";",
+                connection.from.instance,
+                record_port_id("out", &connection.from.arg),
+                connection.to.instance,
+                record_port_id("in", &connection.to.arg)
+            );
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+
+    pub fn to_mermaid(&self) -> String {
+        let mut mermaid = String::from("flowchart LR\n");
+
+        for instance in &self.graph.instances {
+            let title = format!("{}: {}", instance.id, instance.node);
+            let mut lines = vec![format!("<b>{}</b>", mermaid_escape(&title))];
+            for (name, port) in &instance.ports.inputs {
+                let optional = if port.optional { "?" } else { "" };
+                let line = format!("in {name}{optional}: {}", short_type_name(port.type_name));
+                lines.push(mermaid_escape(&line));
+            }
+            for (name, port) in &instance.ports.outputs {
+                let line = format!("out {name}: {}", short_type_name(port.type_name));
+                lines.push(mermaid_escape(&line));
+            }
+
+            let _ = writeln!(mermaid, "    n{}[\"{}\"]", instance.id, lines.join("<br/>"));
+        }
+
+        for (index, (name, port)) in self.graph.inputs.iter().enumerate() {
+            let _ = writeln!(
+                mermaid,
+                "    input{index}([\"{}\"]) -.->|\"{}\"| n{}",
+                mermaid_escape(name),
+                mermaid_escape(&port.arg),
+                port.instance
+            );
+        }
+
+        for connection in &self.graph.connections {
+            let _ = writeln!(
+                mermaid,
+                "    n{} -->|\"{} → {}\"| n{}",
+                connection.from.instance,
+                mermaid_escape(&connection.from.arg),
+                mermaid_escape(&connection.to.arg),
+                connection.to.instance
+            );
+        }
+
+        if self.report.is_some() {
+            for status in [
+                InstanceStatus::Succeeded,
+                InstanceStatus::Failed,
+                InstanceStatus::Skipped,
+                InstanceStatus::Cancelled,
+            ] {
+                let _ = writeln!(
+                    mermaid,
+                    "    classDef {status} fill:{}",
+                    status_color(status)
+                );
+            }
+            for instance in &self.graph.instances {
+                if let Some(status) = self.status(instance.id) {
+                    let _ = writeln!(mermaid, "    class n{} {status}", instance.id);
+                }
+            }
+        }
+
+        mermaid
+    }
+}
+
+impl Task {
+    /// Graphviz DOT of the task, colored by the status of the last run if there was one.
+    pub fn to_dot(&self) -> eyre::Result<String> {
+        self.export(|export| export.to_dot())
+    }
+
+    /// Mermaid flowchart of the task, colored by the status of the last run if there was one.
+    pub fn to_mermaid(&self) -> eyre::Result<String> {
+        self.export(|export| export.to_mermaid())
+    }
+
+    fn export(&self, render: impl FnOnce(&GraphExport) -> String) -> eyre::Result<String> {
+        let graph = self.to_graph()?;
+        let report = self.last_report();
+
+        let mut export = GraphExport::new(&graph);
+        if let Some(report) = &report {
+            export = export.with_report(report);
+        }
+
+        Ok(render(&export))
+    }
+}
+
+fn status_color(status: InstanceStatus) -> &'static str {
+    match status {
+        InstanceStatus::Succeeded => "#c8e6c9",
+        InstanceStatus::Failed => "#ffcdd2",
+        InstanceStatus::Skipped => "#e0e0e0",
+        InstanceStatus::Cancelled => "#ffe0b2",
+    }
+}
+
+/// Column of record fields, every port is addressable by [`record_port_id`].
+fn record_ports(direction: &str, ports: &std::collections::BTreeMap<String, PortGraph>) -> String {
+    let fields = ports
+        .iter()
+        .map(|(name, port)| {
+            let optional = if port.optional { "?" } else { "" };
+            format!(
+                "<{}> {}",
+                record_port_id(direction, name),
+                record_escape(&format!(
+                    "{name}{optional}: {}",
+                    short_type_name(port.type_name)
+                ))
+            )
+        })
+        .collect::<Vec<_>>();
+
+    format!("{{{}}}", fields.join("|"))
+}
+
+/// Field id of the port, `<direction>_<name>` with every character of the name except ASCII
+/// letters and digits written as `_<hex code>_`, so any name gives a distinct valid id.
+fn record_port_id(direction: &str, name: &str) -> String {
+    let mut id = format!("{direction}_");
+    for char in name.chars() {
+        if char.is_ascii_alphanumeric() {
+            id.push(char);
+        } else {
+            let _ = write!(id, "_{:x}_", u32::from(char));
+        }
+    }
+
+    id
+}
+
+/// Escape characters with special meaning in DOT record labels.
+fn record_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for char in text.chars() {
+        if matches!(char, '{' | '}' | '|' | '<' | '>' | '"' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(char);
+    }
+
+    escaped
+}
+
+/// Escape characters breaking quoted Mermaid labels.
+fn mermaid_escape(text: &str) -> String {
+    text.replace('"', "#quot;")
+        .replace('<', "#lt;")
+        .replace('>', "#gt;")
+}
+
+/// Type name without module paths, e.g. `Vec<String>` for `alloc::vec::Vec<alloc::string::String>`.
+fn short_type_name(type_name: &str) -> String {
+    let mut short = String::with_capacity(type_name.len());
+    let mut path = String::new();
+
+    for char in type_name.chars() {
+        if char.is_alphanumeric() || char == '_' || char == ':' {
+            path.push(char);
+        } else {
+            short.push_str(path.rsplit("::").next().unwrap_or_default());
+            path.clear();
+            short.push(char);
+        }
+    }
+    short.push_str(path.rsplit("::").next().unwrap_or_default());
+
+    short
+}
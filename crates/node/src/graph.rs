@@ -0,0 +1,267 @@
+use crate::*;
+use eyre::{ContextCompat, WrapErr};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Serializable description of the task instances and connections between them.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct TaskGraph {
+    pub instances: Vec<InstanceGraph>,
+    #[serde(default)]
+    pub connections: Vec<ConnectionGraph>,
+    /// Named inputs of the graph, each one sets memory of an instance, e.g.
+    /// `{"question": "10000.text"}` sets memory `text` of the instance `10000`.
+    ///
+    /// Inputs without value in the graph memory are required.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub inputs: BTreeMap<String, PortRef>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct InstanceGraph {
+    pub id: NodeInstanceId,
+    pub node: NodeId,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub memory: BTreeMap<String, serde_json::Value>,
+    /// Effective ports of the instance. Informational only, on load ports are derived from the
+    /// node and the instance memory.
+    #[serde(default, skip_deserializing)]
+    pub ports: PortsGraph,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct PortsGraph {
+    pub inputs: BTreeMap<String, PortGraph>,
+    pub outputs: BTreeMap<String, PortGraph>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct PortGraph {
+    #[serde(rename = "type")]
+    pub type_name: &'static str,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub optional: bool,
+}
+
+impl From<&NodePorts> for PortsGraph {
+    fn from(ports: &NodePorts) -> Self {
+        Self {
+            inputs: ports
+                .input_args
+                .iter()
+                .map(|(name, arg)| {
+                    let port = PortGraph {
+                        type_name: arg.value_type.type_name,
+                        optional: arg.is_optional,
+                    };
+                    (name.clone(), port)
+                })
+                .collect(),
+            outputs: ports
+                .output_args
+                .iter()
+                .map(|(name, arg)| {
+                    let port = PortGraph {
+                        type_name: arg.value_type.type_name,
+                        optional: false,
+                    };
+                    (name.clone(), port)
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConnectionGraph {
+    pub from: PortRef,
+    pub to: PortRef,
+}
+
+/// Argument of an instance, written as `<instance id>.<argument name>`.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct PortRef {
+    pub instance: NodeInstanceId,
+    pub arg: String,
+}
+
+impl PortRef {
+    pub fn new(instance: NodeInstanceId, arg: impl Into<String>) -> Self {
+        Self {
+            instance,
+            arg: arg.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for PortRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}", self.instance, self.arg)
+    }
+}
+
+impl std::str::FromStr for PortRef {
+    type Err = eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (instance, arg) = s
+            .split_once('.')
+            .with_context(|| format!("Invalid port {s:?}, expected <instance>.<argument>"))?;
+
+        let instance = instance
+            .parse::<u32>()
+            .with_context(|| format!("Invalid instance id in port {s:?}"))?;
+
+        Ok(Self::new(NodeInstanceId(instance), arg))
+    }
+}
+
+impl TryFrom<String> for PortRef {
+    type Error = eyre::Report;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<PortRef> for String {
+    fn from(value: PortRef) -> Self {
+        value.to_string()
+    }
+}
+
+impl Task {
+    /// Describe task instances with their effective ports and connections.
+    #[tracing::instrument(skip(self))]
+    pub fn to_graph(&self) -> eyre::Result<TaskGraph> {
+        let mut graph = TaskGraph::default();
+
+        for instance in self.instances() {
+            let node = self.get_node(&instance.node_id)?;
+            let ports = self.get_instance_ports(instance.instance_id)?;
+
+            let memory = instance
+                .memory
+                .iter()
+                .map(|(name, value)| {
+                    let json = value.to_json().wrap_err_with(|| {
+                        format!(
+                            "Failed to serialize memory {name:?} of {}",
+                            instance.instance_id
+                        )
+                    })?;
+                    Ok((name.clone(), json))
+                })
+                .collect::<eyre::Result<_>>()?;
+
+            graph.instances.push(InstanceGraph {
+                id: instance.instance_id,
+                node: instance.node_id.clone(),
+                version: Some(node.get_meta().version.clone()),
+                memory,
+                ports: PortsGraph::from(&ports),
+            });
+
+            for (arg, connection) in &instance.input_connections {
+                graph.connections.push(ConnectionGraph {
+                    from: PortRef::new(connection.instance, connection.arg_name.clone()),
+                    to: PortRef::new(instance.instance_id, arg.clone()),
+                });
+            }
+        }
+
+        Ok(graph)
+    }
+
+    /// Create instances and connections described by the graph. Nodes must be registered
+    /// beforehand.
+    #[tracing::instrument(skip_all)]
+    pub fn load_graph(&mut self, graph: &TaskGraph) -> eyre::Result<()> {
+        for (name, port) in &graph.inputs {
+            if !graph
+                .instances
+                .iter()
+                .any(|instance| instance.id == port.instance)
+            {
+                return Err(eyre::eyre!(
+                    "Graph input {name:?} refers to unknown instance {}",
+                    port.instance
+                ));
+            }
+        }
+
+        for instance in &graph.instances {
+            let node = self
+                .get_node(&instance.node)
+                .wrap_err_with(|| format!("Unknown node {:?} of {}", instance.node, instance.id))?;
+
+            let version = &node.get_meta().version;
+            if instance.version.as_ref().is_some_and(|v| v != version) {
+                tracing::warn!(
+                    instance_id = %instance.id,
+                    "Graph was saved with {:?} version {:?}, registered version is {version:?}",
+                    instance.node,
+                    instance.version,
+                );
+            }
+
+            self.instantiate_with_id(&instance.node, instance.id)?;
+
+            for (name, json) in &instance.memory {
+                self.set_instance_memory_value(instance.id, name, Value::from_json(json.clone()))?;
+            }
+        }
+
+        // connect after all memory is set, since ports may depend on it
+        for connection in &graph.connections {
+            self.connect(
+                connection.from.instance,
+                &connection.from.arg,
+                connection.to.instance,
+                &connection.to.arg,
+            )
+            .wrap_err_with(|| {
+                format!("Failed to connect {} -> {}", connection.from, connection.to)
+            })?;
+        }
+
+        Ok(())
+    }
+
+    /// Set values of the graph inputs, see [`TaskGraph::inputs`].
+    ///
+    /// Fails on unknown inputs and on required inputs without value.
+    #[tracing::instrument(skip_all)]
+    pub fn set_graph_inputs(
+        &mut self,
+        graph: &TaskGraph,
+        mut values: BTreeMap<String, Value>,
+    ) -> eyre::Result<()> {
+        if let Some(name) = values.keys().find(|name| !graph.inputs.contains_key(*name)) {
+            return Err(eyre::eyre!("Unknown graph input {name:?}"));
+        }
+
+        let mut missing = Vec::new();
+        for (name, port) in &graph.inputs {
+            match values.remove(name) {
+                Some(value) => self
+                    .set_instance_memory_value(port.instance, &port.arg, value)
+                    .wrap_err_with(|| format!("Failed to set graph input {name:?}"))?,
+                None if self
+                    .get_instance(port.instance)?
+                    .memory
+                    .contains_key(&port.arg) => {}
+                None => missing.push(name.as_str()),
+            }
+        }
+
+        if missing.is_empty() {
+            Ok(())
+        } else {
+            Err(eyre::eyre!("Missing graph inputs: {}", missing.join(", ")))
+        }
+    }
+}
@@ -0,0 +1,340 @@
+use eyre::ContextCompat;
+
+/// Step of a [`JsonPath`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum JsonPathSegment {
+    /// Field of an object, `.name` or `['name']`.
+    Key(String),
+    /// Element of an array, negative indices count from the end, `[0]` or `[-1]`.
+    Index(i64),
+    /// All fields of an object or elements of an array, `.*` or `[*]`.
+    Wildcard,
+}
+
+impl std::fmt::Display for JsonPathSegment {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Key(key) if is_identifier(key) => write!(f, ".{key}"),
+            Self::Key(key) => write!(f, "[{}]", serde_json::Value::String(key.clone())),
+            Self::Index(index) => write!(f, "[{index}]"),
+            Self::Wildcard => write!(f, "[*]"),
+        }
+    }
+}
+
+/// Subset of JSONPath: `$.items[0].name`, `$['key with spaces']`, `$.items[*].id`, `$.items[-1]`.
+///
+/// The leading `$` is optional, so `items[0].name` is the same path.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct JsonPath {
+    pub segments: Vec<JsonPathSegment>,
+}
+
+impl std::fmt::Display for JsonPath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "$")?;
+        for segment in &self.segments {
+            write!(f, "{segment}")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl std::str::FromStr for JsonPath {
+    type Err = eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl JsonPath {
+    pub fn parse(path: &str) -> eyre::Result<Self> {
+        let invalid = |reason: &str| eyre::eyre!("Invalid JSON path {path:?}: {reason}");
+
+        let mut rest = path.trim();
+        rest = rest.strip_prefix('$').unwrap_or(rest);
+        // allow `name.other` without the leading `$.`
+        let mut segments = Vec::new();
+        if rest.starts_with(|c: char| c != '.' && c != '[') {
+            rest = Self::parse_key(rest, &mut segments).map_err(|reason| invalid(&reason))?;
+        }
+
+        while !rest.is_empty() {
+            if let Some(after) = rest.strip_prefix('.') {
+                rest = Self::parse_key(after, &mut segments).map_err(|reason| invalid(&reason))?;
+            } else if let Some(after) = rest.strip_prefix('[') {
+                let (segment, after) =
+                    Self::parse_bracket(after).map_err(|reason| invalid(&reason))?;
+                segments.push(segment);
+                rest = after;
+            } else {
+                return Err(invalid("expected `.` or `[`"));
+            }
+        }
+
+        Ok(Self { segments })
+    }
+
+    fn parse_key<'p>(
+        rest: &'p str,
+        segments: &mut Vec<JsonPathSegment>,
+    ) -> Result<&'p str, String> {
+        if let Some(after) = rest.strip_prefix('*') {
+            segments.push(JsonPathSegment::Wildcard);
+            return Ok(after);
+        }
+
+        let len = rest
+            .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '-'))
+            .unwrap_or(rest.len());
+        if len == 0 {
+            return Err("empty key".to_string());
+        }
+
+        segments.push(JsonPathSegment::Key(rest[..len].to_string()));
+        Ok(&rest[len..])
+    }
+
+    fn parse_bracket(rest: &str) -> Result<(JsonPathSegment, &str), String> {
+        if let Some(quote) = rest.chars().next().filter(|c| *c == '\'' || *c == '"') {
+            let len = rest[1..]
+                .find(quote)
+                .ok_or_else(|| "unterminated quoted key".to_string())?;
+            let key = rest[1..1 + len].to_string();
+            let after = rest[1 + len + 1..]
+                .strip_prefix(']')
+                .ok_or_else(|| "expected `]` after quoted key".to_string())?;
+
+            return Ok((JsonPathSegment::Key(key), after));
+        }
+
+        let len = rest
+            .find(']')
+            .ok_or_else(|| "unterminated `[`".to_string())?;
+        let inner = rest[..len].trim();
+        let segment = match inner {
+            "*" => JsonPathSegment::Wildcard,
+            _ => JsonPathSegment::Index(
+                inner
+                    .parse()
+                    .map_err(|_| format!("invalid index {inner:?}"))?,
+            ),
+        };
+
+        Ok((segment, &rest[len + 1..]))
+    }
+
+    /// Whether the path selects at most one value, i.e. it has no wildcards.
+    pub fn is_singular(&self) -> bool {
+        !self.segments.contains(&JsonPathSegment::Wildcard)
+    }
+
+    /// All values selected by the path, missing keys and indices select nothing.
+    pub fn query<'v>(&self, json: &'v serde_json::Value) -> Vec<&'v serde_json::Value> {
+        let mut current = vec![json];
+
+        for segment in &self.segments {
+            current = current
+                .into_iter()
+                .flat_map(|value| -> Vec<&'v serde_json::Value> {
+                    match (segment, value) {
+                        (JsonPathSegment::Key(key), serde_json::Value::Object(map)) => {
+                            map.get(key).into_iter().collect()
+                        }
+                        (JsonPathSegment::Index(index), serde_json::Value::Array(items)) => {
+                            resolve_index(*index, items.len())
+                                .map(|index| &items[index])
+                                .into_iter()
+                                .collect()
+                        }
+                        (JsonPathSegment::Wildcard, serde_json::Value::Object(map)) => {
+                            map.values().collect()
+                        }
+                        (JsonPathSegment::Wildcard, serde_json::Value::Array(items)) => {
+                            items.iter().collect()
+                        }
+                        _ => Vec::new(),
+                    }
+                })
+                .collect();
+        }
+
+        current
+    }
+
+    /// The single value selected by the path.
+    ///
+    /// The error names the part of the path which could not be resolved.
+    pub fn get<'v>(&self, json: &'v serde_json::Value) -> eyre::Result<&'v serde_json::Value> {
+        let mut current = json;
+        let mut resolved = JsonPath {
+            segments: Vec::new(),
+        };
+
+        for segment in &self.segments {
+            let next = match (segment, current) {
+                (JsonPathSegment::Key(key), serde_json::Value::Object(map)) => map
+                    .get(key)
+                    .with_context(|| format!("Key {key:?} not found at {resolved}")),
+                (JsonPathSegment::Index(index), serde_json::Value::Array(items)) => {
+                    resolve_index(*index, items.len())
+                        .map(|index| &items[index])
+                        .with_context(|| {
+                            format!(
+                                "Index {index} is out of bounds at {resolved}, length is {}",
+                                items.len()
+                            )
+                        })
+                }
+                (JsonPathSegment::Wildcard, _) => Err(eyre::eyre!(
+                    "Wildcard at {resolved} selects multiple values"
+                )),
+                (_, value) => Err(eyre::eyre!(
+                    "Expected {} at {resolved}, found {}",
+                    match segment {
+                        JsonPathSegment::Key(_) => "object",
+                        _ => "array",
+                    },
+                    json_kind(value)
+                )),
+            };
+
+            current = next.map_err(|err| err.wrap_err(format!("JSON path {self} failed")))?;
+            resolved.segments.push(segment.clone());
+        }
+
+        Ok(current)
+    }
+}
+
+fn resolve_index(index: i64, len: usize) -> Option<usize> {
+    let index = if index < 0 {
+        len.checked_sub(index.unsigned_abs() as usize)?
+    } else {
+        index as usize
+    };
+
+    (index < len).then_some(index)
+}
+
+fn is_identifier(key: &str) -> bool {
+    !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+}
+
+/// Name of the JSON type for error messages.
+pub fn json_kind(value: &serde_json::Value) -> &'static str {
+    match value {
+        serde_json::Value::Null => "null",
+        serde_json::Value::Bool(_) => "boolean",
+        serde_json::Value::Number(_) => "number",
+        serde_json::Value::String(_) => "string",
+        serde_json::Value::Array(_) => "array",
+        serde_json::Value::Object(_) => "object",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn key(key: &str) -> JsonPathSegment {
+        JsonPathSegment::Key(key.to_string())
+    }
+
+    #[test]
+    fn parse_paths() {
+        let path = JsonPath::parse("$.items[0].name").unwrap();
+        assert_eq!(
+            path.segments,
+            [key("items"), JsonPathSegment::Index(0), key("name")]
+        );
+        assert_eq!(JsonPath::parse("items[0].name").unwrap(), path);
+        assert_eq!(JsonPath::parse("$").unwrap().segments, []);
+
+        let path = JsonPath::parse(r#"$['key with spaces']["other"][-1][*].*"#).unwrap();
+        assert_eq!(
+            path.segments,
+            [
+                key("key with spaces"),
+                key("other"),
+                JsonPathSegment::Index(-1),
+                JsonPathSegment::Wildcard,
+                JsonPathSegment::Wildcard,
+            ]
+        );
+        assert!(!path.is_singular());
+
+        // display round trips
+        assert_eq!(path.to_string(), r#"$["key with spaces"].other[-1][*][*]"#);
+        assert_eq!(JsonPath::parse(&path.to_string()).unwrap(), path);
+    }
+
+    #[test]
+    fn parse_invalid_paths() {
+        for (path, reason) in [
+            ("$.", "empty key"),
+            ("$..name", "empty key"),
+            ("$[0", "unterminated `[`"),
+            ("$[first]", "invalid index"),
+            ("$['name", "unterminated quoted key"),
+            ("$['name'x]", "expected `]`"),
+            ("$.name!", "expected `.` or `[`"),
+        ] {
+            let err = JsonPath::parse(path).unwrap_err();
+            assert!(err.to_string().contains(reason), "{path}: {err}");
+        }
+    }
+
+    #[test]
+    fn get_values() {
+        let json = json!({ "items": [{ "name": "a" }, { "name": "b" }], "count": 2 });
+        let get = |path: &str| JsonPath::parse(path).unwrap().get(&json).cloned();
+
+        assert_eq!(get("$.items[0].name").unwrap(), "a");
+        assert_eq!(get("$.items[-1].name").unwrap(), "b");
+        assert_eq!(get("count").unwrap(), 2);
+
+        let err = get("$.items[2]").unwrap_err();
+        assert!(
+            format!("{err:#}").contains("Index 2 is out of bounds at $.items, length is 2"),
+            "{err:#}"
+        );
+        let err = get("$.items[-3]").unwrap_err();
+        assert!(format!("{err:#}").contains("out of bounds"), "{err:#}");
+        let err = get("$.items[0].missing").unwrap_err();
+        assert!(
+            format!("{err:#}").contains(r#"Key "missing" not found at $.items[0]"#),
+            "{err:#}"
+        );
+        let err = get("$.count[0]").unwrap_err();
+        assert!(
+            format!("{err:#}").contains("Expected array at $.count, found number"),
+            "{err:#}"
+        );
+        assert!(get("$.items[*]").is_err());
+    }
+
+    #[test]
+    fn query_values() {
+        let json = json!({ "items": [{ "id": 1 }, { "id": 2 }, { "name": "c" }] });
+        let query = |path: &str| {
+            JsonPath::parse(path)
+                .unwrap()
+                .query(&json)
+                .into_iter()
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(query("$.items[*].id"), [json!(1), json!(2)]);
+        assert_eq!(query("$.items[-1].name"), [json!("c")]);
+        assert_eq!(query("$.items[5]"), Vec::<serde_json::Value>::new());
+        assert_eq!(query("$.missing[*]"), Vec::<serde_json::Value>::new());
+    }
+}
@@ -1,7 +1,33 @@
+mod budget;
+mod chat;
+mod context;
+mod debugger;
+mod events;
+mod executor;
+mod export;
+mod graph;
+mod json_path;
 mod node;
+mod report;
+mod run_metrics;
 mod state;
+mod text_split;
 mod value;
+mod vector_store;
 
+pub use budget::*;
+pub use chat::*;
+pub use context::*;
+pub use debugger::*;
+pub use events::*;
+use executor::*;
+pub use export::*;
+pub use graph::*;
+pub use json_path::*;
 pub use node::*;
+pub use report::*;
+pub use run_metrics::*;
 pub use state::*;
+pub use text_split::*;
 pub use value::*;
+pub use vector_store::*;
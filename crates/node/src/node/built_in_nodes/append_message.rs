@@ -0,0 +1,69 @@
+use crate::*;
+use eyre::ContextCompat;
+use std::collections::BTreeMap;
+
+/// Append a message to the conversation.
+///
+/// Role of the message is taken from [`NodeAppendMessage::MEMORY_ROLE`] (`user` by default), if
+/// the conversation input is not connected, a new conversation is started.
+pub struct NodeAppendMessage;
+
+impl NodeAppendMessage {
+    pub const INPUT_ARG_CONVERSATION: &str = "conversation";
+    pub const INPUT_ARG_TEXT: &str = "text";
+    pub const OUT_ARG_CONVERSATION: &str = "conversation";
+    pub const MEMORY_ROLE: &str = "role";
+    pub const MEMORY_NAME: &str = "name";
+}
+
+impl NodeTrait for NodeAppendMessage {
+    fn run<'a>(
+        &'a self,
+        instance: &'a NodeInstance,
+        _ctx: &'a RunContext,
+        input: &'a InstanceRefArgs,
+    ) -> RunResult<'a> {
+        Box::pin(async {
+            let text = input
+                .get(Self::INPUT_ARG_TEXT)
+                .context("Append message node: missing input argument")?
+                .downcast::<String>()?;
+
+            let mut conversation = match input.get(Self::INPUT_ARG_CONVERSATION) {
+                Some(conversation) => conversation.downcast::<Conversation>()?.clone(),
+                None => Conversation::new(),
+            };
+
+            let role = match instance.get_memory::<String>(Self::MEMORY_ROLE)? {
+                Some(role) => role.parse()?,
+                None => ChatRole::User,
+            };
+
+            let mut message = ChatMessage::new(role, text.clone());
+            if let Some(name) = instance.get_memory::<String>(Self::MEMORY_NAME)? {
+                message = message.with_name(name.clone());
+            }
+            conversation.push(message);
+
+            Ok(BTreeMap::from([(
+                Self::OUT_ARG_CONVERSATION.to_string(),
+                Value::new(conversation),
+            )]))
+        })
+    }
+}
+
+impl NodeMetaTrait for NodeAppendMessage {
+    fn get_meta(&self) -> NodeMeta {
+        NodeMeta::new("append_message", "0.1.0")
+            .with_input_arg(
+                Self::INPUT_ARG_CONVERSATION,
+                InputArgMeta::new::<Conversation>().with_optional(true),
+            )
+            .with_input_arg(Self::INPUT_ARG_TEXT, InputArgMeta::new::<String>())
+            .with_output_arg(
+                Self::OUT_ARG_CONVERSATION,
+                OutputArgMeta::new::<Conversation>(),
+            )
+    }
+}
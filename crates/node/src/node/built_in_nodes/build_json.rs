@@ -0,0 +1,117 @@
+use crate::*;
+use eyre::ContextCompat;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Type of the input argument of [`NodeBuildJson`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JsonFieldKind {
+    #[default]
+    Json,
+    String,
+    Integer,
+    Number,
+    Bool,
+    Strings,
+}
+
+impl JsonFieldKind {
+    fn input_arg(self) -> InputArgMeta {
+        match self {
+            Self::Json => InputArgMeta::new::<serde_json::Value>(),
+            Self::String => InputArgMeta::new::<String>(),
+            Self::Integer => InputArgMeta::new::<i64>(),
+            Self::Number => InputArgMeta::new::<f64>(),
+            Self::Bool => InputArgMeta::new::<bool>(),
+            Self::Strings => InputArgMeta::new::<Vec<String>>(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(untagged)]
+enum JsonFields {
+    /// All fields are [`JsonFieldKind::Json`].
+    Names(Vec<String>),
+    Kinds(BTreeMap<String, JsonFieldKind>),
+}
+
+/// Build a JSON object with a field for every input argument.
+///
+/// Fields are configured in memory, each one becomes an input argument of the instance.
+pub struct NodeBuildJson;
+
+impl NodeBuildJson {
+    pub const OUT_ARG_JSON: &str = "json";
+    /// List of field names, or object of field names to [`JsonFieldKind`] names, e.g.
+    /// `{"title": "string", "tags": "strings", "meta": "json"}`.
+    pub const MEMORY_FIELDS: &str = "fields";
+
+    fn fields(instance: &NodeInstance) -> eyre::Result<BTreeMap<String, JsonFieldKind>> {
+        let fields = instance.get_memory_parsed::<JsonFields>(Self::MEMORY_FIELDS)?;
+
+        Ok(match fields {
+            Some(JsonFields::Names(names)) => names
+                .into_iter()
+                .map(|name| (name, JsonFieldKind::Json))
+                .collect(),
+            Some(JsonFields::Kinds(kinds)) => kinds,
+            None => BTreeMap::new(),
+        })
+    }
+}
+
+impl NodeTrait for NodeBuildJson {
+    fn run<'a>(
+        &'a self,
+        instance: &'a NodeInstance,
+        _ctx: &'a RunContext,
+        input: &'a InstanceRefArgs,
+    ) -> RunResult<'a> {
+        Box::pin(async {
+            let mut object = serde_json::Map::new();
+            for name in Self::fields(instance)?.into_keys() {
+                let value = input
+                    .get(name.as_str())
+                    .with_context(|| {
+                        format!(
+                            "Build JSON node {}: missing field {name:?}",
+                            instance.instance_id
+                        )
+                    })?
+                    .to_json()
+                    .map_err(|err| {
+                        err.wrap_err(format!(
+                            "Build JSON node {}: field {name:?} is not serializable",
+                            instance.instance_id
+                        ))
+                    })?;
+
+                object.insert(name, value);
+            }
+
+            Ok(BTreeMap::from([(
+                Self::OUT_ARG_JSON.to_string(),
+                Value::new(serde_json::Value::Object(object)),
+            )]))
+        })
+    }
+
+    fn instance_ports(&self, instance: &NodeInstance, ports: &mut NodePorts) -> eyre::Result<()> {
+        for (name, kind) in Self::fields(instance)? {
+            ports.input_args.insert(name, kind.input_arg());
+        }
+
+        Ok(())
+    }
+}
+
+impl NodeMetaTrait for NodeBuildJson {
+    fn get_meta(&self) -> NodeMeta {
+        NodeMeta::new("build_json", "0.1.0").with_output_arg(
+            Self::OUT_ARG_JSON,
+            OutputArgMeta::new::<serde_json::Value>(),
+        )
+    }
+}
@@ -1,5 +1,44 @@
+mod append_message;
+mod build_json;
+mod parse_json;
 mod print;
+mod query_json;
+mod render_conversation;
+mod split_text;
+mod stringify_json;
+mod template;
 mod text;
+mod trim_conversation;
 
+pub use append_message::*;
+pub use build_json::*;
+pub use parse_json::*;
 pub use print::*;
+pub use query_json::*;
+pub use render_conversation::*;
+pub use split_text::*;
+pub use stringify_json::*;
+pub use template::*;
 pub use text::*;
+pub use trim_conversation::*;
+
+use crate::*;
+
+impl Task {
+    /// Register all nodes of this crate which need no configuration.
+    pub fn register_built_in_nodes(&mut self) -> eyre::Result<()> {
+        self.register_node(NodeAppendMessage)?;
+        self.register_node(NodeBuildJson)?;
+        self.register_node(NodeParseJson)?;
+        self.register_node(NodePrint)?;
+        self.register_node(NodeQueryJson)?;
+        self.register_node(NodeRenderConversation)?;
+        self.register_node(NodeSplitText)?;
+        self.register_node(NodeStringifyJson)?;
+        self.register_node(NodeTemplate)?;
+        self.register_node(NodeText)?;
+        self.register_node(NodeTrimConversation)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,70 @@
+use crate::*;
+use eyre::ContextCompat;
+use std::collections::BTreeMap;
+
+/// Parse text into [`serde_json::Value`].
+///
+/// A markdown code fence around the JSON, common in LLM responses, is ignored.
+pub struct NodeParseJson;
+
+impl NodeParseJson {
+    pub const INPUT_ARG_TEXT: &str = "text";
+    pub const OUT_ARG_JSON: &str = "json";
+
+    /// Text inside the code fence, or the whole text if it is not fenced.
+    pub fn strip_code_fence(text: &str) -> &str {
+        let trimmed = text.trim();
+        let Some(rest) = trimmed.strip_prefix("```") else {
+            return trimmed;
+        };
+        let Some(body) = rest.strip_suffix("```") else {
+            return trimmed;
+        };
+
+        // skip the language tag
+        match body.find('\n') {
+            Some(newline) => body[newline + 1..].trim(),
+            None => body.trim(),
+        }
+    }
+}
+
+impl NodeTrait for NodeParseJson {
+    fn run<'a>(
+        &'a self,
+        instance: &'a NodeInstance,
+        _ctx: &'a RunContext,
+        input: &'a InstanceRefArgs,
+    ) -> RunResult<'a> {
+        Box::pin(async {
+            let text = input
+                .get(Self::INPUT_ARG_TEXT)
+                .context("Parse JSON node: missing input argument")?
+                .downcast::<String>()?;
+
+            let json = serde_json::from_str::<serde_json::Value>(Self::strip_code_fence(text))
+                .map_err(|err| {
+                    eyre::eyre!(
+                        "Parse JSON node {}: invalid JSON: {err}",
+                        instance.instance_id
+                    )
+                })?;
+
+            Ok(BTreeMap::from([(
+                Self::OUT_ARG_JSON.to_string(),
+                Value::new(json),
+            )]))
+        })
+    }
+}
+
+impl NodeMetaTrait for NodeParseJson {
+    fn get_meta(&self) -> NodeMeta {
+        NodeMeta::new("parse_json", "0.1.0")
+            .with_input_arg(Self::INPUT_ARG_TEXT, InputArgMeta::new::<String>())
+            .with_output_arg(
+                Self::OUT_ARG_JSON,
+                OutputArgMeta::new::<serde_json::Value>(),
+            )
+    }
+}
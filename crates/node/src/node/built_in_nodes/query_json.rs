@@ -0,0 +1,87 @@
+use crate::*;
+use eyre::ContextCompat;
+use std::collections::BTreeMap;
+
+/// Extract values from JSON with [`JsonPath`] expressions stored in memory.
+///
+/// Every entry of the `queries` object becomes a [`serde_json::Value`] output argument of the
+/// instance. Paths with wildcards output an array of all matches, other paths output the single
+/// value and fail if it does not exist, unless `optional` is set.
+pub struct NodeQueryJson;
+
+impl NodeQueryJson {
+    pub const INPUT_ARG_JSON: &str = "json";
+    /// Object of output argument names to JSON paths, e.g. `{"name": "$.user.name"}`.
+    pub const MEMORY_QUERIES: &str = "queries";
+    /// Output `null` instead of failing when a path does not exist.
+    pub const MEMORY_OPTIONAL: &str = "optional";
+
+    fn queries(instance: &NodeInstance) -> eyre::Result<BTreeMap<String, String>> {
+        Ok(instance
+            .get_memory_parsed::<BTreeMap<String, String>>(Self::MEMORY_QUERIES)?
+            .unwrap_or_default())
+    }
+}
+
+impl NodeTrait for NodeQueryJson {
+    fn run<'a>(
+        &'a self,
+        instance: &'a NodeInstance,
+        _ctx: &'a RunContext,
+        input: &'a InstanceRefArgs,
+    ) -> RunResult<'a> {
+        Box::pin(async {
+            let json = input
+                .get(Self::INPUT_ARG_JSON)
+                .context("Query JSON node: missing input argument")?
+                .downcast::<serde_json::Value>()?;
+            let optional = instance
+                .get_memory_parsed::<bool>(Self::MEMORY_OPTIONAL)?
+                .unwrap_or(false);
+
+            let mut output = BTreeMap::new();
+            for (name, path) in Self::queries(instance)? {
+                let wrap = |err: eyre::Report| {
+                    err.wrap_err(format!(
+                        "Query JSON node {}: query {name:?} failed",
+                        instance.instance_id
+                    ))
+                };
+
+                let path = JsonPath::parse(&path).map_err(wrap)?;
+                let value = if path.is_singular() {
+                    match path.get(json) {
+                        Ok(value) => value.clone(),
+                        Err(_) if optional => serde_json::Value::Null,
+                        Err(err) => return Err(wrap(err)),
+                    }
+                } else {
+                    serde_json::Value::Array(path.query(json).into_iter().cloned().collect())
+                };
+
+                output.insert(name, Value::new(value));
+            }
+
+            Ok(output)
+        })
+    }
+
+    fn instance_ports(&self, instance: &NodeInstance, ports: &mut NodePorts) -> eyre::Result<()> {
+        for name in Self::queries(instance)?.into_keys() {
+            ports
+                .output_args
+                .insert(name, OutputArgMeta::new::<serde_json::Value>());
+        }
+
+        Ok(())
+    }
+}
+
+impl NodeMetaTrait for NodeQueryJson {
+    fn get_meta(&self) -> NodeMeta {
+        NodeMeta::new("query_json", "0.1.0").with_input_arg(
+            Self::INPUT_ARG_JSON,
+            InputArgMeta::new::<serde_json::Value>(),
+        )
+    }
+}
@@ -0,0 +1,43 @@
+use crate::*;
+use eyre::ContextCompat;
+use std::collections::BTreeMap;
+
+/// Render conversation as plain text
+pub struct NodeRenderConversation;
+
+impl NodeRenderConversation {
+    pub const INPUT_ARG_CONVERSATION: &str = "conversation";
+    pub const OUT_ARG_TEXT: &str = "text";
+}
+
+impl NodeTrait for NodeRenderConversation {
+    fn run<'a>(
+        &'a self,
+        _instance: &'a NodeInstance,
+        _ctx: &'a RunContext,
+        input: &'a InstanceRefArgs,
+    ) -> RunResult<'a> {
+        Box::pin(async {
+            let conversation = input
+                .get(Self::INPUT_ARG_CONVERSATION)
+                .context("Render conversation node: missing input argument")?
+                .downcast::<Conversation>()?;
+
+            Ok(BTreeMap::from([(
+                Self::OUT_ARG_TEXT.to_string(),
+                Value::new(conversation.render()),
+            )]))
+        })
+    }
+}
+
+impl NodeMetaTrait for NodeRenderConversation {
+    fn get_meta(&self) -> NodeMeta {
+        NodeMeta::new("render_conversation", "0.1.0")
+            .with_input_arg(
+                Self::INPUT_ARG_CONVERSATION,
+                InputArgMeta::new::<Conversation>(),
+            )
+            .with_output_arg(Self::OUT_ARG_TEXT, OutputArgMeta::new::<String>())
+    }
+}
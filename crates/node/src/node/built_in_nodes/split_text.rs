@@ -0,0 +1,80 @@
+use crate::*;
+use eyre::ContextCompat;
+use std::collections::BTreeMap;
+
+/// Split text into chunks with [`TextSplitter`] configured through memory.
+///
+/// Besides the chunks, their byte offsets in the input text are returned so downstream nodes
+/// can cite the source.
+pub struct NodeSplitText;
+
+impl NodeSplitText {
+    pub const INPUT_ARG_TEXT: &str = "text";
+    pub const OUT_ARG_CHUNKS: &str = "chunks";
+    pub const OUT_ARG_OFFSETS: &str = "offsets";
+    /// One of [`SplitStrategy`] names, `recursive` by default.
+    pub const MEMORY_STRATEGY: &str = "strategy";
+    pub const MEMORY_CHUNK_SIZE: &str = "chunk_size";
+    pub const MEMORY_CHUNK_OVERLAP: &str = "chunk_overlap";
+    /// Separators of recursive strategies as a list of strings.
+    pub const MEMORY_SEPARATORS: &str = "separators";
+
+    fn splitter(instance: &NodeInstance) -> eyre::Result<TextSplitter> {
+        let mut splitter = TextSplitter::default();
+
+        if let Some(strategy) = instance.get_memory_parsed::<String>(Self::MEMORY_STRATEGY)? {
+            splitter.strategy = strategy.parse()?;
+        }
+        if let Some(chunk_size) = instance.get_memory_parsed::<usize>(Self::MEMORY_CHUNK_SIZE)? {
+            splitter.chunk_size = chunk_size;
+        }
+        if let Some(overlap) = instance.get_memory_parsed::<usize>(Self::MEMORY_CHUNK_OVERLAP)? {
+            splitter.chunk_overlap = overlap;
+        }
+        if let Some(separators) =
+            instance.get_memory_parsed::<Vec<String>>(Self::MEMORY_SEPARATORS)?
+        {
+            splitter.separators = separators;
+        }
+
+        Ok(splitter)
+    }
+}
+
+impl NodeTrait for NodeSplitText {
+    fn run<'a>(
+        &'a self,
+        instance: &'a NodeInstance,
+        _ctx: &'a RunContext,
+        input: &'a InstanceRefArgs,
+    ) -> RunResult<'a> {
+        Box::pin(async {
+            let text = input
+                .get(Self::INPUT_ARG_TEXT)
+                .context("Split text node: missing input argument")?
+                .downcast::<String>()?;
+
+            let offsets = Self::splitter(instance)?
+                .split(text)
+                .map_err(|err| err.wrap_err("Split text node: invalid configuration"))?;
+            let chunks = offsets
+                .iter()
+                .map(|span| span.slice(text).to_string())
+                .collect::<Vec<_>>();
+
+            Ok(BTreeMap::from([
+                (Self::OUT_ARG_CHUNKS.to_string(), Value::new(chunks)),
+                (Self::OUT_ARG_OFFSETS.to_string(), Value::new(offsets)),
+            ]))
+        })
+    }
+}
+
+impl NodeMetaTrait for NodeSplitText {
+    fn get_meta(&self) -> NodeMeta {
+        NodeMeta::new("split_text", "0.1.0")
+            .with_input_arg(Self::INPUT_ARG_TEXT, InputArgMeta::new::<String>())
+            .with_output_arg(Self::OUT_ARG_CHUNKS, OutputArgMeta::new::<Vec<String>>())
+            .with_output_arg(Self::OUT_ARG_OFFSETS, OutputArgMeta::new::<Vec<TextSpan>>())
+    }
+}
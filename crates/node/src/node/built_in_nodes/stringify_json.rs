@@ -0,0 +1,53 @@
+use crate::*;
+use eyre::ContextCompat;
+use std::collections::BTreeMap;
+
+/// Serialize [`serde_json::Value`] into text, compact unless `pretty` is set in memory.
+pub struct NodeStringifyJson;
+
+impl NodeStringifyJson {
+    pub const INPUT_ARG_JSON: &str = "json";
+    pub const OUT_ARG_TEXT: &str = "text";
+    pub const MEMORY_PRETTY: &str = "pretty";
+}
+
+impl NodeTrait for NodeStringifyJson {
+    fn run<'a>(
+        &'a self,
+        instance: &'a NodeInstance,
+        _ctx: &'a RunContext,
+        input: &'a InstanceRefArgs,
+    ) -> RunResult<'a> {
+        Box::pin(async {
+            let json = input
+                .get(Self::INPUT_ARG_JSON)
+                .context("Stringify JSON node: missing input argument")?
+                .downcast::<serde_json::Value>()?;
+
+            let pretty = instance
+                .get_memory_parsed::<bool>(Self::MEMORY_PRETTY)?
+                .unwrap_or(false);
+            let text = if pretty {
+                serde_json::to_string_pretty(json)?
+            } else {
+                serde_json::to_string(json)?
+            };
+
+            Ok(BTreeMap::from([(
+                Self::OUT_ARG_TEXT.to_string(),
+                Value::new(text),
+            )]))
+        })
+    }
+}
+
+impl NodeMetaTrait for NodeStringifyJson {
+    fn get_meta(&self) -> NodeMeta {
+        NodeMeta::new("stringify_json", "0.1.0")
+            .with_input_arg(
+                Self::INPUT_ARG_JSON,
+                InputArgMeta::new::<serde_json::Value>(),
+            )
+            .with_output_arg(Self::OUT_ARG_TEXT, OutputArgMeta::new::<String>())
+    }
+}
@@ -0,0 +1,185 @@
+use crate::*;
+use eyre::ContextCompat;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Render text template stored in memory.
+///
+/// Every `{{name}}` placeholder of the template becomes a `String` input argument of the
+/// instance, so each variable can be connected to another node.
+pub struct NodeTemplate;
+
+impl NodeTemplate {
+    pub const OUT_ARG_TEXT: &str = "text";
+    pub const MEMORY_TEMPLATE: &str = "template";
+
+    /// Names of all placeholders used in the template.
+    pub fn placeholders(template: &str) -> BTreeSet<String> {
+        let mut names = BTreeSet::new();
+        Self::visit(template, |segment| {
+            if let TemplateSegment::Placeholder(name) = segment {
+                names.insert(name.to_string());
+            }
+        });
+
+        names
+    }
+
+    /// Substitute placeholders with the provided values.
+    pub fn render(template: &str, values: &BTreeMap<&str, &str>) -> eyre::Result<String> {
+        let mut result = String::with_capacity(template.len());
+        let mut missing = None;
+
+        Self::visit(template, |segment| match segment {
+            TemplateSegment::Text(text) => result.push_str(text),
+            TemplateSegment::Placeholder(name) => match values.get(name) {
+                Some(value) => result.push_str(value),
+                None => missing = Some(name.to_string()),
+            },
+        });
+
+        match missing {
+            Some(name) => Err(eyre::eyre!("Template variable {name:?} is not provided")),
+            None => Ok(result),
+        }
+    }
+
+    fn visit<'t>(template: &'t str, mut visitor: impl FnMut(TemplateSegment<'t>)) {
+        let mut rest = template;
+
+        while let Some(start) = rest.find("{{") {
+            let Some(len) = rest[start + 2..].find("}}") else {
+                break;
+            };
+
+            let name = rest[start + 2..start + 2 + len].trim();
+            let end = start + 2 + len + 2;
+
+            if Self::is_valid_name(name) {
+                visitor(TemplateSegment::Text(&rest[..start]));
+                visitor(TemplateSegment::Placeholder(name));
+            } else {
+                // not a placeholder, keep braces as is
+                visitor(TemplateSegment::Text(&rest[..end]));
+            }
+
+            rest = &rest[end..];
+        }
+
+        visitor(TemplateSegment::Text(rest));
+    }
+
+    fn is_valid_name(name: &str) -> bool {
+        !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    }
+}
+
+enum TemplateSegment<'t> {
+    Text(&'t str),
+    Placeholder(&'t str),
+}
+
+impl NodeTrait for NodeTemplate {
+    fn run<'a>(
+        &'a self,
+        instance: &'a NodeInstance,
+        _ctx: &'a RunContext,
+        input: &'a InstanceRefArgs,
+    ) -> RunResult<'a> {
+        Box::pin(async {
+            let template = instance
+                .get_memory::<String>(Self::MEMORY_TEMPLATE)?
+                .context("Template node: template is not set")?;
+
+            let values = input
+                .iter()
+                .map(|(name, value)| Ok((*name, value.downcast::<String>()?.as_str())))
+                .collect::<eyre::Result<BTreeMap<_, _>>>()?;
+
+            Ok(BTreeMap::from([(
+                Self::OUT_ARG_TEXT.to_string(),
+                Value::new(Self::render(template, &values)?),
+            )]))
+        })
+    }
+
+    fn instance_ports(&self, instance: &NodeInstance, ports: &mut NodePorts) -> eyre::Result<()> {
+        let Some(template) = instance.get_memory::<String>(Self::MEMORY_TEMPLATE)? else {
+            return Ok(());
+        };
+
+        for name in Self::placeholders(template) {
+            ports.input_args.insert(name, InputArgMeta::new::<String>());
+        }
+
+        Ok(())
+    }
+}
+
+impl NodeMetaTrait for NodeTemplate {
+    fn get_meta(&self) -> NodeMeta {
+        NodeMeta::new("template", "0.1.0")
+            .with_output_arg(Self::OUT_ARG_TEXT, OutputArgMeta::new::<String>())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn placeholders() {
+        let names = NodeTemplate::placeholders("{{a}} {{ b_1 }} {{a}} {{c-d}}{{ not valid }} {{}}");
+        assert_eq!(
+            names,
+            BTreeSet::from(["a".into(), "b_1".into(), "c-d".into()])
+        );
+
+        assert!(NodeTemplate::placeholders("no placeholders {{ unclosed").is_empty());
+    }
+
+    #[test]
+    fn render_values() {
+        let values = BTreeMap::from([("name", "world"), ("count", "3")]);
+
+        assert_eq!(
+            NodeTemplate::render("Hello {{name}}, {{ count }} times {{name}}", &values).unwrap(),
+            "Hello world, 3 times world"
+        );
+        assert_eq!(NodeTemplate::render("", &values).unwrap(), "");
+    }
+
+    #[test]
+    fn render_keeps_braces_which_are_not_placeholders() {
+        let values = BTreeMap::from([("name", "{{count}}")]);
+
+        // JSON, invalid names and unclosed braces are kept as is
+        for text in [
+            r#"{"a": {"b": 1}}"#,
+            "{{ not valid }} and {{}}",
+            "{{name",
+            "{ {name} }",
+        ] {
+            assert_eq!(NodeTemplate::render(text, &values).unwrap(), text);
+        }
+
+        // values are inserted verbatim, placeholders in them are not rendered
+        assert_eq!(
+            NodeTemplate::render("[{{name}}]", &values).unwrap(),
+            "[{{count}}]"
+        );
+    }
+
+    #[test]
+    fn render_missing_value() {
+        let values = BTreeMap::from([("name", "world")]);
+
+        let err = NodeTemplate::render("{{name}} {{missing}}", &values).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            r#"Template variable "missing" is not provided"#
+        );
+    }
+}
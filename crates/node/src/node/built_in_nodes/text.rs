@@ -13,7 +13,7 @@ impl NodeTrait for NodeText {
     fn run<'a>(
         &'a self,
         instance: &'a NodeInstance,
-        _state: &'a Task,
+        _ctx: &'a RunContext,
         _input: &'a InstanceRefArgs,
     ) -> RunResult<'a> {
         Box::pin(async {
@@ -0,0 +1,52 @@
+use crate::*;
+use eyre::ContextCompat;
+use std::collections::BTreeMap;
+
+/// Drop the oldest messages of the conversation so it fits into the token budget stored in
+/// [`NodeTrimConversation::MEMORY_MAX_TOKENS`]. System messages are always kept.
+pub struct NodeTrimConversation;
+
+impl NodeTrimConversation {
+    pub const INPUT_ARG_CONVERSATION: &str = "conversation";
+    pub const OUT_ARG_CONVERSATION: &str = "conversation";
+    pub const MEMORY_MAX_TOKENS: &str = "max_tokens";
+}
+
+impl NodeTrait for NodeTrimConversation {
+    fn run<'a>(
+        &'a self,
+        instance: &'a NodeInstance,
+        _ctx: &'a RunContext,
+        input: &'a InstanceRefArgs,
+    ) -> RunResult<'a> {
+        Box::pin(async {
+            let conversation = input
+                .get(Self::INPUT_ARG_CONVERSATION)
+                .context("Trim conversation node: missing input argument")?
+                .downcast::<Conversation>()?;
+
+            let max_tokens = instance
+                .get_memory_parsed::<usize>(Self::MEMORY_MAX_TOKENS)?
+                .context("Trim conversation node: token budget is not set")?;
+
+            Ok(BTreeMap::from([(
+                Self::OUT_ARG_CONVERSATION.to_string(),
+                Value::new(conversation.trim_to_budget(max_tokens)),
+            )]))
+        })
+    }
+}
+
+impl NodeMetaTrait for NodeTrimConversation {
+    fn get_meta(&self) -> NodeMeta {
+        NodeMeta::new("trim_conversation", "0.1.0")
+            .with_input_arg(
+                Self::INPUT_ARG_CONVERSATION,
+                InputArgMeta::new::<Conversation>(),
+            )
+            .with_output_arg(
+                Self::OUT_ARG_CONVERSATION,
+                OutputArgMeta::new::<Conversation>(),
+            )
+    }
+}
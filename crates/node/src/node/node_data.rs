@@ -13,9 +13,17 @@ pub trait NodeTrait: Send + Sync + 'static {
     fn run<'a>(
         &'a self,
         instance: &'a NodeInstance,
-        state: &'a Task,
+        ctx: &'a RunContext,
         input: &'a InstanceRefArgs,
     ) -> RunResult<'a>;
+
+    /// Adjust ports of the instance based on its configuration (e.g. memory).
+    ///
+    /// `ports` initially contain arguments declared in [`NodeMeta`], nodes with configurable
+    /// arity can add new arguments, override or remove the declared ones.
+    fn instance_ports(&self, _instance: &NodeInstance, _ports: &mut NodePorts) -> eyre::Result<()> {
+        Ok(())
+    }
 }
 
 pub struct Node {
@@ -35,7 +43,10 @@ pub struct Node {
     derive_more::Deref,
     derive_more::Into,
     derive_more::From,
+    serde::Serialize,
+    serde::Deserialize,
 )]
+#[serde(transparent)]
 pub struct NodeId(pub String);
 
 impl From<&str> for NodeId {
@@ -44,6 +55,7 @@ impl From<&str> for NodeId {
     }
 }
 
+#[derive(Clone, Debug)]
 pub struct NodeMeta {
     pub id: NodeId,
     pub version: String,
@@ -72,6 +84,31 @@ impl NodeMeta {
     }
 }
 
+/// Effective input and output arguments of a node instance.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct NodePorts {
+    pub input_args: BTreeMap<String, InputArgMeta>,
+    pub output_args: BTreeMap<String, OutputArgMeta>,
+}
+
+impl NodePorts {
+    #[tracing::instrument(skip(self))]
+    pub fn get_input_arg(&self, key: &str) -> eyre::Result<InputArgMeta> {
+        self.input_args
+            .get(key)
+            .context("Input argument not found")
+            .cloned()
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub fn get_out_arg(&self, key: &str) -> eyre::Result<OutputArgMeta> {
+        self.output_args
+            .get(key)
+            .context("Output argument not found")
+            .cloned()
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct OutputArgMeta {
     pub value_type: ValueType,
@@ -135,10 +172,10 @@ impl Node {
     pub async fn run<'a>(
         &self,
         instance: &NodeInstance,
-        task: &Task,
+        ctx: &RunContext<'_>,
         input: &InstanceRefArgs<'a>,
     ) -> eyre::Result<InstanceArgs> {
-        self.inner.run(instance, task, input).await
+        self.inner.run(instance, ctx, input).await
     }
 
     pub fn id(&self) -> &NodeId {
@@ -171,6 +208,18 @@ impl Node {
         &self.meta.output_args
     }
 
+    /// Ports of the specific instance: declared arguments adjusted by the instance configuration.
+    #[tracing::instrument(skip_all, fields(node_id = ?self.meta.id, instance_id = %instance.instance_id))]
+    pub fn instance_ports(&self, instance: &NodeInstance) -> eyre::Result<NodePorts> {
+        let mut ports = NodePorts {
+            input_args: self.meta.input_args.clone(),
+            output_args: self.meta.output_args.clone(),
+        };
+        self.inner.instance_ports(instance, &mut ports)?;
+
+        Ok(ports)
+    }
+
     /// If node does not have any input arguments, it is a root node.
     pub fn is_root(&self) -> bool {
         self.meta.input_args.is_empty()
@@ -1,5 +1,6 @@
 use crate::*;
 use eyre::Context;
+use serde::de::DeserializeOwned;
 use std::collections::BTreeMap;
 
 #[derive(
@@ -15,7 +16,10 @@ use std::collections::BTreeMap;
     derive_more::Deref,
     derive_more::Into,
     derive_more::From,
+    serde::Serialize,
+    serde::Deserialize,
 )]
+#[serde(transparent)]
 pub struct NodeInstanceId(pub u32);
 
 #[derive(Debug)]
@@ -43,6 +47,11 @@ impl NodeInstanceIdProvider {
         self.next_id += 1;
         NodeInstanceId(id)
     }
+
+    /// Make sure the id will not be returned by [`Self::next_id`].
+    pub fn reserve(&mut self, id: NodeInstanceId) {
+        self.next_id = self.next_id.max(id.0 + 1);
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -51,12 +60,14 @@ pub struct NodeConnection {
     pub arg_name: String,
 }
 
+#[derive(Clone)]
 pub struct NodeInstance {
     pub node_id: NodeId,
     pub instance_id: NodeInstanceId,
     pub memory: BTreeMap<String, Value>,
     pub input_connections: BTreeMap<String, NodeConnection>,
-    pub output_connections: BTreeMap<String, NodeConnection>,
+    /// Inputs connected to every output, an output can feed many inputs.
+    pub output_connections: BTreeMap<String, Vec<NodeConnection>>,
 }
 
 impl NodeInstance {
@@ -85,6 +96,10 @@ impl NodeInstance {
         Ok(())
     }
 
+    pub fn set_memory_value(&mut self, name: String, val: Value) {
+        self.memory.insert(name, val);
+    }
+
     #[tracing::instrument(
         skip_all,
         fields(instance_id = ?self.instance_id, name, type_name = std::any::type_name::<T>())
@@ -96,4 +111,21 @@ impl NodeInstance {
             .transpose()
             .context("Failed to downcast value to the type")
     }
+
+    /// Same as [`Self::get_memory`], but also accepts values stored as JSON (e.g. loaded from a
+    /// graph file).
+    #[tracing::instrument(
+        skip_all,
+        fields(instance_id = ?self.instance_id, name, type_name = std::any::type_name::<T>())
+    )]
+    pub fn get_memory_parsed<T: DeserializeOwned + Clone + 'static>(
+        &self,
+        name: &str,
+    ) -> eyre::Result<Option<T>> {
+        self.memory
+            .get(name)
+            .map(Value::parse)
+            .transpose()
+            .with_context(|| format!("Failed to read memory {name:?}"))
+    }
 }
@@ -0,0 +1,118 @@
+use crate::*;
+use serde::{Serialize, Serializer};
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+/// Tokens spent by LLM calls.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, serde::Deserialize)]
+pub struct TokenUsage {
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    /// Cost in USD, zero if the price of the model is unknown.
+    pub cost: f64,
+    /// Token counts are estimated locally, since provider did not report them.
+    #[serde(default)]
+    pub estimated: bool,
+}
+
+impl TokenUsage {
+    pub fn new(prompt_tokens: u64, completion_tokens: u64) -> Self {
+        Self {
+            prompt_tokens,
+            completion_tokens,
+            ..Default::default()
+        }
+    }
+
+    pub fn total_tokens(&self) -> u64 {
+        self.prompt_tokens + self.completion_tokens
+    }
+}
+
+impl std::ops::Add for TokenUsage {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Self {
+            prompt_tokens: self.prompt_tokens + rhs.prompt_tokens,
+            completion_tokens: self.completion_tokens + rhs.completion_tokens,
+            cost: self.cost + rhs.cost,
+            estimated: self.estimated || rhs.estimated,
+        }
+    }
+}
+
+impl std::ops::AddAssign for TokenUsage {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl std::iter::Sum for TokenUsage {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::default(), |acc, usage| acc + usage)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, derive_more::Display)]
+#[serde(rename_all = "snake_case")]
+pub enum InstanceStatus {
+    #[display("succeeded")]
+    Succeeded,
+    #[display("failed")]
+    Failed,
+    /// Instance was not executed, e.g. because one of its dependencies failed.
+    #[display("skipped")]
+    Skipped,
+    /// Instance was stopped while running, e.g. because the run budget was exceeded.
+    #[display("cancelled")]
+    Cancelled,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct InstanceReport {
+    pub node_id: NodeId,
+    pub status: InstanceStatus,
+    #[serde(serialize_with = "serialize_secs")]
+    pub duration: Duration,
+    pub usage: TokenUsage,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl InstanceReport {
+    pub fn skipped(node_id: NodeId) -> Self {
+        Self {
+            node_id,
+            status: InstanceStatus::Skipped,
+            duration: Duration::ZERO,
+            usage: TokenUsage::default(),
+            error: None,
+        }
+    }
+}
+
+/// Outcome of a [`Task::run`].
+#[derive(Clone, Debug, Serialize)]
+pub struct RunReport {
+    pub instances: BTreeMap<NodeInstanceId, InstanceReport>,
+    #[serde(serialize_with = "serialize_secs")]
+    pub duration: Duration,
+    /// Total usage of all instances.
+    pub usage: TokenUsage,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl RunReport {
+    pub fn is_success(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+pub(crate) fn serialize_secs<S: Serializer>(
+    duration: &Duration,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_f64(duration.as_secs_f64())
+}
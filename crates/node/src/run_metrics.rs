@@ -0,0 +1,74 @@
+use crate::*;
+use std::time::Duration;
+
+/// Executions of instances by `node_id`.
+pub const METRIC_NODE_EXECUTIONS: &str = "agent_node_executions_total";
+/// Failed executions of instances by `node_id`.
+pub const METRIC_NODE_ERRORS: &str = "agent_node_errors_total";
+/// Duration of instance executions by `node_id`, including failed ones.
+pub const METRIC_NODE_DURATION: &str = "agent_node_duration_seconds";
+/// Retried calls of instances by `node_id`, see [`RunContext::record_retries`].
+pub const METRIC_NODE_RETRIES: &str = "agent_node_retries_total";
+/// Tokens spent by instances by `node_id` and `kind`, `prompt` or `completion`.
+pub const METRIC_NODE_TOKENS: &str = "agent_node_tokens_total";
+/// Finished runs by `status`, `succeeded` or `failed`.
+pub const METRIC_RUNS: &str = "agent_runs_total";
+pub const METRIC_RUN_DURATION: &str = "agent_run_duration_seconds";
+
+/// Register descriptions of the metrics recorded by the executor, call after installing the
+/// recorder.
+pub fn describe_metrics() {
+    metrics::describe_counter!(METRIC_NODE_EXECUTIONS, "Executions of node instances");
+    metrics::describe_counter!(METRIC_NODE_ERRORS, "Failed executions of node instances");
+    metrics::describe_histogram!(
+        METRIC_NODE_DURATION,
+        metrics::Unit::Seconds,
+        "Duration of node instance executions"
+    );
+    metrics::describe_counter!(METRIC_NODE_RETRIES, "Retried calls of node instances");
+    metrics::describe_counter!(METRIC_NODE_TOKENS, "Tokens spent by node instances");
+    metrics::describe_counter!(METRIC_RUNS, "Finished task runs");
+    metrics::describe_histogram!(
+        METRIC_RUN_DURATION,
+        metrics::Unit::Seconds,
+        "Duration of task runs"
+    );
+}
+
+/// Record the finished execution of the instance, dropped (cancelled) executions are not
+/// recorded.
+pub(crate) fn record_instance_metrics(
+    instance: &NodeInstance,
+    duration: Duration,
+    usage: TokenUsage,
+    retries: u32,
+    is_success: bool,
+) {
+    let node_id = instance.node_id.to_string();
+
+    metrics::counter!(METRIC_NODE_EXECUTIONS, "node_id" => node_id.clone()).increment(1);
+    if !is_success {
+        metrics::counter!(METRIC_NODE_ERRORS, "node_id" => node_id.clone()).increment(1);
+    }
+    metrics::histogram!(METRIC_NODE_DURATION, "node_id" => node_id.clone())
+        .record(duration.as_secs_f64());
+    if retries > 0 {
+        metrics::counter!(METRIC_NODE_RETRIES, "node_id" => node_id.clone())
+            .increment(retries.into());
+    }
+    if usage.prompt_tokens > 0 {
+        metrics::counter!(METRIC_NODE_TOKENS, "node_id" => node_id.clone(), "kind" => "prompt")
+            .increment(usage.prompt_tokens);
+    }
+    if usage.completion_tokens > 0 {
+        metrics::counter!(METRIC_NODE_TOKENS, "node_id" => node_id, "kind" => "completion")
+            .increment(usage.completion_tokens);
+    }
+}
+
+pub(crate) fn record_run_metrics(duration: Duration, is_success: bool) {
+    let status = if is_success { "succeeded" } else { "failed" };
+
+    metrics::counter!(METRIC_RUNS, "status" => status).increment(1);
+    metrics::histogram!(METRIC_RUN_DURATION).record(duration.as_secs_f64());
+}
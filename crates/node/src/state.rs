@@ -1,12 +1,24 @@
 use crate::*;
 use eyre::ContextCompat;
 use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
 
 /// Collection of nodes and connections between them.
 pub struct Task {
     nodes: HashMap<NodeId, Node>,
     instances: HashMap<NodeInstanceId, NodeInstance>,
     instance_id_provider: NodeInstanceIdProvider,
+    last_report: Mutex<Option<RunReport>>,
+    events: tokio::sync::broadcast::Sender<RunEvent>,
+    next_run_id: AtomicU64,
+    event_preview: Option<usize>,
+}
+
+impl Default for Task {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Task {
@@ -15,6 +27,10 @@ impl Task {
             nodes: HashMap::new(),
             instance_id_provider: NodeInstanceIdProvider::default(),
             instances: HashMap::new(),
+            last_report: Mutex::new(None),
+            events: tokio::sync::broadcast::Sender::new(RUN_EVENTS_CAPACITY),
+            next_run_id: AtomicU64::new(1),
+            event_preview: None,
         }
     }
 
@@ -37,14 +53,33 @@ impl Task {
 
     #[tracing::instrument(skip(self))]
     pub fn instantiate(&mut self, node_id: &NodeId) -> eyre::Result<NodeInstanceId> {
+        let node = self.nodes.get(node_id).context("Node not found")?;
         let instance_id = self.instance_id_provider.next_id();
+        let instance = NodeInstance::new(node, instance_id);
+
+        self.instances.insert(instance_id, instance);
+
+        Ok(instance_id)
+    }
+
+    /// Create instance with the specific id, used to restore saved graphs.
+    #[tracing::instrument(skip(self))]
+    pub fn instantiate_with_id(
+        &mut self,
+        node_id: &NodeId,
+        instance_id: NodeInstanceId,
+    ) -> eyre::Result<()> {
+        if self.instances.contains_key(&instance_id) {
+            return Err(eyre::eyre!("Instance id already exists"));
+        }
 
         let node = self.nodes.get(node_id).context("Node not found")?;
         let instance = NodeInstance::new(node, instance_id);
 
+        self.instance_id_provider.reserve(instance_id);
         self.instances.insert(instance_id, instance);
 
-        Ok(instance_id)
+        Ok(())
     }
 
     #[tracing::instrument(skip(self))]
@@ -70,9 +105,87 @@ impl Task {
         let instance = self.get_instance_mut(id)?;
         instance.set_memory(name.into(), val)?;
 
+        // ports may depend on memory
+        self.drop_stale_connections(id)?;
+
+        Ok(())
+    }
+
+    /// Same as [`Self::set_instance_memory`], but for already wrapped values.
+    #[tracing::instrument(skip(self, val))]
+    pub fn set_instance_memory_value(
+        &mut self,
+        id: NodeInstanceId,
+        name: &str,
+        val: Value,
+    ) -> eyre::Result<()> {
+        let instance = self.get_instance_mut(id)?;
+        instance.set_memory_value(name.to_string(), val);
+
+        self.drop_stale_connections(id)?;
+
         Ok(())
     }
 
+    /// Ports of the instance, taking instance configuration into account.
+    #[tracing::instrument(skip(self))]
+    pub fn get_instance_ports(&self, id: NodeInstanceId) -> eyre::Result<NodePorts> {
+        let instance = self.get_instance(id)?;
+        let node = self.get_node(&instance.node_id)?;
+
+        node.instance_ports(instance)
+    }
+
+    /// Remove connections of the instance that no longer match its ports.
+    #[tracing::instrument(skip(self))]
+    fn drop_stale_connections(&mut self, id: NodeInstanceId) -> eyre::Result<()> {
+        let instance = self.get_instance(id)?;
+
+        let inputs = instance
+            .input_connections
+            .iter()
+            .map(|(arg, conn)| (conn.instance, conn.arg_name.clone(), id, arg.clone()));
+        let outputs = instance.output_connections.iter().flat_map(|(arg, conns)| {
+            conns
+                .iter()
+                .map(move |conn| (id, arg.clone(), conn.instance, conn.arg_name.clone()))
+        });
+
+        let mut stale = Vec::new();
+        for (output_id, output_arg, input_id, input_arg) in inputs.chain(outputs) {
+            let is_valid = self
+                .match_types(output_id, &output_arg, input_id, &input_arg)
+                .unwrap_or(false);
+
+            if !is_valid {
+                stale.push((output_id, output_arg, input_id, input_arg));
+            }
+        }
+
+        for (output_id, output_arg, input_id, input_arg) in stale {
+            tracing::warn!(
+                "Port changed, removing connection {output_id}[{output_arg:?}] -> {input_id}[{input_arg:?}]"
+            );
+            self.disconnect(input_id, &input_arg)?;
+        }
+
+        Ok(())
+    }
+
+    /// All registered nodes, sorted by id.
+    pub fn nodes(&self) -> Vec<&Node> {
+        let mut nodes = self.nodes.values().collect::<Vec<_>>();
+        nodes.sort_by(|a, b| a.id().cmp(b.id()));
+        nodes
+    }
+
+    /// All instances, sorted by id.
+    pub fn instances(&self) -> Vec<&NodeInstance> {
+        let mut instances = self.instances.values().collect::<Vec<_>>();
+        instances.sort_by_key(|instance| instance.instance_id);
+        instances
+    }
+
     #[tracing::instrument(skip(self))]
     pub fn get_node(&self, id: &NodeId) -> eyre::Result<&Node> {
         self.nodes.get(id).context("Node not found")
@@ -90,17 +203,21 @@ impl Task {
             return Err(eyre::eyre!("Incompatible types"));
         }
 
-        self.get_instance_mut(output_id)?.output_connections.insert(
-            output_arg.to_string(),
-            NodeConnection {
-                instance: input_id.clone(),
+        // input can have only one source
+        self.disconnect(input_id, input_arg)?;
+
+        self.get_instance_mut(output_id)?
+            .output_connections
+            .entry(output_arg.to_string())
+            .or_default()
+            .push(NodeConnection {
+                instance: input_id,
                 arg_name: input_arg.to_string(),
-            },
-        );
+            });
         self.get_instance_mut(input_id)?.input_connections.insert(
             input_arg.to_string(),
             NodeConnection {
-                instance: output_id.clone(),
+                instance: output_id,
                 arg_name: output_arg.to_string(),
             },
         );
@@ -108,6 +225,31 @@ impl Task {
         Ok(())
     }
 
+    /// Remove connection of the input argument, if any.
+    #[tracing::instrument(skip(self))]
+    pub fn disconnect(&mut self, input_id: NodeInstanceId, input_arg: &str) -> eyre::Result<()> {
+        let Some(connection) = self
+            .get_instance_mut(input_id)?
+            .input_connections
+            .remove(input_arg)
+        else {
+            return Ok(());
+        };
+
+        // the output may feed other inputs as well, only this connection is removed
+        let output_connections = &mut self
+            .get_instance_mut(connection.instance)?
+            .output_connections;
+        if let Some(connections) = output_connections.get_mut(&connection.arg_name) {
+            connections.retain(|conn| !(conn.instance == input_id && conn.arg_name == input_arg));
+            if connections.is_empty() {
+                output_connections.remove(&connection.arg_name);
+            }
+        }
+
+        Ok(())
+    }
+
     #[tracing::instrument(skip(self))]
     pub fn can_connect_nodes(
         &self,
@@ -131,14 +273,12 @@ impl Task {
         input_id: NodeInstanceId,
         input_arg: &str,
     ) -> eyre::Result<bool> {
-        let output_instance = self.get_instance(output_id)?;
-        let output_node = self.get_node(&output_instance.node_id)?;
-
-        let input_instance = self.get_instance(input_id)?;
-        let input_node = self.get_node(&input_instance.node_id)?;
-
-        let out_ty = output_node.get_out_arg(&output_arg)?;
-        let in_ty = input_node.get_input_arg(&input_arg)?;
+        let out_ty = self
+            .get_instance_ports(output_id)?
+            .get_out_arg(output_arg)?;
+        let in_ty = self
+            .get_instance_ports(input_id)?
+            .get_input_arg(input_arg)?;
 
         Ok(in_ty.value_type == out_ty.value_type)
     }
@@ -180,18 +320,20 @@ impl Task {
         Ok(visited)
     }
 
+    /// Inputs connected to the output argument.
     #[tracing::instrument(skip(self))]
-    pub fn get_node_out_connection<'a>(
+    pub fn get_node_out_connections<'a>(
         &'a self,
         instance_id: NodeInstanceId,
         output_arg: &str,
-    ) -> eyre::Result<Option<&'a NodeConnection>> {
+    ) -> eyre::Result<&'a [NodeConnection]> {
         let instance = self.get_instance(instance_id)?;
-        let Some(output_connected_to) = instance.output_connections.get(output_arg) else {
-            return Ok(None);
-        };
 
-        Ok(Some(output_connected_to))
+        Ok(instance
+            .output_connections
+            .get(output_arg)
+            .map(Vec::as_slice)
+            .unwrap_or_default())
     }
 
     #[tracing::instrument(skip(self))]
@@ -216,18 +358,13 @@ impl Task {
         input_id: NodeInstanceId,
         input_arg: &str,
     ) -> eyre::Result<bool> {
-        let Some(output_connected_to) = self.get_node_out_connection(output_id, output_arg)? else {
-            return Ok(false);
-        };
-
-        let Some(input_connected_to) = self.get_node_in_connection(input_id, input_arg)? else {
-            return Ok(false);
-        };
-
-        let a_to_b =
-            output_connected_to.instance == input_id && output_connected_to.arg_name == input_arg;
-        let b_to_a =
-            input_connected_to.instance == output_id && input_connected_to.arg_name == output_arg;
+        let a_to_b = self
+            .get_node_out_connections(output_id, output_arg)?
+            .iter()
+            .any(|conn| conn.instance == input_id && conn.arg_name == input_arg);
+        let b_to_a = self
+            .get_node_in_connection(input_id, input_arg)?
+            .is_some_and(|conn| conn.instance == output_id && conn.arg_name == output_arg);
 
         match (a_to_b, b_to_a) {
             (true, true) => Ok(true),
@@ -235,72 +372,106 @@ impl Task {
             // If one node is connected to the other, but not the other way around,
             // we have a corrupted connection
             _ => Err(eyre::eyre!(
-                "Corrupted connection: {}[{:?}] -> {}[{:?}] and {}[{:?}] -> {}[{:?}]",
+                "Corrupted connection: {}[{:?}] -> {}[{:?}] is recorded only on the {} side",
                 output_id,
                 output_arg,
-                output_connected_to.instance,
-                output_connected_to.arg_name,
                 input_id,
                 input_arg,
-                input_connected_to.instance,
-                input_connected_to.arg_name
+                if a_to_b { "output" } else { "input" }
             )),
         }
     }
 
-    #[tracing::instrument(skip(self))]
-    pub async fn run(&self) -> eyre::Result<()> {
-        self.is_all_nodes_connected()?;
-
-        let instances_to_update = self.get_leaf_nodes();
-        let mut results = HashMap::<NodeInstanceId, InstanceArgs>::new();
-
-        for instance in instances_to_update {
-            self.update_node_recursive(instance, &mut results).await?;
-        }
-
-        Ok(())
+    /// Run all instances within the budget, independent instances run concurrently.
+    ///
+    /// The report is also available via [`Self::last_report`], including reports of failed runs.
+    pub async fn run(&self, budget: &RunBudget) -> eyre::Result<RunReport> {
+        self.run_with(RunContext::new(self, budget.clone())).await
     }
 
-    #[tracing::instrument(skip_all, fields(instance_id = %instance.instance_id, results_len = %results.len()))]
-    pub async fn update_node_recursive(
-        &self,
-        instance: &NodeInstance,
-        results: &mut HashMap<NodeInstanceId, InstanceArgs>,
-    ) -> eyre::Result<()> {
-        // ensure all nodes this instance depends on are updated
-        for connection in instance.input_connections.values() {
-            if results.contains_key(&connection.instance) {
-                continue;
-            };
+    /// Run in debug mode, pausing before instances matching the breakpoints of the debugger.
+    ///
+    /// Instances run one at a time in the order of their ids. The pauses are received from the
+    /// returned session, which must be polled together with the run, e.g. with
+    /// [`tokio::join!`], and ends once the run finished.
+    pub fn run_debug<'a>(
+        &'a self,
+        budget: &RunBudget,
+        debugger: Debugger,
+    ) -> (
+        DebugSession,
+        impl std::future::Future<Output = eyre::Result<RunReport>> + 'a,
+    ) {
+        let (debugger, session) = DebugState::new(debugger);
+        let ctx = RunContext::new(self, budget.clone()).with_debugger(debugger);
+
+        (session, self.run_with(ctx))
+    }
 
-            let instance = self.get_instance(connection.instance)?;
-            Box::pin(self.update_node_recursive(instance, results)).await?;
+    #[tracing::instrument(
+        name = "run",
+        skip_all,
+        fields(budget = ?ctx.budget(), run_id, prompt_tokens, completion_tokens, cost, otel.status_code)
+    )]
+    async fn run_with(&self, ctx: RunContext<'_>) -> eyre::Result<RunReport> {
+        self.validate()?;
+
+        tracing::Span::current().record("run_id", ctx.run_id());
+        ctx.emit(RunEventKind::RunStarted {
+            instances: self.instances.len(),
+        });
+        let result = execute(self, &ctx).await;
+
+        let report = ctx.into_report(result.as_ref().err());
+
+        let span = tracing::Span::current();
+        span.record("prompt_tokens", report.usage.prompt_tokens);
+        span.record("completion_tokens", report.usage.completion_tokens);
+        span.record("cost", report.usage.cost);
+        if result.is_err() {
+            span.record("otel.status_code", "ERROR");
         }
+        record_run_metrics(report.duration, result.is_ok());
+        tracing::info!(
+            duration = ?report.duration,
+            total_tokens = report.usage.total_tokens(),
+            "Run finished"
+        );
 
-        // collect args
-        let mut args = InstanceRefArgs::default();
+        *self.last_report.lock().expect("report lock poisoned") = Some(report.clone());
 
-        for (arg_name, connection) in &instance.input_connections {
-            let input_instance_result = results
-                .get(&connection.instance)
-                .context("Result not found")?;
+        result.map(|_| report)
+    }
 
-            let arg_value = input_instance_result
-                .get(arg_name)
-                .context("Argument not found in the instance")?;
+    /// Receive events of all following runs.
+    pub fn subscribe(&self) -> tokio::sync::broadcast::Receiver<RunEvent> {
+        self.events.subscribe()
+    }
 
-            args.insert(arg_name, arg_value);
-        }
+    /// Include JSON previews of output values up to `max_chars` long in
+    /// [`RunEventKind::NodeOutput`] events, disabled by default.
+    pub fn set_event_preview(&mut self, max_chars: Option<usize>) {
+        self.event_preview = max_chars;
+    }
 
-        // update node
-        let node = self.get_node(&instance.node_id)?;
-        let update_result = node.run(instance, self, &args).await?;
+    pub(crate) fn events(&self) -> &tokio::sync::broadcast::Sender<RunEvent> {
+        &self.events
+    }
 
-        // cache result for nodes depending on the current node instance
-        results.insert(instance.instance_id, update_result);
+    pub(crate) fn event_preview(&self) -> Option<usize> {
+        self.event_preview
+    }
 
-        Ok(())
+    pub(crate) fn next_run_id(&self) -> u64 {
+        self.next_run_id.fetch_add(1, Ordering::Relaxed)
+    }
+
+    /// Report of the latest finished run.
+    pub fn last_report(&self) -> Option<RunReport> {
+        self.last_report
+            .lock()
+            .expect("report lock poisoned")
+            .clone()
     }
 
     pub fn is_all_nodes_connected(&self) -> eyre::Result<bool> {
@@ -313,19 +484,54 @@ impl Task {
         Ok(true)
     }
 
-    pub fn is_node_connected(&self, instance: &NodeInstance) -> eyre::Result<bool> {
-        let node = self.get_node(&instance.node_id)?;
-        if node.is_root() {
-            return Ok(true);
+    /// Check that every required input is connected and all connections match instance ports.
+    #[tracing::instrument(skip(self))]
+    pub fn validate(&self) -> eyre::Result<()> {
+        let mut errors = Vec::new();
+
+        for instance in self.instances() {
+            let id = instance.instance_id;
+            let ports = self.get_instance_ports(id)?;
+
+            for (arg_name, arg_ty) in &ports.input_args {
+                if !arg_ty.is_optional && !instance.input_connections.contains_key(arg_name) {
+                    errors.push(format!(
+                        "{id}[{arg_name:?}]: required input is not connected"
+                    ));
+                }
+            }
+
+            for (arg_name, connection) in &instance.input_connections {
+                let is_valid = self
+                    .match_types(connection.instance, &connection.arg_name, id, arg_name)
+                    .unwrap_or(false);
+
+                if !is_valid {
+                    errors.push(format!(
+                        "{}[{:?}] -> {id}[{arg_name:?}]: connection does not match ports",
+                        connection.instance, connection.arg_name
+                    ));
+                }
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(eyre::eyre!("Invalid task:\n{}", errors.join("\n")))
         }
+    }
+
+    pub fn is_node_connected(&self, instance: &NodeInstance) -> eyre::Result<bool> {
+        let ports = self.get_instance_ports(instance.instance_id)?;
 
-        for (arg_name, arg_ty) in node.input_args() {
+        for (arg_name, arg_ty) in ports.input_args {
             if arg_ty.is_optional {
                 // Optional arguments are not required to be connected
                 continue;
             }
 
-            if instance.input_connections.get(arg_name).is_none() {
+            if !instance.input_connections.contains_key(&arg_name) {
                 return Ok(false);
             };
         }
@@ -337,8 +543,8 @@ impl Task {
         let mut root_nodes = Vec::new();
 
         for instance in self.instances.values() {
-            let node = self.get_node(&instance.node_id)?;
-            if node.is_root() {
+            let ports = self.get_instance_ports(instance.instance_id)?;
+            if ports.input_args.is_empty() {
                 root_nodes.push(instance);
             }
         }
@@ -0,0 +1,450 @@
+use crate::*;
+use serde::{Deserialize, Serialize};
+
+/// Byte range of a chunk in the source text.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct TextSpan {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl TextSpan {
+    pub fn new(start: usize, end: usize) -> Self {
+        Self { start, end }
+    }
+
+    pub fn slice<'t>(&self, text: &'t str) -> &'t str {
+        &text[self.start..self.end]
+    }
+}
+
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, derive_more::Display,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum SplitStrategy {
+    /// Fixed amount of characters, chunks overlap by `chunk_overlap` characters.
+    #[display("characters")]
+    Characters,
+    /// Paragraphs separated by blank lines, merged up to the chunk size. Oversized paragraphs
+    /// are split by characters.
+    #[display("paragraphs")]
+    Paragraphs,
+    /// Sentences, merged up to the chunk size. Oversized sentences are split by characters.
+    #[display("sentences")]
+    Sentences,
+    /// Split by the first separator found, oversized pieces are split by the next ones.
+    #[default]
+    #[display("recursive")]
+    Recursive,
+    /// Same as [`Self::Recursive`], but sizes are estimated tokens instead of characters.
+    #[display("tokens")]
+    Tokens,
+}
+
+impl std::str::FromStr for SplitStrategy {
+    type Err = eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "characters" => Ok(Self::Characters),
+            "paragraphs" => Ok(Self::Paragraphs),
+            "sentences" => Ok(Self::Sentences),
+            "recursive" => Ok(Self::Recursive),
+            "tokens" => Ok(Self::Tokens),
+            _ => Err(eyre::eyre!("Unknown split strategy: {s:?}")),
+        }
+    }
+}
+
+/// Split text into chunks not larger than `chunk_size`, keeping their position in the source.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TextSplitter {
+    pub strategy: SplitStrategy,
+    /// Max size of the chunk in characters, or in tokens for [`SplitStrategy::Tokens`].
+    pub chunk_size: usize,
+    /// Size of the text repeated at the start of the next chunk, in the same units.
+    pub chunk_overlap: usize,
+    /// Separators used by recursive strategies, from the coarsest to the finest.
+    pub separators: Vec<String>,
+}
+
+impl Default for TextSplitter {
+    fn default() -> Self {
+        Self {
+            strategy: SplitStrategy::default(),
+            chunk_size: 1000,
+            chunk_overlap: 0,
+            separators: ["\n\n", "\n", ". ", " ", ""]
+                .into_iter()
+                .map(String::from)
+                .collect(),
+        }
+    }
+}
+
+impl TextSplitter {
+    pub fn new(strategy: SplitStrategy, chunk_size: usize) -> Self {
+        Self {
+            strategy,
+            chunk_size,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_overlap(mut self, chunk_overlap: usize) -> Self {
+        self.chunk_overlap = chunk_overlap;
+        self
+    }
+
+    pub fn with_separators(mut self, separators: Vec<String>) -> Self {
+        self.separators = separators;
+        self
+    }
+
+    /// Spans of the chunks, leading and trailing whitespace of every chunk is excluded.
+    pub fn split(&self, text: &str) -> eyre::Result<Vec<TextSpan>> {
+        if self.chunk_size == 0 {
+            return Err(eyre::eyre!("Chunk size must be positive"));
+        }
+        if self.chunk_overlap >= self.chunk_size {
+            return Err(eyre::eyre!(
+                "Chunk overlap {} must be smaller than the chunk size {}",
+                self.chunk_overlap,
+                self.chunk_size
+            ));
+        }
+
+        let spans = match self.strategy {
+            SplitStrategy::Characters => self.windows(text, TextSpan::new(0, text.len())),
+            SplitStrategy::Paragraphs => {
+                let pieces = Self::split_inclusive(text, TextSpan::new(0, text.len()), "\n\n");
+                self.merge(text, &self.fit(text, pieces))
+            }
+            SplitStrategy::Sentences => self.merge(text, &self.fit(text, Self::sentences(text))),
+            SplitStrategy::Recursive | SplitStrategy::Tokens => {
+                let mut pieces = Vec::new();
+                self.split_recursive(
+                    text,
+                    TextSpan::new(0, text.len()),
+                    &self.separators,
+                    &mut pieces,
+                );
+
+                self.merge(text, &pieces)
+            }
+        };
+
+        Ok(spans
+            .into_iter()
+            .filter_map(|span| Self::trim(text, span))
+            .collect())
+    }
+
+    /// Size of the text in the units of the strategy.
+    fn size(&self, text: &str) -> usize {
+        match self.strategy {
+            SplitStrategy::Tokens => estimate_tokens(text),
+            _ => text.chars().count(),
+        }
+    }
+
+    /// Max amount of characters fitting into the chunk size.
+    fn max_chars(&self, size: usize) -> usize {
+        match self.strategy {
+            SplitStrategy::Tokens => size * CHARS_PER_TOKEN,
+            _ => size,
+        }
+    }
+
+    /// Fixed size windows of characters overlapping by `chunk_overlap`.
+    fn windows(&self, text: &str, span: TextSpan) -> Vec<TextSpan> {
+        let chunk_chars = self.max_chars(self.chunk_size);
+        let step = chunk_chars - self.max_chars(self.chunk_overlap);
+
+        // byte offsets of all char boundaries within the span, including the end
+        let boundaries = span
+            .slice(text)
+            .char_indices()
+            .map(|(index, _)| span.start + index)
+            .chain([span.end])
+            .collect::<Vec<_>>();
+        let chars = boundaries.len() - 1;
+
+        let mut spans = Vec::new();
+        let mut start = 0;
+        while start < chars {
+            let end = (start + chunk_chars).min(chars);
+            spans.push(TextSpan::new(boundaries[start], boundaries[end]));
+
+            if end == chars {
+                break;
+            }
+            start += step;
+        }
+
+        spans
+    }
+
+    /// Split oversized pieces into windows so every piece fits into a chunk.
+    fn fit(&self, text: &str, pieces: Vec<TextSpan>) -> Vec<TextSpan> {
+        pieces
+            .into_iter()
+            .flat_map(|piece| {
+                if self.size(piece.slice(text)) <= self.chunk_size {
+                    vec![piece]
+                } else {
+                    Self {
+                        chunk_overlap: 0,
+                        ..self.clone()
+                    }
+                    .windows(text, piece)
+                }
+            })
+            .collect()
+    }
+
+    fn split_recursive(
+        &self,
+        text: &str,
+        span: TextSpan,
+        separators: &[String],
+        pieces: &mut Vec<TextSpan>,
+    ) {
+        if self.size(span.slice(text)) <= self.chunk_size {
+            pieces.push(span);
+            return;
+        }
+
+        // the empty separator, as well as running out of separators, means splitting by
+        // characters
+        let position = separators.iter().position(|separator| {
+            !separator.is_empty() && span.slice(text).contains(separator.as_str())
+        });
+        let Some(position) = position else {
+            pieces.extend(self.fit(text, vec![span]));
+            return;
+        };
+
+        let separator = &separators[position];
+        for piece in Self::split_inclusive(text, span, separator) {
+            self.split_recursive(text, piece, &separators[position + 1..], pieces);
+        }
+    }
+
+    /// Split the span after every separator, pieces cover the whole span.
+    fn split_inclusive(text: &str, span: TextSpan, separator: &str) -> Vec<TextSpan> {
+        let mut start = span.start;
+
+        span.slice(text)
+            .split_inclusive(separator)
+            .map(|piece| {
+                let piece_span = TextSpan::new(start, start + piece.len());
+                start = piece_span.end;
+                piece_span
+            })
+            .collect()
+    }
+
+    /// Sentences ending with `.`, `!` or `?` followed by whitespace, or with a blank line.
+    fn sentences(text: &str) -> Vec<TextSpan> {
+        let mut spans = Vec::new();
+        let mut start = 0;
+        let mut chars = text.char_indices().peekable();
+
+        while let Some((index, char)) = chars.next() {
+            let is_end = match char {
+                '.' | '!' | '?' => chars.peek().is_some_and(|(_, next)| next.is_whitespace()),
+                '\n' => chars.peek().is_some_and(|(_, next)| *next == '\n'),
+                _ => false,
+            };
+
+            if is_end {
+                // keep the following whitespace with the sentence
+                let mut end = index + char.len_utf8();
+                while let Some((next_index, next)) = chars.peek().copied() {
+                    if !next.is_whitespace() {
+                        break;
+                    }
+                    end = next_index + next.len_utf8();
+                    chars.next();
+                }
+
+                spans.push(TextSpan::new(start, end));
+                start = end;
+            }
+        }
+
+        if start < text.len() {
+            spans.push(TextSpan::new(start, text.len()));
+        }
+
+        spans
+    }
+
+    /// Merge consecutive pieces into chunks up to the chunk size, the next chunk starts with
+    /// the trailing pieces of the previous one fitting into the overlap.
+    fn merge(&self, text: &str, pieces: &[TextSpan]) -> Vec<TextSpan> {
+        let sizes = pieces
+            .iter()
+            .map(|piece| self.size(piece.slice(text)))
+            .collect::<Vec<_>>();
+
+        let mut chunks = Vec::new();
+        let mut start = 0;
+        while start < pieces.len() {
+            let mut end = start;
+            let mut size = 0;
+            while end < pieces.len() && (end == start || size + sizes[end] <= self.chunk_size) {
+                size += sizes[end];
+                end += 1;
+            }
+
+            chunks.push(TextSpan::new(pieces[start].start, pieces[end - 1].end));
+            if end == pieces.len() {
+                break;
+            }
+
+            // the overlap must leave room for the next piece
+            let max_overlap = self
+                .chunk_overlap
+                .min(self.chunk_size.saturating_sub(sizes[end]));
+            let mut next = end;
+            let mut overlap = 0;
+            while next > start + 1 && overlap + sizes[next - 1] <= max_overlap {
+                overlap += sizes[next - 1];
+                next -= 1;
+            }
+            start = next;
+        }
+
+        chunks
+    }
+
+    fn trim(text: &str, span: TextSpan) -> Option<TextSpan> {
+        let slice = span.slice(text);
+        let trimmed = slice.trim();
+        if trimmed.is_empty() {
+            return None;
+        }
+
+        let start = span.start + (slice.len() - slice.trim_start().len());
+        Some(TextSpan::new(start, start + trimmed.len()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STRATEGIES: [SplitStrategy; 5] = [
+        SplitStrategy::Characters,
+        SplitStrategy::Paragraphs,
+        SplitStrategy::Sentences,
+        SplitStrategy::Recursive,
+        SplitStrategy::Tokens,
+    ];
+
+    fn chunks<'t>(splitter: &TextSplitter, text: &'t str) -> Vec<&'t str> {
+        splitter
+            .split(text)
+            .unwrap()
+            .iter()
+            .map(|span| span.slice(text))
+            .collect()
+    }
+
+    fn non_whitespace(text: &str) -> String {
+        text.chars().filter(|char| !char.is_whitespace()).collect()
+    }
+
+    #[test]
+    fn multibyte_text_is_split_at_char_boundaries() {
+        let text = "日本語テキスト";
+        let splitter = TextSplitter::new(SplitStrategy::Characters, 4);
+        assert_eq!(chunks(&splitter, text), ["日本語テ", "キスト"]);
+
+        let text = "Grüße aus Köln. Ça va? Ünïcödé everywhere — 🦀🦀🦀🦀🦀🦀!";
+        for strategy in STRATEGIES {
+            let splitter = TextSplitter::new(strategy, 5).with_overlap(2);
+            for chunk in chunks(&splitter, text) {
+                assert!(splitter.size(chunk) <= 5, "{strategy}: {chunk:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn characters_overlap() {
+        let splitter = TextSplitter::new(SplitStrategy::Characters, 4).with_overlap(2);
+        assert_eq!(
+            chunks(&splitter, "abcdefghij"),
+            ["abcd", "cdef", "efgh", "ghij"]
+        );
+    }
+
+    #[test]
+    fn overlap_not_smaller_than_chunk_size_is_rejected() {
+        for overlap in [4, 5] {
+            let splitter = TextSplitter::new(SplitStrategy::Recursive, 4).with_overlap(overlap);
+            let err = splitter.split("some text").unwrap_err();
+            assert!(err.to_string().contains("must be smaller"), "{err}");
+        }
+
+        assert!(TextSplitter::new(SplitStrategy::Recursive, 0)
+            .split("some text")
+            .is_err());
+    }
+
+    #[test]
+    fn separator_longer_than_chunk() {
+        let text = "abcdefgh<<SEPARATOR>>ij";
+        let splitter = TextSplitter::new(SplitStrategy::Recursive, 5)
+            .with_separators(vec!["<<SEPARATOR>>".to_string()]);
+
+        let chunks = chunks(&splitter, text);
+        assert!(
+            chunks.iter().all(|chunk| chunk.chars().count() <= 5),
+            "{chunks:?}"
+        );
+        assert_eq!(chunks.concat(), text);
+    }
+
+    #[test]
+    fn empty_input_has_no_chunks() {
+        for strategy in STRATEGIES {
+            let splitter = TextSplitter::new(strategy, 10);
+            assert_eq!(splitter.split("").unwrap(), [], "{strategy}");
+            assert_eq!(splitter.split(" \n\n \t").unwrap(), [], "{strategy}");
+        }
+    }
+
+    #[test]
+    fn spans_map_chunks_to_source() {
+        let text = "First paragraph. It has two sentences!\n\n\
+            Second paragraph, a bit longer than the others, with ünïcödé.\n\
+            Next line?\n\n  Last one  ";
+
+        for strategy in STRATEGIES {
+            let splitter = TextSplitter::new(strategy, 12);
+            let spans = splitter.split(text).unwrap();
+
+            let mut previous_end = 0;
+            let mut joined = String::new();
+            for span in &spans {
+                assert!(
+                    span.start >= previous_end && span.start < span.end,
+                    "{strategy}"
+                );
+                let chunk = span.slice(text);
+                assert_eq!(chunk, chunk.trim(), "{strategy}");
+                assert!(splitter.size(chunk) <= 12, "{strategy}: {chunk:?}");
+
+                previous_end = span.end;
+                joined.push_str(chunk);
+            }
+
+            // without overlap the chunks cover all of the text except whitespace
+            assert_eq!(non_whitespace(&joined), non_whitespace(text), "{strategy}");
+        }
+    }
+}
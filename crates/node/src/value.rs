@@ -1,4 +1,6 @@
-use eyre::ContextCompat;
+use crate::*;
+use eyre::{ContextCompat, WrapErr};
+use serde::de::DeserializeOwned;
 use std::any::{Any, TypeId};
 
 pub trait ValueTrait: Any + Send + Sync + 'static {
@@ -58,6 +60,76 @@ impl Value {
     pub fn downcast<T: 'static>(&self) -> eyre::Result<&T> {
         self.try_downcast().context("Value type mismatch")
     }
+
+    pub fn type_name(&self) -> &'static str {
+        self.value.type_name()
+    }
+
+    /// Convert JSON into a value, strings become [`String`] and everything else is kept as
+    /// [`serde_json::Value`].
+    pub fn from_json(json: serde_json::Value) -> Self {
+        match json {
+            serde_json::Value::String(text) => Self::new(text),
+            json => Self::new(json),
+        }
+    }
+
+    /// Convert value into JSON, only plain data types, [`Conversation`] and search
+    /// results are supported.
+    #[tracing::instrument(skip_all, fields(value_type = ?self.value.type_name()))]
+    pub fn to_json(&self) -> eyre::Result<serde_json::Value> {
+        macro_rules! try_types {
+            ($($ty:ty),* $(,)?) => {
+                $(
+                    if let Some(value) = self.try_downcast::<$ty>() {
+                        return Ok(serde_json::to_value(value)?);
+                    }
+                )*
+            };
+        }
+
+        try_types!(
+            serde_json::Value,
+            String,
+            bool,
+            i64,
+            u64,
+            i32,
+            u32,
+            usize,
+            f64,
+            f32,
+            Vec<String>,
+            Vec<f32>,
+            Vec<Vec<f32>>,
+            Conversation,
+            Vec<ScoredDocument>,
+            Vec<TextSpan>,
+        );
+
+        Err(eyre::eyre!("Value is not serializable"))
+    }
+
+    /// Read the value as `T`, deserializing it if it was stored as JSON or a string.
+    #[tracing::instrument(
+        skip_all,
+        fields(value_type = ?self.value.type_name(), expected_type = ?std::any::type_name::<T>())
+    )]
+    pub fn parse<T: DeserializeOwned + Clone + 'static>(&self) -> eyre::Result<T> {
+        if let Some(value) = self.try_downcast::<T>() {
+            return Ok(value.clone());
+        }
+
+        let json = if let Some(json) = self.try_downcast::<serde_json::Value>() {
+            json.clone()
+        } else if let Some(text) = self.try_downcast::<String>() {
+            serde_json::Value::String(text.clone())
+        } else {
+            return Err(eyre::eyre!("Value type mismatch"));
+        };
+
+        serde_json::from_value(json).wrap_err("Failed to parse value")
+    }
 }
 
 #[derive(Clone, Copy, Eq, PartialEq, PartialOrd, Ord, Hash, Debug)]
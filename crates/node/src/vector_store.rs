@@ -0,0 +1,242 @@
+use eyre::WrapErr;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Document {
+    pub id: String,
+    pub text: String,
+    #[serde(default, skip_serializing_if = "serde_json::Value::is_null")]
+    pub metadata: serde_json::Value,
+}
+
+impl Document {
+    pub fn new(id: impl Into<String>, text: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            text: text.into(),
+            metadata: serde_json::Value::Null,
+        }
+    }
+
+    pub fn with_metadata(mut self, metadata: serde_json::Value) -> Self {
+        self.metadata = metadata;
+        self
+    }
+}
+
+/// Search result, higher score means more similar document.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ScoredDocument {
+    #[serde(flatten)]
+    pub document: Document,
+    pub score: f32,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct StoredDocument {
+    #[serde(flatten)]
+    document: Document,
+    embedding: Vec<f32>,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct StoreFile {
+    documents: Vec<StoredDocument>,
+}
+
+/// Cosine similarity of two vectors, zero vectors are not similar to anything.
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    let dot = a.iter().zip(b).map(|(a, b)| a * b).sum::<f32>();
+    let norm_a = a.iter().map(|a| a * a).sum::<f32>().sqrt();
+    let norm_b = b.iter().map(|b| b * b).sum::<f32>().sqrt();
+
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 0.0;
+    }
+
+    dot / (norm_a * norm_b)
+}
+
+fn check_dimension(dimension: Option<usize>, embedding: &[f32]) -> eyre::Result<()> {
+    if embedding.is_empty() {
+        return Err(eyre::eyre!("Embedding is empty"));
+    }
+
+    match dimension {
+        Some(dimension) if dimension != embedding.len() => Err(eyre::eyre!(
+            "Embedding dimension {} does not match the store dimension {dimension}",
+            embedding.len()
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// In-memory collection of embedded documents.
+///
+/// Clones share the same documents, use [`Self::deep_clone`] for a copy not seeing the upserts
+/// of the original.
+#[derive(Clone, Default)]
+pub struct VectorStore {
+    documents: Arc<RwLock<Vec<StoredDocument>>>,
+}
+
+impl std::fmt::Debug for VectorStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("VectorStore")
+            .field("len", &self.len())
+            .field("dimension", &self.dimension())
+            .finish()
+    }
+}
+
+impl VectorStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.documents.read().expect("store lock poisoned").len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Length of the stored embeddings, `None` for an empty store.
+    pub fn dimension(&self) -> Option<usize> {
+        self.documents
+            .read()
+            .expect("store lock poisoned")
+            .first()
+            .map(|stored| stored.embedding.len())
+    }
+
+    /// Copy of the store with its own documents.
+    pub fn deep_clone(&self) -> Self {
+        Self {
+            documents: Arc::new(RwLock::new(
+                self.documents.read().expect("store lock poisoned").clone(),
+            )),
+        }
+    }
+
+    /// Insert the document or replace the one with the same id.
+    pub fn upsert(&self, document: Document, embedding: Vec<f32>) -> eyre::Result<()> {
+        self.upsert_all([(document, embedding)])
+    }
+
+    /// Upsert all documents, nothing is stored if any of the embeddings has a wrong dimension.
+    pub fn upsert_all(
+        &self,
+        documents: impl IntoIterator<Item = (Document, Vec<f32>)>,
+    ) -> eyre::Result<()> {
+        let new_documents = documents.into_iter().collect::<Vec<_>>();
+        let mut documents = self.documents.write().expect("store lock poisoned");
+
+        let dimension = documents
+            .first()
+            .map(|stored| stored.embedding.len())
+            .or_else(|| new_documents.first().map(|(_, embedding)| embedding.len()));
+        for (document, embedding) in &new_documents {
+            check_dimension(dimension, embedding)
+                .wrap_err_with(|| format!("Invalid embedding of document {:?}", document.id))?;
+        }
+
+        for (document, embedding) in new_documents {
+            let stored = StoredDocument {
+                document,
+                embedding,
+            };
+
+            match documents
+                .iter_mut()
+                .find(|existing| existing.document.id == stored.document.id)
+            {
+                Some(existing) => *existing = stored,
+                None => documents.push(stored),
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn get(&self, id: &str) -> Option<Document> {
+        self.documents
+            .read()
+            .expect("store lock poisoned")
+            .iter()
+            .find(|stored| stored.document.id == id)
+            .map(|stored| stored.document.clone())
+    }
+
+    /// Remove the document, returns `false` if it was not stored.
+    pub fn remove(&self, id: &str) -> bool {
+        let mut documents = self.documents.write().expect("store lock poisoned");
+        let len = documents.len();
+        documents.retain(|stored| stored.document.id != id);
+
+        documents.len() != len
+    }
+
+    /// `top_k` documents most similar to the embedding by cosine similarity, best first.
+    pub fn search(&self, embedding: &[f32], top_k: usize) -> eyre::Result<Vec<ScoredDocument>> {
+        check_dimension(self.dimension(), embedding)?;
+
+        let mut scored = self
+            .documents
+            .read()
+            .expect("store lock poisoned")
+            .iter()
+            .map(|stored| ScoredDocument {
+                document: stored.document.clone(),
+                score: cosine_similarity(&stored.embedding, embedding),
+            })
+            .collect::<Vec<_>>();
+
+        scored.sort_by(|a, b| b.score.total_cmp(&a.score));
+        scored.truncate(top_k);
+
+        Ok(scored)
+    }
+
+    /// Write all documents with their embeddings to a JSON file.
+    #[tracing::instrument(skip(self))]
+    pub async fn save(&self, path: &Path) -> eyre::Result<()> {
+        let file = StoreFile {
+            documents: self.documents.read().expect("store lock poisoned").clone(),
+        };
+        let path = path.to_path_buf();
+
+        // large stores take a while to serialize, so it runs off the async workers
+        tokio::task::spawn_blocking(move || {
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)
+                    .wrap_err_with(|| format!("Failed to create dir {}", parent.display()))?;
+            }
+            std::fs::write(&path, serde_json::to_vec(&file)?)
+                .wrap_err_with(|| format!("Failed to write vector store {}", path.display()))
+        })
+        .await
+        .wrap_err("Vector store save panicked")?
+    }
+
+    /// Read the store written by [`Self::save`].
+    #[tracing::instrument]
+    pub async fn load(path: &Path) -> eyre::Result<Self> {
+        let path = path.to_path_buf();
+        let file = tokio::task::spawn_blocking(move || {
+            let file = std::fs::read(&path)
+                .wrap_err_with(|| format!("Failed to read vector store {}", path.display()))?;
+            serde_json::from_slice::<StoreFile>(&file)
+                .wrap_err_with(|| format!("Failed to parse vector store {}", path.display()))
+        })
+        .await
+        .wrap_err("Vector store load panicked")??;
+
+        Ok(Self {
+            documents: Arc::new(RwLock::new(file.documents)),
+        })
+    }
+}
@@ -0,0 +1,203 @@
+use node::*;
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+/// Reports the usage stored in memory, optionally after a delay, and passes `text` through.
+struct NodeSpend;
+
+impl NodeSpend {
+    const MEMORY_TOKENS: &str = "tokens";
+    const MEMORY_COST: &str = "cost";
+    const MEMORY_DELAY: &str = "delay";
+}
+
+impl NodeTrait for NodeSpend {
+    fn run<'a>(
+        &'a self,
+        instance: &'a NodeInstance,
+        ctx: &'a RunContext,
+        _input: &'a InstanceRefArgs,
+    ) -> RunResult<'a> {
+        Box::pin(async move {
+            if let Some(delay) = instance.get_memory::<Duration>(Self::MEMORY_DELAY)? {
+                tokio::time::sleep(*delay).await;
+            }
+
+            let tokens = instance.get_memory::<u64>(Self::MEMORY_TOKENS)?;
+            let cost = instance.get_memory::<f64>(Self::MEMORY_COST)?;
+            ctx.record_usage(
+                instance.instance_id,
+                TokenUsage {
+                    cost: cost.copied().unwrap_or_default(),
+                    ..TokenUsage::new(tokens.copied().unwrap_or_default(), 0)
+                },
+            );
+
+            Ok(BTreeMap::from([(
+                "text".to_string(),
+                Value::new(String::new()),
+            )]))
+        })
+    }
+}
+
+impl NodeMetaTrait for NodeSpend {
+    fn get_meta(&self) -> NodeMeta {
+        NodeMeta::new("spend", "0.1.0")
+            .with_input_arg("text", InputArgMeta::new::<String>().with_optional(true))
+            .with_output_arg("text", OutputArgMeta::new::<String>())
+    }
+}
+
+/// Chain of `spend` instances, each one configured by `configure`.
+fn chain(
+    len: usize,
+    configure: impl Fn(&mut Task, NodeInstanceId) -> eyre::Result<()>,
+) -> eyre::Result<(Task, Vec<NodeInstanceId>)> {
+    let mut task = Task::new();
+    task.register_node(NodeSpend)?;
+
+    let mut ids = Vec::new();
+    for _ in 0..len {
+        let id = task.instantiate(&"spend".into())?;
+        configure(&mut task, id)?;
+        if let Some(&previous) = ids.last() {
+            task.connect(previous, "text", id, "text")?;
+        }
+        ids.push(id);
+    }
+
+    Ok((task, ids))
+}
+
+fn status(task: &Task, id: NodeInstanceId) -> InstanceStatus {
+    task.last_report().unwrap().instances[&id].status
+}
+
+fn exceeded(err: &eyre::Report) -> &BudgetExceeded {
+    err.downcast_ref::<BudgetExceeded>()
+        .expect("run fails with BudgetExceeded")
+}
+
+#[tokio::test]
+async fn max_tokens_stops_run() -> eyre::Result<()> {
+    let (task, ids) = chain(3, |task, id| {
+        task.set_instance_memory(id, NodeSpend::MEMORY_TOKENS, 5u64)
+    })?;
+
+    let err = task
+        .run(&RunBudget::unlimited().with_max_tokens(8))
+        .await
+        .unwrap_err();
+    assert_eq!(
+        exceeded(&err),
+        &BudgetExceeded {
+            limit: BudgetLimit::Tokens { limit: 8, used: 10 },
+            instance_id: Some(ids[1]),
+        }
+    );
+    assert_eq!(status(&task, ids[2]), InstanceStatus::Skipped);
+
+    // within the budget
+    task.run(&RunBudget::unlimited().with_max_tokens(15))
+        .await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn max_tokens_crossed_by_last_instance() -> eyre::Result<()> {
+    let (task, ids) = chain(2, |task, id| {
+        task.set_instance_memory(id, NodeSpend::MEMORY_TOKENS, 5u64)
+    })?;
+
+    let err = task
+        .run(&RunBudget::unlimited().with_max_tokens(8))
+        .await
+        .unwrap_err();
+    assert_eq!(exceeded(&err).instance_id, Some(ids[1]));
+    assert!(task.last_report().unwrap().error.is_some());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn max_cost_stops_run() -> eyre::Result<()> {
+    let (task, ids) = chain(3, |task, id| {
+        task.set_instance_memory(id, NodeSpend::MEMORY_COST, 0.5f64)
+    })?;
+
+    let err = task
+        .run(&RunBudget::unlimited().with_max_cost(0.75))
+        .await
+        .unwrap_err();
+    assert_eq!(
+        exceeded(&err),
+        &BudgetExceeded {
+            limit: BudgetLimit::Cost {
+                limit: 0.75,
+                used: 1.0
+            },
+            instance_id: Some(ids[1]),
+        }
+    );
+    assert_eq!(status(&task, ids[2]), InstanceStatus::Skipped);
+
+    // the last instance crosses the limit
+    let err = task
+        .run(&RunBudget::unlimited().with_max_cost(1.25))
+        .await
+        .unwrap_err();
+    assert_eq!(exceeded(&err).instance_id, Some(ids[2]));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn max_node_executions_stops_run() -> eyre::Result<()> {
+    let (task, ids) = chain(3, |_, _| Ok(()))?;
+
+    let err = task
+        .run(&RunBudget::unlimited().with_max_node_executions(2))
+        .await
+        .unwrap_err();
+    assert_eq!(
+        exceeded(&err),
+        &BudgetExceeded {
+            limit: BudgetLimit::NodeExecutions { limit: 2 },
+            instance_id: Some(ids[2]),
+        }
+    );
+    assert_eq!(status(&task, ids[1]), InstanceStatus::Succeeded);
+    assert_eq!(status(&task, ids[2]), InstanceStatus::Skipped);
+
+    task.run(&RunBudget::unlimited().with_max_node_executions(3))
+        .await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn max_duration_cancels_running_instance() -> eyre::Result<()> {
+    let (task, ids) = chain(2, |task, id| {
+        task.set_instance_memory(id, NodeSpend::MEMORY_DELAY, Duration::from_secs(10))
+    })?;
+
+    let err = task
+        .run(&RunBudget::unlimited().with_max_duration(Duration::from_millis(50)))
+        .await
+        .unwrap_err();
+    assert_eq!(
+        exceeded(&err),
+        &BudgetExceeded {
+            limit: BudgetLimit::Duration {
+                limit: Duration::from_millis(50)
+            },
+            instance_id: Some(ids[0]),
+        }
+    );
+    assert_eq!(status(&task, ids[0]), InstanceStatus::Cancelled);
+    assert_eq!(status(&task, ids[1]), InstanceStatus::Skipped);
+
+    Ok(())
+}
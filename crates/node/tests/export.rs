@@ -0,0 +1,37 @@
+use node::*;
+use serde_json::json;
+
+#[test]
+fn dot_port_ids_are_sanitized() -> eyre::Result<()> {
+    let name = r#"a "b"|<c>"#;
+    let id = "a_20__22_b_22__7c__3c_c_3e_";
+
+    let mut task = Task::new();
+    task.register_built_in_nodes()?;
+    let query = task.instantiate(&"query_json".into())?;
+    let build = task.instantiate(&"build_json".into())?;
+    task.set_instance_memory_value(
+        query,
+        NodeQueryJson::MEMORY_QUERIES,
+        Value::from_json(json!({ name: "$.a" })),
+    )?;
+    task.set_instance_memory_value(
+        build,
+        NodeBuildJson::MEMORY_FIELDS,
+        Value::from_json(json!([name])),
+    )?;
+    task.connect(query, name, build, name)?;
+
+    let dot = task.to_dot()?;
+    // the record field ids and the edge endpoints use the same id
+    assert!(dot.contains(&format!("{{<out_{id}> ")), "{dot}");
+    assert!(dot.contains(&format!("{{<in_{id}> ")), "{dot}");
+    assert!(
+        dot.contains(&format!(r#""{query}":"out_{id}" -> "{build}":"in_{id}";"#)),
+        "{dot}"
+    );
+    // the label still shows the name
+    assert!(dot.contains(r#"a \"b\"\|\<c\>: Value"#), "{dot}");
+
+    Ok(())
+}
@@ -0,0 +1,190 @@
+use node::*;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+/// Keeps the last received text.
+#[derive(Clone, Default)]
+struct NodeCapture(Arc<Mutex<Option<String>>>);
+
+impl NodeTrait for NodeCapture {
+    fn run<'a>(
+        &'a self,
+        _instance: &'a NodeInstance,
+        _ctx: &'a RunContext,
+        input: &'a InstanceRefArgs,
+    ) -> RunResult<'a> {
+        Box::pin(async {
+            *self.0.lock().unwrap() = Some(input["value"].downcast::<String>()?.clone());
+
+            Ok(BTreeMap::new())
+        })
+    }
+}
+
+impl NodeMetaTrait for NodeCapture {
+    fn get_meta(&self) -> NodeMeta {
+        NodeMeta::new("capture", "0.1.0").with_input_arg("value", InputArgMeta::new::<String>())
+    }
+}
+
+#[tokio::test]
+async fn input_reads_connected_output_by_its_name() -> eyre::Result<()> {
+    let capture = NodeCapture::default();
+    let mut task = Task::new();
+    task.register_built_in_nodes()?;
+    task.register_node(capture.clone())?;
+
+    let text = task.instantiate(&"text".into())?;
+    let template = task.instantiate(&"template".into())?;
+    let captured = task.instantiate(&"capture".into())?;
+    task.set_instance_memory(text, NodeText::MEMORY_TEXT, "world".to_string())?;
+    task.set_instance_memory(
+        template,
+        NodeTemplate::MEMORY_TEMPLATE,
+        "Hello {{name}}!".to_string(),
+    )?;
+
+    // the names of the output and the input differ on both connections
+    task.connect(text, NodeText::OUT_ARG_TEXT, template, "name")?;
+    task.connect(template, NodeTemplate::OUT_ARG_TEXT, captured, "value")?;
+
+    task.run(&RunBudget::unlimited()).await?;
+    assert_eq!(capture.0.lock().unwrap().as_deref(), Some("Hello world!"));
+
+    Ok(())
+}
+
+/// `text` feeding the `name` placeholder of two templates.
+fn fan_out() -> eyre::Result<(Task, [NodeInstanceId; 3])> {
+    let mut task = Task::new();
+    task.register_built_in_nodes()?;
+
+    let text = task.instantiate(&"text".into())?;
+    task.set_instance_memory(text, NodeText::MEMORY_TEXT, "world".to_string())?;
+
+    let mut templates = Vec::new();
+    for template in ["Hello {{name}}!", "Bye {{name}}"] {
+        let id = task.instantiate(&"template".into())?;
+        task.set_instance_memory(id, NodeTemplate::MEMORY_TEMPLATE, template.to_string())?;
+        task.connect(text, NodeText::OUT_ARG_TEXT, id, "name")?;
+        templates.push(id);
+    }
+
+    Ok((task, [text, templates[0], templates[1]]))
+}
+
+fn output_targets(task: &Task, id: NodeInstanceId) -> eyre::Result<Vec<(NodeInstanceId, String)>> {
+    Ok(task
+        .get_node_out_connections(id, NodeText::OUT_ARG_TEXT)?
+        .iter()
+        .map(|conn| (conn.instance, conn.arg_name.clone()))
+        .collect())
+}
+
+#[tokio::test]
+async fn output_feeds_many_inputs() -> eyre::Result<()> {
+    let (mut task, [text, hello, bye]) = fan_out()?;
+
+    assert_eq!(
+        output_targets(&task, text)?,
+        [(hello, "name".to_string()), (bye, "name".to_string())]
+    );
+    assert!(task.is_nodes_connected(text, NodeText::OUT_ARG_TEXT, hello, "name")?);
+    assert!(task.is_nodes_connected(text, NodeText::OUT_ARG_TEXT, bye, "name")?);
+
+    // only the connection of the disconnected input is removed
+    task.disconnect(hello, "name")?;
+    assert_eq!(output_targets(&task, text)?, [(bye, "name".to_string())]);
+    assert!(!task.is_nodes_connected(text, NodeText::OUT_ARG_TEXT, hello, "name")?);
+    assert!(task.is_nodes_connected(text, NodeText::OUT_ARG_TEXT, bye, "name")?);
+
+    task.disconnect(bye, "name")?;
+    assert!(task.get_instance(text)?.is_leaf());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn graph_round_trip() -> eyre::Result<()> {
+    let (task, [_, hello, _]) = fan_out()?;
+    let graph = task.to_graph()?;
+    assert_eq!(graph.connections.len(), 2);
+    assert_eq!(
+        graph.instances[1].ports.inputs.keys().collect::<Vec<_>>(),
+        ["name"]
+    );
+
+    let json = serde_json::to_value(&graph)?;
+    let mut loaded = Task::new();
+    loaded.register_built_in_nodes()?;
+    loaded.load_graph(&serde_json::from_value(json.clone())?)?;
+
+    assert_eq!(serde_json::to_value(loaded.to_graph()?)?, json);
+    assert_eq!(
+        loaded
+            .get_instance(hello)?
+            .get_memory::<String>(NodeTemplate::MEMORY_TEMPLATE)?
+            .map(String::as_str),
+        Some("Hello {{name}}!")
+    );
+    loaded.validate()?;
+    loaded.run(&RunBudget::unlimited()).await?;
+
+    Ok(())
+}
+
+#[test]
+fn load_graph_rejects_unknown_ports() -> eyre::Result<()> {
+    let (task, _) = fan_out()?;
+    let mut graph = task.to_graph()?;
+    graph.connections[0].to.arg = "missing".to_string();
+
+    let mut loaded = Task::new();
+    loaded.register_built_in_nodes()?;
+    let err = loaded.load_graph(&graph).unwrap_err();
+    assert!(format!("{err:#}").contains("Failed to connect"), "{err:#}");
+
+    Ok(())
+}
+
+#[test]
+fn changed_ports_drop_stale_connections() -> eyre::Result<()> {
+    let (mut task, [text, hello, bye]) = fan_out()?;
+
+    // the `name` placeholder is gone, so is its connection on both sides
+    task.set_instance_memory(
+        hello,
+        NodeTemplate::MEMORY_TEMPLATE,
+        "Hello {{other}}!".to_string(),
+    )?;
+    assert!(task.get_instance(hello)?.input_connections.is_empty());
+    assert_eq!(output_targets(&task, text)?, [(bye, "name".to_string())]);
+
+    // ports not affected by the change keep their connections
+    task.set_instance_memory(
+        bye,
+        NodeTemplate::MEMORY_TEMPLATE,
+        "Bye {{name}} and {{other}}".to_string(),
+    )?;
+    assert!(task.is_nodes_connected(text, NodeText::OUT_ARG_TEXT, bye, "name")?);
+
+    Ok(())
+}
+
+#[test]
+fn validate_reports_unconnected_inputs() -> eyre::Result<()> {
+    let (mut task, [text, hello, _]) = fan_out()?;
+    task.validate()?;
+
+    task.disconnect(hello, "name")?;
+    let err = task.validate().unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        format!("Invalid task:\n{hello}[\"name\"]: required input is not connected")
+    );
+
+    task.connect(text, NodeText::OUT_ARG_TEXT, hello, "name")?;
+    task.validate()?;
+
+    Ok(())
+}
@@ -0,0 +1,128 @@
+use node::*;
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+/// Keeps the last received text.
+#[derive(Clone, Default)]
+struct NodeCapture(Arc<Mutex<Option<String>>>);
+
+impl NodeTrait for NodeCapture {
+    fn run<'a>(
+        &'a self,
+        _instance: &'a NodeInstance,
+        _ctx: &'a RunContext,
+        input: &'a InstanceRefArgs,
+    ) -> RunResult<'a> {
+        Box::pin(async {
+            *self.0.lock().unwrap() = Some(input["text"].downcast::<String>()?.clone());
+
+            Ok(BTreeMap::new())
+        })
+    }
+}
+
+impl NodeMetaTrait for NodeCapture {
+    fn get_meta(&self) -> NodeMeta {
+        NodeMeta::new("capture", "0.1.0").with_input_arg("text", InputArgMeta::new::<String>())
+    }
+}
+
+/// `text -> parse_json -> query_json -> build_json -> stringify_json -> capture`
+fn graph(text: &str, queries: serde_json::Value) -> eyre::Result<(Task, NodeCapture)> {
+    let capture = NodeCapture::default();
+    let mut task = Task::new();
+    task.register_built_in_nodes()?;
+    task.register_node(capture.clone())?;
+
+    let text_id = task.instantiate(&"text".into())?;
+    let parse = task.instantiate(&"parse_json".into())?;
+    let query = task.instantiate(&"query_json".into())?;
+    let build = task.instantiate(&"build_json".into())?;
+    let stringify = task.instantiate(&"stringify_json".into())?;
+    let captured = task.instantiate(&"capture".into())?;
+
+    task.set_instance_memory(text_id, NodeText::MEMORY_TEXT, text.to_string())?;
+    task.set_instance_memory_value(
+        query,
+        NodeQueryJson::MEMORY_QUERIES,
+        Value::from_json(queries.clone()),
+    )?;
+    let fields = queries
+        .as_object()
+        .unwrap()
+        .keys()
+        .cloned()
+        .collect::<Vec<_>>();
+    task.set_instance_memory_value(
+        build,
+        NodeBuildJson::MEMORY_FIELDS,
+        Value::from_json(json!(fields)),
+    )?;
+
+    task.connect(
+        text_id,
+        NodeText::OUT_ARG_TEXT,
+        parse,
+        NodeParseJson::INPUT_ARG_TEXT,
+    )?;
+    task.connect(
+        parse,
+        NodeParseJson::OUT_ARG_JSON,
+        query,
+        NodeQueryJson::INPUT_ARG_JSON,
+    )?;
+    for field in &fields {
+        task.connect(query, field, build, field)?;
+    }
+    task.connect(
+        build,
+        NodeBuildJson::OUT_ARG_JSON,
+        stringify,
+        NodeStringifyJson::INPUT_ARG_JSON,
+    )?;
+    task.connect(stringify, NodeStringifyJson::OUT_ARG_TEXT, captured, "text")?;
+
+    Ok((task, capture))
+}
+
+#[tokio::test]
+async fn json_nodes_graph() -> eyre::Result<()> {
+    let text = r#"```json
+{"user": {"name": "Ann", "tags": ["a", "b", "c"]}}
+```"#;
+    let (task, capture) = graph(
+        text,
+        json!({
+            "name": "$.user.name",
+            "last_tag": "$.user.tags[-1]",
+            "tags": "$.user.tags[*]",
+        }),
+    )?;
+
+    task.run(&RunBudget::unlimited()).await?;
+    let output = capture.0.lock().unwrap().clone().unwrap();
+    assert_eq!(
+        serde_json::from_str::<serde_json::Value>(&output)?,
+        json!({ "name": "Ann", "last_tag": "c", "tags": ["a", "b", "c"] })
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn json_nodes_graph_missing_key() -> eyre::Result<()> {
+    let (task, capture) = graph(
+        r#"{"user": {"name": "Ann"}}"#,
+        json!({ "email": "$.user.email" }),
+    )?;
+
+    let err = task.run(&RunBudget::unlimited()).await.unwrap_err();
+    assert!(
+        format!("{err:#}").contains(r#"Key "email" not found at $.user"#),
+        "{err:#}"
+    );
+    assert!(capture.0.lock().unwrap().is_none());
+
+    Ok(())
+}